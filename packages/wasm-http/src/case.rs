@@ -0,0 +1,184 @@
+/*!
+    `JSON` `body` 及 `query` 参数中 `key` 的命名风格转换, 如 `snake_case` 与 `camelCase` 互转。
+    - 连续的大写字母视为一个单词, 如 `userID` => `user_id`、`HTTPServer` => `http_server`
+    - 开头和结尾的 `_`/`-` 原样保留, 如 `_id`、`__typename`
+    - 转换后的 `key` 与对象中已有的 `key` 冲突时, 保留原始的 `key`, 如 `{"user_id": 1, "userId": 2}`
+    - `skip` 中不包含 `.` 的为 `key`, 任意位置的同名 `key` 及其值都不转换; 包含 `.` 的为从根开始的路径(使用原始的 `key`, 忽略数组下标)
+*/
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// 命名风格
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    Camel,  // userId
+    Snake,  // user_id
+    Kebab,  // user-id
+    Pascal, // UserId
+}
+
+impl Case {
+    pub fn get_case(case: String) -> Option<Case> {
+        match case.trim().to_lowercase().as_str() {
+            "camel" => Some(Case::Camel),
+            "snake" => Some(Case::Snake),
+            "kebab" => Some(Case::Kebab),
+            "pascal" => Some(Case::Pascal),
+            _ => None,
+        }
+    }
+
+    /// 转换一个 `key`
+    pub fn convert(&self, key: &str) -> String {
+        let words = split_words(key);
+        if words.is_empty() {
+            return key.to_string();
+        }
+
+        let converted: String = match self {
+            Case::Snake => words.join("_"),
+            Case::Kebab => words.join("-"),
+            Case::Camel => words.iter().enumerate().map(|(index, word)| if index == 0 { word.clone() } else { capitalize(word) }).collect(),
+            Case::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+        };
+
+        // 保留开头和结尾的分隔符
+        let is_separator = |c: char| c == '_' || c == '-';
+        let body = key.trim_start_matches(is_separator);
+        let prefix = &key[..key.len() - body.len()];
+        let suffix = &body[body.trim_end_matches(is_separator).len()..];
+        format!("{}{}{}", prefix, converted, suffix)
+    }
+}
+
+/// 拆分成小写的单词, 数字跟随前面的单词
+pub fn split_words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for (index, c) in chars.iter().enumerate() {
+        if *c == '_' || *c == '-' || c.is_whitespace() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[index - 1];
+            let next_lower = chars.get(index + 1).map(|next| next.is_lowercase()).unwrap_or(false);
+            // `userID` 在 `I` 处拆分, `HTTPServer` 在 `S` 处拆分
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+
+        current.extend(c.to_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// 递归转换对象的 `key`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyTransform {
+    pub case: Case,
+    pub skip: Vec<String>, // 不转换的 `key` 或路径
+}
+
+impl KeyTransform {
+    pub fn new(case: Case, skip: Vec<String>) -> Self {
+        Self { case, skip }
+    }
+
+    pub fn transform(&self, value: Value) -> Value {
+        self.transform_value(value, "")
+    }
+
+    fn transform_value(&self, value: Value, path: &str) -> Value {
+        match value {
+            Value::Object(map) => {
+                let keys: HashSet<String> = map.keys().cloned().collect();
+                let mut result = Map::new();
+                for (key, value) in map {
+                    let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                    if self.is_skipped(&key, &key_path) {
+                        result.insert(key, value);
+                        continue;
+                    }
+
+                    let value = self.transform_value(value, &key_path);
+                    let converted = self.case.convert(&key);
+                    // 与已有的 `key` 冲突时保留原始的 `key`
+                    if converted != key && (keys.contains(&converted) || result.contains_key(&converted)) {
+                        result.insert(key, value);
+                    } else {
+                        result.insert(converted, value);
+                    }
+                }
+
+                Value::Object(result)
+            }
+            Value::Array(values) => Value::Array(values.into_iter().map(|value| self.transform_value(value, path)).collect()),
+            value => value,
+        }
+    }
+
+    fn is_skipped(&self, key: &str, path: &str) -> bool {
+        self.skip.iter().any(|skip| if skip.contains('.') { skip == path } else { skip == key })
+    }
+
+    /// 转换 `url` 中 `query` 参数的名称
+    pub fn transform_query(&self, url: &str) -> String {
+        let (url, hash) = match url.split_once('#') {
+            Some((url, hash)) => (url, Some(hash)),
+            None => (url, None),
+        };
+
+        let mut result = match url.split_once('?') {
+            Some((base, query)) => {
+                let query: Vec<String> = query
+                    .split('&')
+                    .map(|pair| {
+                        let (name, value) = match pair.split_once('=') {
+                            Some((name, value)) => (name, Some(value)),
+                            None => (pair, None),
+                        };
+
+                        let name = if name.is_empty() || self.is_skipped(name, name) { name.to_string() } else { self.case.convert(name) };
+                        match value {
+                            Some(value) => format!("{}={}", name, value),
+                            None => name,
+                        }
+                    })
+                    .collect();
+                format!("{}?{}", base, query.join("&"))
+            }
+            None => url.to_string(),
+        };
+
+        if let Some(hash) = hash {
+            result.push('#');
+            result.push_str(hash);
+        }
+
+        result
+    }
+}
@@ -0,0 +1,78 @@
+//! 熔断器的 `tower` `Layer`, 未启用时直接调用内部的服务
+
+use crate::circuit;
+use crate::error::Error;
+use crate::response::timing;
+use crate::{HttpRequestOptions, HttpResponseOptions};
+use http::{Request, Response};
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::Layer;
+use tower_service::Service;
+
+#[derive(Debug, Clone)]
+pub(crate) struct CircuitLayer {
+    key: Option<String>, // 未启用时为 `None`
+}
+
+impl CircuitLayer {
+    pub(crate) fn new(options: &HttpRequestOptions) -> Self {
+        Self {
+            key: circuit::key(&options.url, options.circuit_key.as_deref()),
+        }
+    }
+}
+
+impl<S> Layer<S> for CircuitLayer {
+    type Service = CircuitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CircuitService { inner, layer: self.clone() }
+    }
+}
+
+pub(crate) struct CircuitService<S> {
+    inner: S,
+    layer: CircuitLayer,
+}
+
+impl<S> Service<Request<Value>> for CircuitService<S>
+where
+    S: Service<Request<Value>, Response = Response<HttpResponseOptions>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = Response<HttpResponseOptions>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Value>) -> Self::Future {
+        let key = match self.layer.key.clone() {
+            Some(key) => key,
+            None => return Box::pin(self.inner.call(request)),
+        };
+
+        // 熔断时不发送请求
+        if let Err(retry_after) = circuit::acquire(&key, timing::now()) {
+            return Box::pin(async move { Err(Error::CircuitOpen { key, retry_after }) });
+        }
+
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let result = future.await;
+            match &result {
+                Ok(response) => circuit::report(&key, response.body().status_code >= 500, timing::now()),
+                Err(err) if err.is_network_error() => circuit::report(&key, true, timing::now()),
+                // 主动取消、响应校验失败等不能说明服务异常
+                Err(_) => circuit::release(&key),
+            }
+
+            result
+        })
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+    熔断器, 调用 `setCircuitBreaker` 后生效, 默认按 `host` 分组, 也可以按路由模板或请求的 `circuitKey` 分组。
+    - `closed`: 正常发送请求, 连续失败次数达到 `failureThreshold`, 或最近 `windowSize` 个请求的失败率达到 `failureRate` 时进入 `open`
+    - `open`: 不发送请求, 直接返回 `Error::CircuitOpen`, 经过 `coolDown` 毫秒后进入 `half-open`
+    - `half-open`: 只放行 `halfOpenRequests` 个探测请求, 全部成功后进入 `closed`, 任意一个失败则重新进入 `open`
+
+    网络错误、超时或状态码为 `5xx` 视为失败, 主动取消、响应校验失败等不计入统计, 状态变化时回调 `onStateChange({key, from, to})`。
+*/
+
+pub(crate) mod layer;
+
+use crate::metrics::route_template;
+use js_sys::{Array, Function, Object};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static BREAKER: RefCell<Option<(CircuitBreaker, Option<Function>)>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CircuitState {
+    #[default]
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// 分组方式
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CircuitKey {
+    /// 协议、域名及端口
+    #[default]
+    Host,
+
+    /// `host` 加上路由模板, 模板规则与 `metrics` 相同
+    Route(Vec<String>),
+}
+
+impl CircuitKey {
+    /// 请求的分组
+    pub fn key(&self, url: &str) -> String {
+        let host = match url.split_once("://") {
+            Some((scheme, rest)) => format!("{}://{}", scheme.to_lowercase(), rest.split(['/', '?', '#']).next().unwrap_or_default().to_lowercase()),
+            None => String::from("same-origin"), // 相对地址
+        };
+
+        match self {
+            CircuitKey::Host => host,
+            CircuitKey::Route(routes) => format!("{}{}", host, route_template(url, routes)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitOptions {
+    pub failure_threshold: u32,  // 连续失败次数
+    pub failure_rate: f64,       // 失败率, `0-1`
+    pub minimum_requests: usize, // 计算失败率的最少请求数
+    pub window_size: usize,      // 计算失败率的请求数
+    pub cool_down: f64,          // `open` 持续的时间(毫秒)
+    pub half_open_requests: u32, // `half-open` 时放行的探测请求数
+    pub key: CircuitKey,
+}
+
+impl Default for CircuitOptions {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            failure_rate: 0.5,
+            minimum_requests: 10,
+            window_size: 20,
+            cool_down: 30000.0,
+            half_open_requests: 1,
+            key: CircuitKey::default(),
+        }
+    }
+}
+
+/// 状态变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Transition {
+    pub key: String,
+    pub from: CircuitState,
+    pub to: CircuitState,
+}
+
+/// 一个分组的熔断器
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    state: CircuitState,
+    opened_at: f64,
+    consecutive_failures: u32,
+    results: VecDeque<bool>, // 最近的请求是否失败
+    probes: u32,             // `half-open` 时已放行的探测请求
+    probe_successes: u32,
+}
+
+impl Circuit {
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    /// 发送请求前调用, `open` 时返回剩余的冷却时间, `half-open` 时探测请求还未结束则返回 `coolDown`
+    pub fn acquire(&mut self, options: &CircuitOptions, now: f64) -> (Result<(), f64>, Option<(CircuitState, CircuitState)>) {
+        let mut transition = None;
+        if self.state == CircuitState::Open {
+            let remaining = self.opened_at + options.cool_down - now;
+            if remaining > 0.0 {
+                return (Err(remaining), None);
+            }
+
+            transition = self.set_state(CircuitState::HalfOpen);
+        }
+
+        if self.state == CircuitState::HalfOpen {
+            if self.probes >= options.half_open_requests.max(1) {
+                return (Err(options.cool_down.max(1.0)), transition);
+            }
+
+            self.probes += 1;
+        }
+
+        (Ok(()), transition)
+    }
+
+    /// 请求结束后调用
+    pub fn report(&mut self, options: &CircuitOptions, failed: bool, now: f64) -> Option<(CircuitState, CircuitState)> {
+        match self.state {
+            // 熔断前发出的请求, 忽略
+            CircuitState::Open => None,
+            CircuitState::HalfOpen => {
+                if failed {
+                    return self.open(now);
+                }
+
+                self.probe_successes += 1;
+                if self.probe_successes >= options.half_open_requests.max(1) {
+                    return self.set_state(CircuitState::Closed);
+                }
+
+                None
+            }
+            CircuitState::Closed => {
+                self.consecutive_failures = if failed { self.consecutive_failures + 1 } else { 0 };
+                self.results.push_back(failed);
+                while self.results.len() > options.window_size.max(1) {
+                    self.results.pop_front();
+                }
+
+                let failures = self.results.iter().filter(|failed| **failed).count();
+                let rate_exceeded = self.results.len() >= options.minimum_requests.max(1) && failures as f64 / self.results.len() as f64 >= options.failure_rate;
+                if (options.failure_threshold > 0 && self.consecutive_failures >= options.failure_threshold) || rate_exceeded {
+                    return self.open(now);
+                }
+
+                None
+            }
+        }
+    }
+
+    /// 请求结束但不计入统计时调用, 归还 `half-open` 时占用的探测名额
+    pub fn release(&mut self) {
+        if self.state == CircuitState::HalfOpen {
+            self.probes = self.probes.saturating_sub(1);
+        }
+    }
+
+    fn open(&mut self, now: f64) -> Option<(CircuitState, CircuitState)> {
+        self.opened_at = now;
+        self.set_state(CircuitState::Open)
+    }
+
+    /// 切换状态并重置统计
+    fn set_state(&mut self, state: CircuitState) -> Option<(CircuitState, CircuitState)> {
+        let from = self.state;
+        self.state = state;
+        self.consecutive_failures = 0;
+        self.results.clear();
+        self.probes = 0;
+        self.probe_successes = 0;
+        if from == state {
+            None
+        } else {
+            Some((from, state))
+        }
+    }
+}
+
+/// 按分组管理熔断器
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker {
+    options: CircuitOptions,
+    circuits: BTreeMap<String, Circuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(options: CircuitOptions) -> Self {
+        Self { options, circuits: BTreeMap::new() }
+    }
+
+    pub fn options(&self) -> &CircuitOptions {
+        &self.options
+    }
+
+    pub fn state(&self, key: &str) -> CircuitState {
+        self.circuits.get(key).map(|circuit| circuit.state()).unwrap_or_default()
+    }
+
+    pub fn acquire(&mut self, key: &str, now: f64) -> (Result<(), f64>, Option<Transition>) {
+        let circuit = self.circuits.entry(key.to_string()).or_default();
+        let (result, transition) = circuit.acquire(&self.options, now);
+        (result, Self::transition(key, transition))
+    }
+
+    pub fn report(&mut self, key: &str, failed: bool, now: f64) -> Option<Transition> {
+        let circuit = self.circuits.entry(key.to_string()).or_default();
+        let transition = circuit.report(&self.options, failed, now);
+        Self::transition(key, transition)
+    }
+
+    pub fn release(&mut self, key: &str) {
+        if let Some(circuit) = self.circuits.get_mut(key) {
+            circuit.release();
+        }
+    }
+
+    /// 所有分组的状态
+    pub fn states(&self) -> BTreeMap<String, CircuitState> {
+        self.circuits.iter().map(|(key, circuit)| (key.clone(), circuit.state())).collect()
+    }
+
+    fn transition(key: &str, transition: Option<(CircuitState, CircuitState)>) -> Option<Transition> {
+        transition.map(|(from, to)| Transition { key: key.to_string(), from, to })
+    }
+}
+
+/// 熔断器未启用时返回 `None`, 否则返回请求的分组
+pub(crate) fn key(url: &str, circuit_key: Option<&str>) -> Option<String> {
+    BREAKER.with(|breaker| breaker.borrow().as_ref().map(|(breaker, _)| circuit_key.map(String::from).unwrap_or_else(|| breaker.options().key.key(url))))
+}
+
+pub(crate) fn acquire(key: &str, now: f64) -> Result<(), f64> {
+    let (result, transition) = BREAKER.with(|breaker| match breaker.borrow_mut().as_mut() {
+        Some((breaker, callback)) => {
+            let (result, transition) = breaker.acquire(key, now);
+            (result, transition.zip(callback.clone()))
+        }
+        None => (Ok(()), None),
+    });
+
+    emit(transition);
+    result
+}
+
+pub(crate) fn report(key: &str, failed: bool, now: f64) {
+    let transition = BREAKER.with(|breaker| match breaker.borrow_mut().as_mut() {
+        Some((breaker, callback)) => breaker.report(key, failed, now).zip(callback.clone()),
+        None => None,
+    });
+
+    emit(transition);
+}
+
+pub(crate) fn release(key: &str) {
+    BREAKER.with(|breaker| {
+        if let Some((breaker, _)) = breaker.borrow_mut().as_mut() {
+            breaker.release(key);
+        }
+    });
+}
+
+/// 释放 `borrow` 后再回调, 回调的异常被忽略
+fn emit(transition: Option<(Transition, Function)>) {
+    if let Some((transition, callback)) = transition {
+        if let Ok(event) = transition.serialize(&serde_wasm_bindgen::Serializer::json_compatible()) {
+            let _ = callback.call1(&JsValue::NULL, &event);
+        }
+    }
+}
+
+/**
+  启用熔断器, 为 `null` 时停用, 重新设置时清空所有分组的状态
+  `options` 中可以设置 `failureThreshold`(默认为 `5`)、`failureRate`(默认为 `0.5`)、`minimumRequests`(默认为 `10`)、`windowSize`(默认为 `20`)、
+  `coolDown`(默认为 `30000` 毫秒)、`halfOpenRequests`(默认为 `1`)、`key`(`host` 或 `route`, 默认为 `host`)、`routes`(`key` 为 `route` 时的路由模板)及 `onStateChange`
+*/
+#[wasm_bindgen(js_name = setCircuitBreaker)]
+pub fn set_circuit_breaker(options: JsValue) -> Result<(), JsValue> {
+    if options.is_null() || options.is_undefined() {
+        BREAKER.with(|breaker| *breaker.borrow_mut() = None);
+        return Ok(());
+    }
+
+    let obj = options.dyn_ref::<Object>().ok_or_else(|| JsValue::from_str(&crate::Error::Error("`options` is not a object !".to_string()).to_string()))?;
+    let number = |name: &str| crate::send::HttpClient::get_value(obj, name).and_then(|value| value.as_f64()).map(|value| value.max(0.0));
+
+    let mut circuit_options = CircuitOptions::default();
+    if let Some(value) = number("failureThreshold") {
+        circuit_options.failure_threshold = value as u32;
+    }
+
+    if let Some(value) = number("failureRate") {
+        circuit_options.failure_rate = value.min(1.0);
+    }
+
+    if let Some(value) = number("minimumRequests") {
+        circuit_options.minimum_requests = value as usize;
+    }
+
+    if let Some(value) = number("windowSize") {
+        circuit_options.window_size = value as usize;
+    }
+
+    if let Some(value) = number("coolDown") {
+        circuit_options.cool_down = value;
+    }
+
+    if let Some(value) = number("halfOpenRequests") {
+        circuit_options.half_open_requests = value as u32;
+    }
+
+    let key = crate::send::HttpClient::get_value(obj, "key").and_then(|key| key.as_string()).unwrap_or_default();
+    if key.trim().eq_ignore_ascii_case("route") {
+        let routes = crate::send::HttpClient::get_value(obj, "routes")
+            .map(|routes| Array::from(&routes).iter().filter_map(|route| route.as_string()).collect())
+            .unwrap_or_default();
+        circuit_options.key = CircuitKey::Route(routes);
+    }
+
+    let callback = crate::send::HttpClient::get_value(obj, "onStateChange").and_then(|callback| callback.dyn_into::<Function>().ok());
+    BREAKER.with(|breaker| *breaker.borrow_mut() = Some((CircuitBreaker::new(circuit_options), callback)));
+    Ok(())
+}
+
+/**
+  获取所有分组的状态, 如 `{"https://api.example.org": "open"}`
+*/
+#[wasm_bindgen(js_name = getCircuitStates)]
+pub fn get_circuit_states() -> Result<JsValue, JsValue> {
+    let states = BREAKER.with(|breaker| breaker.borrow().as_ref().map(|(breaker, _)| breaker.states()).unwrap_or_default());
+    states.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| JsValue::from_str(&err.to_string()))
+}
@@ -0,0 +1,107 @@
+/*!
+    `beacon` 模式, 用于页面卸载时的统计上报, 只发送请求, 不等待响应。
+    - 支持 `keepalive` 且 `body` 不超过 `64KB` 时, 使用 `keepalive` 的 `fetch`, 页面卸载后浏览器会继续发送
+    - 否则 `POST` 请求使用 `navigator.sendBeacon`(只能发送 `POST`, 不能设置 `headers`), 超出浏览器的配额时返回 `false`
+    - `compressRequest` 使用 `Rust` 实现同步压缩字符串 `body`(需要 `rust-compression` feature), 压缩后需要 `Content-Encoding`, 不使用 `sendBeacon`; 不支持压缩 `Blob`
+    - 都不可用时使用普通的 `fetch`, 页面卸载时可能被取消
+    - 不支持 `ReadableStream` 作为 `body`
+*/
+
+use crate::client::call::Call;
+use crate::client::fetch;
+use crate::error::Error;
+use crate::request::HttpRequest;
+use crate::request::{compression, stream};
+use crate::HttpRequestOptions;
+use http::header::CONTENT_ENCODING;
+use js_sys::{Function, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Blob;
+
+/// `keepalive` 请求及 `sendBeacon` 的 `body` 上限(字节)
+pub const LIMIT: f64 = 64.0 * 1024.0;
+
+/// 发送请求, 返回请求是否会在页面卸载后继续发送(`keepalive` 或 `sendBeacon`)
+pub(crate) fn send(options: &HttpRequestOptions, request: HttpRequest) -> Result<bool, Error> {
+    let headers = Call::prepare_headers(options)?;
+    let (method, is_method_get) = Call::prepare_method(options);
+    let mut data = match Call::source_body(options, is_method_get) {
+        Some(body) => JsValue::from(body),
+        None => Call::prepare_body(options)?,
+    };
+    if stream::is_stream(&data) {
+        return Err(Error::Error("`beacon` does not support stream body !".to_string()));
+    }
+
+    // 页面卸载时不能等待 `CompressionStream`, 同步压缩
+    let mut compressed = false;
+    if let Some(compression) = options.compress_request.filter(|_| !is_method_get) {
+        if let Some(body) = compression::compress_sync(&data, compression, options.compress_threshold.unwrap_or(compression::THRESHOLD))? {
+            headers.set(CONTENT_ENCODING.as_str(), compression.encoding()).map_err(Error::js_error)?;
+            data = body;
+            compressed = true;
+        }
+    }
+
+    let size = body_size(&data);
+    let js_request = Call::prepare_js_request(options, headers, method, is_method_get, data.clone())?;
+
+    // keepalive
+    if supports_keepalive() && size.is_some_and(|size| size <= LIMIT) {
+        spawn(options, &js_request, request.keepalive(true));
+        return Ok(true);
+    }
+
+    // sendBeacon, 大小未知(如 `FormData`)时由浏览器判断
+    if method == "POST" && !compressed && size.map(|size| size <= LIMIT).unwrap_or(true) && send_beacon(&js_request.url(), &data) {
+        return Ok(true);
+    }
+
+    spawn(options, &js_request, request);
+    Ok(false)
+}
+
+/// 发送 `fetch`, 不等待响应, 失败被忽略
+fn spawn(options: &HttpRequestOptions, js_request: &web_sys::Request, request: HttpRequest) {
+    let promise = fetch::js_fetch(options.timeout, js_request, Some(request));
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = JsFuture::from(promise).await;
+    });
+}
+
+/// `body` 的字节数, 无法计算时返回 `None`
+fn body_size(data: &JsValue) -> Option<f64> {
+    if let Some(data) = data.as_string() {
+        return Some(data.len() as f64);
+    }
+
+    if let Some(bytes) = data.dyn_ref::<Uint8Array>() {
+        return Some(bytes.length() as f64);
+    }
+
+    data.dyn_ref::<Blob>().map(|blob| blob.size())
+}
+
+/// 是否支持 `Request.keepalive`
+fn supports_keepalive() -> bool {
+    Reflect::get(&js_sys::global(), &JsValue::from_str("Request"))
+        .and_then(|request| Reflect::get(&request, &JsValue::from_str("prototype")))
+        .and_then(|prototype| Reflect::has(&prototype, &JsValue::from_str("keepalive")))
+        .unwrap_or(false)
+}
+
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Navigator/sendBeacon)"]
+fn send_beacon(url: &str, data: &JsValue) -> bool {
+    let navigator = match Reflect::get(&js_sys::global(), &JsValue::from_str("navigator")) {
+        Ok(navigator) if navigator.is_object() => navigator,
+        _ => return false,
+    };
+
+    let send_beacon = match Reflect::get(&navigator, &JsValue::from_str("sendBeacon")).ok().and_then(|send_beacon| send_beacon.dyn_into::<Function>().ok()) {
+        Some(send_beacon) => send_beacon,
+        None => return false,
+    };
+
+    send_beacon.call2(&navigator, &JsValue::from_str(url), data).map(|queued| queued.as_bool().unwrap_or(false)).unwrap_or(false)
+}
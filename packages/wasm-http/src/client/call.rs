@@ -1,44 +1,119 @@
+use crate::case::KeyTransform;
 use crate::client::fetch::fetch;
+use crate::har::WireResponse;
+use crate::multipart;
+use crate::request::compression;
+use crate::request::stream;
 use crate::request::HttpRequest;
-use crate::{Error, HttpRequestOptions, HttpRequestType, HttpResponseOptions, TIMEOUT};
-use http::header::CONTENT_TYPE;
+use crate::response::charset;
+use crate::response::digest;
+use crate::response::headers::append_header;
+use crate::response::response_type::ResponseType;
+use crate::response::timing::Timing;
+use crate::schema::SchemaMode;
+use crate::{warn, Error, HttpRequestOptions, HttpRequestType, HttpResponseOptions, TIMEOUT};
+use http::header::{CONTENT_ENCODING, CONTENT_TYPE};
 use http::response::Builder;
-use http::Response;
+use http::{HeaderMap, Response};
 use js_sys::{Array, Object, Uint8Array, JSON};
 use std::collections::HashMap;
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{Blob, BlobPropertyBag, FormData, Headers, RequestCredentials, RequestInit, UrlSearchParams};
+use web_sys::{Blob, BlobPropertyBag, Headers, ReadableStream, RequestCredentials, RequestInit, RequestMode, UrlSearchParams};
 
 pub struct Call;
 
 impl Call {
     pub async fn exec(request: Option<HttpRequest>, options: HttpRequestOptions) -> Result<Response<HttpResponseOptions>, Error> {
-        let headers = Self::prepare_headers(&options)?;
+        let timing = Timing::start();
+        let (js_request, response) = Self::fetch(request, &options).await?;
 
-        let js_request = Self::prepare_js_request(&options, headers)?;
-        let response = fetch(options.timeout, &js_request, request).await?;
+        // 录制 `HAR` 时复制一份响应, 处理完成后读取原始内容, 处理失败时同样记录
+        let wire = match &options.har_wire {
+            Some(wire) if !ResponseType::from(response.type_()).is_opaque() => Some((wire, response.clone().map_err(Error::js_error)?)),
+            _ => None,
+        };
+
+        let mut result = Self::read_response(&js_request.method(), response, &options, timing).await;
+        if let Ok(result) = &mut result {
+            let http_response = result.body_mut();
+            http_response.timing = http_response.timing.clone().end().resource(&js_request.url());
+        }
+
+        if let Some((wire, response)) = wire {
+            if let Ok(response) = Self::read_wire(response).await {
+                wire.set(response);
+            }
+        }
+
+        result
+    }
+
+    /// 读取原始响应, 用于 `HAR` 录制
+    async fn read_wire(response: web_sys::Response) -> Result<WireResponse, Error> {
+        let (_, headers) = Self::prepare_response_headers(Response::builder(), response.headers())?;
+        let array_buffer = JsFuture::from(response.array_buffer().map_err(Error::js_error)?).await.map_err(Error::js_error)?;
+        Ok(WireResponse {
+            status: response.status(),
+            status_text: response.status_text(),
+            headers,
+            body: Uint8Array::new(&array_buffer).to_vec(),
+        })
+    }
 
+    /// 处理响应: 校验摘要、按 `responseType` 及 `charset` 读取 `body`、转换 `key`、校验 `schema`, `HAR` 回放时同样使用
+    pub(crate) async fn read_response(method: &str, response: web_sys::Response, options: &HttpRequestOptions, timing: Timing) -> Result<Response<HttpResponseOptions>, Error> {
         // http response
         let result = Response::builder().status(response.status());
 
         // response headers
         let response_headers = response.headers().clone();
         let (result, response_headers) = Self::prepare_response_headers(result, response_headers)?;
+        let kind = ResponseType::from(response.type_());
+
+        let mut http_response = HttpResponseOptions {
+            status_code: response.status(),
+            status_text: response.status_text(),
+            ok: response.ok(),
+            url: response.url(),
+            redirected: response.redirected(),
+            kind,
+            headers: response_headers,
+            filtered: kind.is_filtered(),
+            body: serde_json::Value::Null,
+            error: "".to_string(),
+            timing,
+            blobs: Vec::new(),
+            raw: None,
+        };
+
+        // opaque 响应无法读取 body
+        if kind.is_opaque() {
+            http_response.error = Error::OpaqueResponse.to_string();
+            return result.body(http_response).map_err(Error::HttpError);
+        }
+
+        // 校验摘要, 不一致时不返回数据, 错误状态码的响应不校验
+        let response = match &options.verify_digest {
+            Some(verify_digest) if response.ok() => digest::verify_response(response, verify_digest).await?,
+            _ => response,
+        };
 
         // response body
-        let status_code = response.status();
         let response_type = options.response_type.clone().unwrap_or(HttpRequestType::Text);
 
-        let method = js_request.method().to_string();
         let mut body = serde_json::Value::Null;
+        let content_type = http_response.headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string();
+        let is_text = matches!(options.response_type, Some(HttpRequestType::Text) | Some(HttpRequestType::Html));
 
         match response_type {
             HttpRequestType::Blob => {
                 let response_body = response.blob().map_err(Error::js_error)?;
                 let response_body: JsValue = JsFuture::from(response_body).await.map_err(Error::js_error)?;
                 let blob = response_body.dyn_ref::<Blob>();
-                if let Some(blob) = blob {
+                if let Some(blob) = blob.filter(|_| options.raw_blob) {
+                    http_response.blobs.push((Vec::new(), blob.clone()));
+                } else if let Some(blob) = blob {
                     let array_buffer = JsFuture::from(blob.array_buffer()).await.map_err(Error::js_error)?;
                     let uint8_array = Uint8Array::new(&array_buffer);
                     let binary_array: Vec<u8> = uint8_array.to_vec();
@@ -46,62 +121,121 @@ impl Call {
                 }
             }
             HttpRequestType::FormData => {
-                let response_body = response.form_data().map_err(Error::js_error)?;
-                let response_body: JsValue = JsFuture::from(response_body).await.map_err(Error::js_error)?;
-                let form_data = response_body.dyn_ref::<FormData>();
-                if let Some(form_data) = form_data {
-                    let iterator = js_sys::try_iter(form_data).map_err(Error::js_error)?;
-                    let mut form_data_map = serde_json::Map::new();
-                    if let Some(iterator) = iterator {
-                        for item in iterator {
-                            let entry = item.map_err(Error::js_error)?;
-                            let entry = Array::from(&entry);
-                            let key = entry.get(0).as_string().unwrap();
-                            let value = entry.get(1).as_string().unwrap();
-                            form_data_map.insert(key, serde_json::Value::String(value));
-                        }
-
-                        body = serde_json::Value::Object(form_data_map);
-                    }
+                let (response_body, blobs) = multipart::read_response(&response, &content_type).await?;
+                body = response_body;
+                http_response.blobs = blobs;
+            }
+            // 流式读取 `multipart/mixed`, 如 `GraphQL` 的 `@defer`/`@stream`, 由回调处理每个 `part`, `body` 为空
+            _ if !is_text && multipart::is_mixed(&content_type) && options.on_part.is_some() => {
+                if let Some(listener) = &options.on_part {
+                    multipart::read_stream(&response, &content_type, listener).await?;
                 }
             }
+            // `multipart/mixed` 等响应
+            _ if !is_text && multipart::get_boundary(&content_type).is_some() => {
+                let (response_body, blobs) = multipart::read_response(&response, &content_type).await?;
+                body = response_body;
+                http_response.blobs = blobs;
+            }
             _ => {
-                let response_body = response.text().map_err(Error::js_error)?;
+                let response_body = response.array_buffer().map_err(Error::js_error)?;
                 let response_body: JsValue = JsFuture::from(response_body).await.map_err(Error::js_error)?;
-                if let Some(response_body) = response_body.as_string() {
-                    if method.to_lowercase() == "post" || method.to_lowercase() == "get" {
-                        body = serde_json::from_slice(response_body.as_bytes()).map_err(|_| Error::MissingResponseBody)?;
+                let bytes = Uint8Array::new(&response_body);
+
+                // 根据 `BOM`、`responseEncoding`、`charset` 解码
+                let content_type = Some(content_type.as_str()).filter(|content_type| !content_type.is_empty());
+                let bom = bytes.subarray(0, bytes.length().min(3)).to_vec();
+                let encoding = charset::get_encoding(&bom, content_type, options.response_encoding.as_deref())?;
+                let response_body = charset::decode_js(&bytes, encoding)?;
+
+                if is_text {
+                    body = serde_json::Value::String(response_body);
+                } else {
+                    let method = method.to_lowercase();
+                    // `post` 和 `get` 必须有 `body`, 其他请求(`head` 除外)只解析非空的 `json` `body`, 如 `OK`、`html` 等仍为 `null`
+                    let is_json = content_type.map(|content_type| content_type.to_lowercase().contains("json")).unwrap_or(false);
+                    let has_body = method == "post" || method == "get" || (method != "head" && is_json && !response_body.trim().is_empty());
+
+                    // `JS` 调用且无需转换、校验时, 保留原始文本, 由 `JSON.parse` 解析
+                    if options.raw_json && options.transform_response_keys.is_none() && options.schema.is_none() {
+                        http_response.raw = Some(response_body).filter(|_| has_body);
+                    } else {
+                        if has_body {
+                            body = serde_json::from_slice(response_body.as_bytes()).map_err(|_| Error::MissingResponseBody)?;
+                        }
+
+                        // 转换 `key`, 之后再校验 `schema`
+                        if let Some(case) = options.transform_response_keys {
+                            body = KeyTransform::new(case, options.transform_skip.clone().unwrap_or_default()).transform(body);
+                        }
+
+                        // schema 校验
+                        if let Some(schema) = &options.schema {
+                            if let Err(err) = schema.validate(&body) {
+                                if options.schema_mode.unwrap_or_default() != SchemaMode::Warn {
+                                    return Err(err);
+                                }
+
+                                warn(&format!("wasm http: {}", err));
+                                http_response.error = err.to_string();
+                            }
+                        }
                     }
                 }
             }
         }
 
-        result
-            .body(HttpResponseOptions {
-                status_code,
-                headers: response_headers,
-                body,
-                error: "".to_string(),
-            })
-            .map_err(Error::HttpError)
+        http_response.body = body;
+        result.body(http_response).map_err(Error::HttpError)
+    }
+
+    /// 发送请求, 返回 `js` 的 `Request` 和 `Response`, 不读取 `body`
+    pub(crate) async fn fetch(request: Option<HttpRequest>, options: &HttpRequestOptions) -> Result<(web_sys::Request, web_sys::Response), Error> {
+        let headers = Self::prepare_headers(options)?;
+        let (method, is_method_get) = Self::prepare_method(options);
+        let mut data = Self::prepare_body(options)?;
+
+        // 压缩 `body`
+        if let Some(compression) = options.compress_request.filter(|_| !is_method_get) {
+            if let Some(compressed) = compression::compress(&data, compression, options.compress_threshold.unwrap_or(compression::THRESHOLD)).await? {
+                headers.set(CONTENT_ENCODING.as_str(), compression.encoding()).map_err(Error::js_error)?;
+                data = compressed;
+            }
+        }
+
+        // 修改了 `url` 时沿用原生 `Request` 的 `body`
+        if let Some(body) = Self::source_body(options, is_method_get) {
+            data = JsValue::from(body);
+        }
+
+        // 不支持请求流时读取为 `Blob`
+        if !is_method_get && stream::is_stream(&data) && !stream::supports_request_streams() {
+            data = JsValue::from(stream::buffer(data.unchecked_ref()).await?);
+        }
+
+        let js_request = Self::prepare_js_request(options, headers, method, is_method_get, data)?;
+        let response = fetch(options.timeout, &js_request, request).await?;
+        Ok((js_request, response))
     }
 
     /// request headers
-    fn prepare_headers(options: &HttpRequestOptions) -> Result<Headers, Error> {
+    pub(crate) fn prepare_headers(options: &HttpRequestOptions) -> Result<Headers, Error> {
         let headers = &options.headers;
         let new_headers = Headers::new().map_err(Error::js_error)?;
         let mut print_headers: HashMap<String, String> = HashMap::new();
 
+        // `FormData` 由浏览器设置带 `boundary` 的 `Content-Type`
         let request_type = &options.request_type;
-        if let Some(request_type) = request_type {
+        if let Some(request_type) = request_type.as_ref().filter(|request_type| !matches!(request_type, HttpRequestType::FormData)) {
             new_headers.append(CONTENT_TYPE.as_str(), request_type.get_content_type().as_str()).map_err(Error::js_error)?;
         }
 
         if let Some(headers) = headers {
-            if !headers.is_null() {
-                for (key, value) in headers.as_object().unwrap() {
+            if let Some(headers) = headers.as_object() {
+                for (key, value) in headers {
                     let header_value = value.as_str().unwrap_or("");
-                    new_headers.append(key.as_str(), header_value).map_err(Error::js_error)?;
+                    // 覆盖根据 `type` 设置的 `Content-Type`
+                    new_headers.set(key.as_str(), header_value).map_err(Error::js_error)?;
                     print_headers.insert(key.to_string(), header_value.to_string());
                 }
             }
@@ -111,10 +245,8 @@ impl Call {
         Ok(new_headers)
     }
 
-    /// js request
-    #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Request/Request)"]
-    fn prepare_js_request(options: &HttpRequestOptions, headers: Headers) -> Result<web_sys::Request, Error> {
-        let request = RequestInit::new();
+    /// request method, 返回 `method` 及是否为不能携带 `body` 的 `GET`、`HEAD`
+    pub(crate) fn prepare_method(options: &HttpRequestOptions) -> (&'static str, bool) {
         let mut is_method_get: bool = false;
         let method = match &options.method {
             None => "POST",
@@ -126,12 +258,29 @@ impl Call {
                     "PUT"
                 } else if method.to_lowercase() == "delete" {
                     "DELETE"
+                } else if method.to_lowercase() == "patch" {
+                    "PATCH"
+                } else if method.to_lowercase() == "head" {
+                    is_method_get = true;
+                    "HEAD"
+                } else if method.to_lowercase() == "options" {
+                    "OPTIONS"
                 } else {
                     "POST"
                 }
             }
         };
 
+        (method, is_method_get)
+    }
+
+    /// request body
+    pub(crate) fn prepare_body(options: &HttpRequestOptions) -> Result<JsValue, Error> {
+        // `ReadableStream` 直接发送, 不区分 `type`
+        if let Some(value) = options.data.as_ref().filter(|value| stream::is_stream(value)) {
+            return Ok(value.clone());
+        }
+
         let mut data: JsValue = JsValue::from_str("");
 
         let request_type = &options.request_type;
@@ -140,9 +289,14 @@ impl Call {
                 // Blob
                 HttpRequestType::Blob => {
                     if let Some(value) = options.data.clone() {
-                        let uint8_array = Uint8Array::new(&value);
-                        let blob = Blob::new_with_u8_array_sequence_and_options(&Array::of1(&uint8_array), &BlobPropertyBag::new()).map_err(Error::js_error)?;
-                        data = JsValue::from(blob);
+                        // `Blob`(包括 `Blob.slice` 的结果)直接发送, 不复制到 `wasm` 内存
+                        if value.is_instance_of::<Blob>() {
+                            data = value;
+                        } else {
+                            let uint8_array = Uint8Array::new(&value);
+                            let blob = Blob::new_with_u8_array_sequence_and_options(&Array::of1(&uint8_array), &BlobPropertyBag::new()).map_err(Error::js_error)?;
+                            data = JsValue::from(blob);
+                        }
                     }
                 }
                 // form 表单提交
@@ -174,43 +328,117 @@ impl Call {
                 }
                 _ => {
                     if let Some(value) = options.data.clone() {
-                        if let Some(value) = value.dyn_ref::<Object>() {
+                        // 字符串直接发送
+                        if value.is_string() {
+                            data = value;
+                        } else if let Some(value) = value.dyn_ref::<Object>() {
                             let value = JSON::stringify(value).map_err(Error::js_error)?;
                             data = JsValue::from(value);
+
+                            // 转换 `key`
+                            if let (Some(case), Some(text)) = (options.transform_request_keys, data.as_string()) {
+                                let value: serde_json::Value = serde_json::from_str(&text).map_err(|err| Error::Error(err.to_string()))?;
+                                let value = KeyTransform::new(case, options.transform_skip.clone().unwrap_or_default()).transform(value);
+                                data = JsValue::from_str(&value.to_string());
+                            }
                         }
                     }
                 }
             }
         }
 
+        Ok(data)
+    }
+
+    /// request url, 转换 `query` 参数的名称
+    fn prepare_url(options: &HttpRequestOptions) -> String {
+        match options.transform_request_keys {
+            Some(case) => KeyTransform::new(case, options.transform_skip.clone().unwrap_or_default()).transform_query(&options.url),
+            None => options.url.clone(),
+        }
+    }
+
+    /// 原生的 `Request` 修改了 `url` 且未设置 `data` 时, 返回其 `body`(`ReadableStream`), 与 `data` 一样按是否支持请求流发送
+    pub(crate) fn source_body(options: &HttpRequestOptions, is_method_get: bool) -> Option<ReadableStream> {
+        let source = options.source.as_ref().filter(|_| !is_method_get && options.data.is_none())?;
+        if Self::prepare_url(options) == source.url() {
+            return None;
+        }
+
+        source.body()
+    }
+
+    /// js request
+    #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Request/Request)"]
+    pub(crate) fn prepare_js_request(options: &HttpRequestOptions, headers: Headers, method: &str, is_method_get: bool, data: JsValue) -> Result<web_sys::Request, Error> {
+        let request = RequestInit::new();
         request.set_method(method);
         request.set_headers(headers.as_ref());
+
+        let url = Self::prepare_url(options);
+        if !is_method_get && stream::is_stream(&data) {
+            js_sys::Reflect::set(&request, &JsValue::from_str("duplex"), &JsValue::from_str("half")).map_err(Error::js_error)?;
+        }
+
+        // 原生的 `Request`, 未设置 `data` 时沿用其 `body`(`ReadableStream`), 不读取到内存
+        if let Some(source) = &options.source {
+            if url == source.url() {
+                if !is_method_get && options.data.is_some() {
+                    request.set_body(&data);
+                }
+
+                return web_sys::Request::new_with_request_and_init(source, &request).map_err(Error::js_error);
+            }
+
+            // 修改了 `url`, `body` 已由 `source_body` 取出; `mode`、`credentials` 等属性从 `source` 复制, `HttpRequest` 中设置的值在发送时覆盖
+            Self::copy_source(source, &request);
+            if !is_method_get {
+                request.set_body(&data);
+            }
+
+            return web_sys::Request::new_with_str_and_init(&url, &request).map_err(Error::js_error);
+        }
+
         request.set_credentials(RequestCredentials::SameOrigin);
         if !is_method_get {
             request.set_body(&data);
         }
 
-        web_sys::Request::new_with_str_and_init(&options.url.as_str(), &request).map_err(Error::js_error)
+        web_sys::Request::new_with_str_and_init(&url, &request).map_err(Error::js_error)
+    }
+
+    /// 复制原生 `Request` 的属性, `navigate` 不能用于新建的 `Request`, 改为 `same-origin`
+    fn copy_source(source: &web_sys::Request, request: &RequestInit) {
+        request.set_mode(match source.mode() {
+            RequestMode::Navigate => RequestMode::SameOrigin,
+            mode => mode,
+        });
+        request.set_credentials(source.credentials());
+        request.set_cache(source.cache());
+        request.set_redirect(source.redirect());
+        request.set_referrer(&source.referrer());
+        request.set_referrer_policy(source.referrer_policy());
+        if !source.integrity().is_empty() {
+            request.set_integrity(&source.integrity());
+        }
     }
 
     pub(crate) fn prepare_request_timeout(timeout: Option<i32>) -> i32 {
         let mut request_timeout = TIMEOUT;
         // timeout, -1 表示不超时
         if let Some(timeout) = timeout {
-            if timeout != -1 {
-                if timeout > 0 {
-                    request_timeout = timeout;
-                }
+            if timeout == -1 || timeout > 0 {
+                request_timeout = timeout;
             }
         }
 
         request_timeout
     }
 
-    /// response headers
-    fn prepare_response_headers(mut result: Builder, headers: Headers) -> Result<(Builder, HashMap<String, String>), Error> {
+    /// response headers, 同名多值会被拆分
+    fn prepare_response_headers(mut result: Builder, headers: Headers) -> Result<(Builder, HeaderMap), Error> {
         let headers_iter = js_sys::try_iter(headers.as_ref()).map_err(Error::js_error)?;
-        let mut response_headers: HashMap<String, String> = HashMap::new();
+        let mut response_headers = HeaderMap::new();
         if let Some(header_iter) = headers_iter {
             for header in header_iter {
                 let header = header.map_err(Error::js_error)?;
@@ -220,15 +448,16 @@ impl Call {
                 let header_value = pair.get(1).as_string();
 
                 match (header_name, header_value) {
-                    (Some(header_name), Some(header_value)) => {
-                        response_headers.insert(header_name.clone(), header_value.clone());
-                        result = result.header(header_name.clone(), header_value.clone());
-                    }
+                    (Some(header_name), Some(header_value)) => append_header(&mut response_headers, &header_name, &header_value),
                     _ => continue,
                 }
             }
         }
 
+        for (name, value) in response_headers.iter() {
+            result = result.header(name, value);
+        }
+
         Ok((result, response_headers))
     }
 }
@@ -1,10 +1,11 @@
-use js_sys::{Function, Promise};
+use js_sys::Promise;
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{window, AbortController, Request, RequestInit, Response};
+use web_sys::{AbortController, AbortSignal, DomException, Request, RequestInit, Response};
 
 use crate::client::call::Call;
+use crate::client::timer;
 use crate::{error::Error, request::HttpRequest};
 
 #[wasm_bindgen]
@@ -13,67 +14,59 @@ extern "C" {
     fn fetch_with_request_and_init(input: &Request, init: &RequestInit) -> Promise;
 }
 
+/// 超时取消请求时的 `reason`, 与调用方的取消区分, 见 `Error::js_error`
+pub(crate) const TIMEOUT_ERROR: &str = "TimeoutError";
+
 #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WorkerGlobalScope)"]
 #[doc = "[Github Example](https://github.com/mdn/dom-examples/tree/main/fetch)"]
-fn js_fetch(timeout: Option<i32>, request: &Request, http_request: Option<HttpRequest>) -> Promise {
+pub(crate) fn js_fetch(timeout: Option<i32>, request: &Request, http_request: Option<HttpRequest>) -> Promise {
     let global = js_sys::global();
-    let window = window();
     let init = http_request.clone().map(Into::into).unwrap_or_else(RequestInit::new);
 
-    // timeout
-    let mut timeout = Call::prepare_request_timeout(timeout);
-
-    // abort controller
-    let controller = AbortController::new();
-    if let Err(err) = controller {
-        return Promise::reject(&err);
-    }
-
-    let controller = controller.unwrap();
+    // timeout, -1 表示不超时
+    let timeout = Call::prepare_request_timeout(timeout);
     if timeout != -1 {
-        let signal = controller.signal();
-        timeout = timeout * 1000;
+        let controller = match AbortController::new() {
+            Ok(controller) => controller,
+            Err(err) => return Promise::reject(&err),
+        };
 
-        if let Some(http_request) = http_request.clone() {
-            http_request.signal(Some(&signal));
+        // 调用方传入的 `signal` 取消时同样取消请求, 沿用其 `reason`
+        if let Some(signal) = http_request.and_then(|http_request| http_request.signal) {
+            follow(&controller, &signal);
         }
-    }
-
-    // 创建一个安全的 `Javascript` 闭包
-    let controller_closure = Closure::wrap(Box::new(move || {
-        // 在这里执行超时时的操作
-        println!("Timeout reached!");
-
-        // 在超时时调用 controller.abort() 方法
-        controller.abort();
-    }) as Box<dyn FnMut()>);
 
-    let callback = Function::from(controller_closure.as_ref().clone());
+        init.set_signal(Some(&controller.signal()));
 
-    // 释放闭包，避免内存泄漏
-    controller_closure.forget();
+        // 超时时以 `TimeoutError` 取消请求
+        timer::once((timeout as u32).saturating_mul(1000), move || {
+            let reason = DomException::new_with_message_and_name("request timeout", TIMEOUT_ERROR).map(JsValue::from).unwrap_or_else(|_| JsValue::from_str(TIMEOUT_ERROR));
+            controller.abort_with_reason(&reason);
+        });
+    }
 
     if let Ok(true) = js_sys::Reflect::has(&global, &JsValue::from_str("ServiceWorkerGlobalScope")) {
         let service_worker_global_scope = global.unchecked_into::<web_sys::ServiceWorkerGlobalScope>();
-        if timeout != -1 {
-            let result = service_worker_global_scope.set_timeout_with_callback_and_timeout_and_arguments_0(&callback, timeout);
-            if let Err(err) = result {
-                return Promise::reject(&err);
-            }
-        }
         service_worker_global_scope.fetch_with_request_and_init(request, &init)
     } else {
-        if let Some(window) = window {
-            let result = window.set_timeout_with_callback_and_timeout_and_arguments_0(&callback, timeout);
-            if let Err(err) = result {
-                return Promise::reject(&err);
-            }
-        }
         fetch_with_request_and_init(request, &init)
     }
 }
 
+/// `signal` 取消时取消 `controller`
+fn follow(controller: &AbortController, signal: &AbortSignal) {
+    if signal.aborted() {
+        controller.abort_with_reason(&signal.reason());
+        return;
+    }
+
+    let target = signal.clone();
+    let controller = controller.clone();
+    let listener = Closure::once_into_js(move || controller.abort_with_reason(&target.reason()));
+    let _ = signal.add_event_listener_with_callback("abort", listener.unchecked_ref());
+}
+
 pub async fn fetch(timeout: Option<i32>, request: &Request, http_request: Option<HttpRequest>) -> Result<Response, Error> {
-    let js_response = JsFuture::from(js_fetch(timeout, request, http_request)).await.map_err(Error::js_error)?;
+    let js_response = JsFuture::from(js_fetch(timeout, request, http_request)).await.map_err(Error::fetch_error)?;
     Ok(js_response.unchecked_into())
 }
@@ -0,0 +1,19 @@
+//! `js` 的异步迭代器, 用于 `for await`
+
+use js_sys::{Object, Promise};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(inline_js = r#"
+export function asyncIterator(next, finish) {
+    return { next, return: finish, [Symbol.asyncIterator]() { return this; } };
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(js_name = asyncIterator)]
+    fn new_async_iterator(next: &JsValue, finish: &JsValue) -> Object;
+}
+
+/// 由 `next`、`return` 创建异步迭代器, `Symbol.asyncIterator` 返回自身, 闭包随迭代器一起回收
+pub(crate) fn async_iterator(next: Closure<dyn FnMut() -> Promise>, finish: Closure<dyn FnMut() -> Promise>) -> Object {
+    new_async_iterator(&next.into_js_value(), &finish.into_js_value())
+}
@@ -1,5 +1,9 @@
-mod call;
-mod fetch;
+pub(crate) mod beacon;
+pub(crate) mod call;
+pub(crate) mod fetch;
+pub(crate) mod iterator;
+pub(crate) mod timer;
+pub mod websocket;
 
 use crate::client::call::Call;
 use crate::error::Error;
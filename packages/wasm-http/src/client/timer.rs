@@ -0,0 +1,29 @@
+//! 定时器, `setTimeout` 在 `Window` 和 `Worker` 中都可以使用
+
+use js_sys::{Function, Promise};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &Function, timeout: i32) -> JsValue;
+}
+
+/// `timeout` 毫秒后执行一次 `callback`, 返回定时器的 `id`
+pub(crate) fn once<F>(timeout: u32, callback: F) -> JsValue
+where
+    F: FnOnce() + 'static,
+{
+    let callback = Closure::once_into_js(callback);
+    set_timeout(callback.unchecked_ref(), timeout.min(i32::MAX as u32) as i32)
+}
+
+/// 等待 `timeout` 毫秒
+pub(crate) async fn sleep(timeout: u32) {
+    let promise = Promise::new(&mut |resolve, _| {
+        set_timeout(&resolve, timeout.min(i32::MAX as u32) as i32);
+    });
+
+    let _ = JsFuture::from(promise).await;
+}
@@ -0,0 +1,794 @@
+/*!
+    `WebSocket` 客户端, 支持:
+    - 断线后按指数退避自动重连
+    - `ping`/`pong` 心跳, 超时未收到任何消息时判定连接已断开并重连
+    - 断线期间的发送缓冲区, 重连成功后按顺序发送
+    - 消息以 `JSON`、文本或二进制的形式分发给 `js` 的回调、异步迭代器, 以及 `Rust` 的 `Stream`/`Sink`
+*/
+
+use crate::client::{iterator, timer};
+use crate::error::Error;
+use crate::response::timing::now;
+use crate::send::HttpClient;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::future::poll_fn;
+use futures::{Sink, StreamExt};
+use js_sys::{Array, ArrayBuffer, Function, Object, Promise, Reflect, Uint8Array, JSON};
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+/// 心跳超时时关闭连接使用的状态码
+const HEARTBEAT_TIMEOUT_CODE: u16 = 4000;
+
+/// 消息
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Json(Value),
+}
+
+impl Message {
+    /// 转换成 `js` 的值, `Binary` 为 `Uint8Array`
+    fn to_js(&self) -> JsValue {
+        match self {
+            Message::Text(text) => JsValue::from_str(text),
+            Message::Binary(bytes) => Uint8Array::from(bytes.as_slice()).into(),
+            Message::Json(value) => value.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap_or(JsValue::NULL),
+        }
+    }
+}
+
+/// 文本消息的解析方式
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MessageType {
+    /// 解析为 `JSON`, 解析失败时作为文本
+    #[default]
+    Json,
+
+    /// 文本
+    Text,
+}
+
+impl MessageType {
+    pub fn get_message_type(message_type: String) -> MessageType {
+        match message_type.trim().to_lowercase().as_str() {
+            "text" => MessageType::Text,
+            _ => MessageType::Json,
+        }
+    }
+}
+
+/// 重连配置, 第 `n` 次重连的等待时间为 `min(initial_delay * factor ^ (n - 1), max_delay)`, 并增加 `±jitter` 的随机抖动
+#[derive(Debug, Clone)]
+pub struct ReconnectOptions {
+    pub initial_delay: u32,       // 首次重连的等待时间(毫秒)
+    pub max_delay: u32,           // 最大等待时间(毫秒)
+    pub factor: f64,              // 指数
+    pub max_retries: Option<u32>, // 最大重连次数, `None` 为不限制
+    pub jitter: f64,              // 抖动比例, 0 ~ 1
+}
+
+impl Default for ReconnectOptions {
+    fn default() -> Self {
+        Self {
+            initial_delay: 1000,
+            max_delay: 30000,
+            factor: 2.0,
+            max_retries: None,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl ReconnectOptions {
+    /// 第 `attempt` 次重连的等待时间, `random` 为 0 ~ 1 的随机数
+    pub fn delay(&self, attempt: u32, random: f64) -> u32 {
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let delay = (self.initial_delay as f64 * self.factor.max(1.0).powi(exponent)).min(self.max_delay as f64);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let delay = delay * (1.0 - jitter + 2.0 * jitter * random.clamp(0.0, 1.0));
+        delay.round() as u32
+    }
+}
+
+/// 心跳配置, 每隔 `interval` 发送一次 `ping`, `timeout` 内没有收到任何消息时判定连接已断开
+#[derive(Debug, Clone)]
+pub struct HeartbeatOptions {
+    pub interval: u32, // 发送间隔(毫秒)
+    pub timeout: u32,  // 超时时间(毫秒)
+    pub ping: String,  // 发送的内容
+    pub pong: String,  // 服务端回复的内容, 收到后不会分发
+}
+
+impl Default for HeartbeatOptions {
+    fn default() -> Self {
+        Self {
+            interval: 30000,
+            timeout: 10000,
+            ping: String::from("ping"),
+            pong: String::from("pong"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebSocketOptions {
+    pub protocols: Vec<String>,              // 子协议
+    pub message_type: MessageType,           // 文本消息的解析方式
+    pub reconnect: Option<ReconnectOptions>, // 重连配置, `None` 为不重连
+    pub heartbeat: Option<HeartbeatOptions>, // 心跳配置, `None` 为不发送心跳
+    pub buffer_size: usize,                  // 断线期间最多缓存的消息数
+}
+
+impl Default for WebSocketOptions {
+    fn default() -> Self {
+        Self {
+            protocols: Vec::new(),
+            message_type: MessageType::default(),
+            reconnect: Some(ReconnectOptions::default()),
+            heartbeat: None,
+            buffer_size: 100,
+        }
+    }
+}
+
+/// 连接状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Connecting,
+    Open,
+    Reconnecting,
+    Closing,
+    Closed,
+}
+
+impl State {
+    fn as_str(&self) -> &'static str {
+        match self {
+            State::Connecting => "connecting",
+            State::Open => "open",
+            State::Reconnecting => "reconnecting",
+            State::Closing => "closing",
+            State::Closed => "closed",
+        }
+    }
+}
+
+/// 绑定在 `WebSocket` 上的回调, 需要和连接保存在一起, 避免被释放
+struct Handlers {
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+    _on_error: Closure<dyn FnMut(Event)>,
+}
+
+struct Inner {
+    url: String,
+    options: WebSocketOptions,
+    socket: Option<WebSocket>,
+    handlers: Option<Handlers>,
+    state: State,
+    manual_close: bool,                         // 是否由用户关闭, 用户关闭时不重连
+    attempts: u32,                              // 当前的重连次数
+    generation: u32,                            // 每次连接递增, 用于忽略旧连接的回调
+    buffer: VecDeque<Message>,                  // 断线期间的发送缓冲区
+    last_received: f64,                         // 最后一次收到消息的时间
+    listeners: Vec<(String, Function)>,         // `js` 回调
+    subscribers: Vec<UnboundedSender<Message>>, // `Stream` 及异步迭代器
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct WebSocketClient {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[wasm_bindgen]
+impl WebSocketClient {
+    /**
+      创建客户端并连接, `options` 中可以设置 `protocols`、`messageType`、`reconnect`、`heartbeat`、`bufferSize`
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String, options: JsValue) -> Result<WebSocketClient, JsValue> {
+        let options = Self::get_options(&options)?;
+        let inner = Self::create(&url, options);
+        // 地址或协议不合法时直接抛出, 不再重连
+        Self::open(&inner)?;
+        Ok(Self { inner })
+    }
+
+    /**
+      连接状态: `connecting`、`open`、`reconnecting`、`closing`、`closed`
+    */
+    #[wasm_bindgen(getter)]
+    pub fn state(&self) -> String {
+        self.inner.borrow().state.as_str().to_string()
+    }
+
+    /**
+      发送缓冲区中的消息数
+    */
+    #[wasm_bindgen(getter)]
+    pub fn buffered(&self) -> usize {
+        self.inner.borrow().buffer.len()
+    }
+
+    /**
+      关闭后重新连接, 地址或协议不合法时抛出
+    */
+    pub fn connect(&self) -> Result<(), JsValue> {
+        {
+            let mut inner = self.inner.borrow_mut();
+            if inner.state != State::Closed {
+                return Ok(());
+            }
+
+            inner.manual_close = false;
+            inner.attempts = 0;
+        }
+
+        Self::open(&self.inner)
+    }
+
+    /**
+      发送消息, `object` 会被序列化为 `JSON`, `ArrayBuffer` 和 `TypedArray` 以二进制发送, 断线期间会被缓存
+    */
+    pub fn send(&self, data: JsValue) -> Result<(), JsValue> {
+        let message = if let Some(text) = data.as_string() {
+            Message::Text(text)
+        } else if data.is_instance_of::<ArrayBuffer>() {
+            Message::Binary(Uint8Array::new(&data).to_vec())
+        } else if ArrayBuffer::is_view(&data) {
+            // `TypedArray` 及 `DataView` 按字节发送
+            let view: js_sys::DataView = data.unchecked_into();
+            Message::Binary(Uint8Array::new_with_byte_offset_and_length(&view.buffer(), view.byte_offset() as u32, view.byte_length() as u32).to_vec())
+        } else {
+            Message::Text(String::from(JSON::stringify(&data)?))
+        };
+
+        self.send_message(message).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /**
+      监听事件: `open`、`message`、`close`、`error`、`reconnect`
+    */
+    pub fn on(&self, event: String, callback: Function) {
+        self.inner.borrow_mut().listeners.push((event, callback));
+    }
+
+    /**
+      取消监听
+    */
+    pub fn off(&self, event: String, callback: Function) {
+        self.inner.borrow_mut().listeners.retain(|(name, listener)| !(name == &event && listener == &callback));
+    }
+
+    /**
+      返回异步迭代器, 用于 `for await (const message of client.messages())`, 关闭后结束
+    */
+    pub fn messages(&self) -> Result<Object, JsValue> {
+        let receiver: Rc<RefCell<Option<UnboundedReceiver<Message>>>> = Rc::new(RefCell::new(Some(self.subscribe())));
+
+        let next_receiver = receiver.clone();
+        let next = Closure::wrap(Box::new(move || -> Promise {
+            let receiver = next_receiver.clone();
+            future_to_promise(async move {
+                let message = poll_fn(|cx| match receiver.borrow_mut().as_mut() {
+                    Some(receiver) => receiver.poll_next_unpin(cx),
+                    None => Poll::Ready(None),
+                })
+                .await;
+                Self::iterator_result(message.map(|message| message.to_js()))
+            })
+        }) as Box<dyn FnMut() -> Promise>);
+
+        // `for await` 提前结束时调用, 释放 `receiver` 并移除对应的订阅, 避免消息继续堆积
+        let weak = Rc::downgrade(&self.inner);
+        let finish = Closure::wrap(Box::new(move || -> Promise {
+            receiver.borrow_mut().take();
+            if let Some(inner) = weak.upgrade() {
+                inner.borrow_mut().subscribers.retain(|subscriber| !subscriber.is_closed());
+            }
+            future_to_promise(async { Self::iterator_result(None) })
+        }) as Box<dyn FnMut() -> Promise>);
+
+        // 迭代器的生命周期由 `js` 决定, 回收迭代器时一起释放闭包
+        Ok(iterator::async_iterator(next, finish))
+    }
+
+    /**
+      `Stream` 及异步迭代器的订阅数
+    */
+    #[wasm_bindgen(getter)]
+    pub fn subscribers(&self) -> usize {
+        self.inner.borrow().subscribers.len()
+    }
+
+    /**
+      关闭连接, 不会再重连
+    */
+    pub fn close(&self, code: Option<u16>, reason: Option<String>) {
+        let socket = {
+            let mut inner = self.inner.borrow_mut();
+            inner.manual_close = true;
+            inner.buffer.clear();
+            inner.socket.clone()
+        };
+
+        match socket {
+            Some(socket) if socket.ready_state() != WebSocket::CLOSED => {
+                self.inner.borrow_mut().state = State::Closing;
+                let result = match (code, reason) {
+                    (Some(code), Some(reason)) => socket.close_with_code_and_reason(code, &reason),
+                    (Some(code), None) => socket.close_with_code(code),
+                    _ => socket.close(),
+                };
+
+                if result.is_err() {
+                    let _ = socket.close();
+                }
+            }
+            // 正在等待重连
+            _ => Self::finish(&self.inner),
+        }
+    }
+}
+
+impl WebSocketClient {
+    /// 创建客户端并连接, 创建 `WebSocket` 失败时触发 `error` 事件, 状态为 `closed`, 不再重连
+    pub fn connect_with(url: &str, options: WebSocketOptions) -> Self {
+        let inner = Self::create(url, options);
+        let _ = Self::open(&inner);
+        Self { inner }
+    }
+
+    fn create(url: &str, options: WebSocketOptions) -> Rc<RefCell<Inner>> {
+        Rc::new(RefCell::new(Inner {
+            url: url.to_string(),
+            options,
+            socket: None,
+            handlers: None,
+            state: State::Connecting,
+            manual_close: false,
+            attempts: 0,
+            generation: 0,
+            buffer: VecDeque::new(),
+            last_received: 0.0,
+            listeners: Vec::new(),
+            subscribers: Vec::new(),
+        }))
+    }
+
+    /// 订阅消息, 连接关闭且不再重连时 `Stream` 结束
+    pub fn subscribe(&self) -> UnboundedReceiver<Message> {
+        let (sender, receiver) = unbounded();
+        let mut inner = self.inner.borrow_mut();
+        // 顺便移除已经释放的订阅
+        inner.subscribers.retain(|subscriber| !subscriber.is_closed());
+        if inner.state == State::Closed && inner.manual_close {
+            sender.close_channel();
+        } else {
+            inner.subscribers.push(sender);
+        }
+
+        receiver
+    }
+
+    /// 用于发送消息的 `Sink`
+    pub fn sink(&self) -> WebSocketSink {
+        WebSocketSink { client: self.clone() }
+    }
+
+    fn iterator_result(message: Option<JsValue>) -> Result<JsValue, JsValue> {
+        let result = Object::new();
+        Reflect::set(&result, &JsValue::from_str("done"), &JsValue::from_bool(message.is_none()))?;
+        Reflect::set(&result, &JsValue::from_str("value"), &message.unwrap_or(JsValue::UNDEFINED))?;
+        Ok(result.into())
+    }
+
+    /// 发送消息, 断线期间会被缓存
+    pub fn send_message(&self, message: Message) -> Result<(), Error> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.manual_close {
+            return Err(Error::Error("websocket is closed !".to_string()));
+        }
+
+        if inner.state == State::Open {
+            if let Some(socket) = &inner.socket {
+                return Self::send_frame(socket, &message);
+            }
+        }
+
+        if inner.buffer.len() >= inner.options.buffer_size {
+            return Err(Error::Error(format!("websocket send buffer is full, size: {} !", inner.options.buffer_size)));
+        }
+
+        inner.buffer.push_back(message);
+        Ok(())
+    }
+
+    fn send_frame(socket: &WebSocket, message: &Message) -> Result<(), Error> {
+        match message {
+            Message::Text(text) => socket.send_with_str(text),
+            Message::Binary(bytes) => socket.send_with_u8_array(bytes),
+            Message::Json(value) => socket.send_with_str(&value.to_string()),
+        }
+        .map_err(Error::js_error)
+    }
+
+    /// 解析 `js` 配置
+    fn get_options(options: &JsValue) -> Result<WebSocketOptions, JsValue> {
+        let mut websocket_options = WebSocketOptions::default();
+        let obj = match options.dyn_ref::<Object>() {
+            Some(obj) => obj,
+            None => return Ok(websocket_options),
+        };
+
+        let number = |obj: &Object, name: &str| HttpClient::get_value(obj, name).and_then(|value| value.as_f64());
+
+        // protocols
+        if let Some(protocols) = HttpClient::get_value(obj, "protocols") {
+            websocket_options.protocols = match protocols.as_string() {
+                Some(protocol) => vec![protocol],
+                None => Array::from(&protocols).iter().filter_map(|protocol| protocol.as_string()).collect(),
+            };
+        }
+
+        // messageType
+        if let Some(message_type) = HttpClient::get_value(obj, "messageType") {
+            websocket_options.message_type = MessageType::get_message_type(HttpClient::get_str(message_type));
+        }
+
+        // reconnect, `false` 为不重连
+        if let Some(reconnect) = HttpClient::get_value(obj, "reconnect") {
+            if reconnect.as_bool() == Some(false) {
+                websocket_options.reconnect = None;
+            } else if let Some(reconnect) = reconnect.dyn_ref::<Object>() {
+                let mut reconnect_options = ReconnectOptions::default();
+                if let Some(initial_delay) = number(reconnect, "initialDelay") {
+                    reconnect_options.initial_delay = initial_delay.max(0.0) as u32;
+                }
+                if let Some(max_delay) = number(reconnect, "maxDelay") {
+                    reconnect_options.max_delay = max_delay.max(0.0) as u32;
+                }
+                if let Some(factor) = number(reconnect, "factor") {
+                    reconnect_options.factor = factor;
+                }
+                if let Some(max_retries) = number(reconnect, "maxRetries") {
+                    reconnect_options.max_retries = if max_retries < 0.0 { None } else { Some(max_retries as u32) };
+                }
+                if let Some(jitter) = number(reconnect, "jitter") {
+                    reconnect_options.jitter = jitter;
+                }
+                websocket_options.reconnect = Some(reconnect_options);
+            }
+        }
+
+        // heartbeat, `true` 使用默认配置
+        if let Some(heartbeat) = HttpClient::get_value(obj, "heartbeat") {
+            if heartbeat.as_bool() == Some(true) {
+                websocket_options.heartbeat = Some(HeartbeatOptions::default());
+            } else if let Some(heartbeat) = heartbeat.dyn_ref::<Object>() {
+                let mut heartbeat_options = HeartbeatOptions::default();
+                if let Some(interval) = number(heartbeat, "interval") {
+                    heartbeat_options.interval = interval.max(0.0) as u32;
+                }
+                if let Some(timeout) = number(heartbeat, "timeout") {
+                    heartbeat_options.timeout = timeout.max(0.0) as u32;
+                }
+                if let Some(ping) = HttpClient::get_value(heartbeat, "ping") {
+                    heartbeat_options.ping = if ping.is_string() { HttpClient::get_str(ping) } else { String::from(JSON::stringify(&ping)?) };
+                }
+                if let Some(pong) = HttpClient::get_value(heartbeat, "pong") {
+                    heartbeat_options.pong = if pong.is_string() { HttpClient::get_str(pong) } else { String::from(JSON::stringify(&pong)?) };
+                }
+                websocket_options.heartbeat = Some(heartbeat_options);
+            }
+        }
+
+        // bufferSize
+        if let Some(buffer_size) = number(obj, "bufferSize") {
+            websocket_options.buffer_size = buffer_size.max(0.0) as usize;
+        }
+
+        Ok(websocket_options)
+    }
+
+    /// 建立连接, 创建 `WebSocket` 失败时返回错误, 不再重连
+    fn open(this: &Rc<RefCell<Inner>>) -> Result<(), JsValue> {
+        let (url, protocols, generation) = {
+            let mut inner = this.borrow_mut();
+            inner.generation += 1;
+            if inner.state != State::Reconnecting {
+                inner.state = State::Connecting;
+            }
+            (inner.url.clone(), inner.options.protocols.clone(), inner.generation)
+        };
+
+        let protocols = protocols.iter().map(JsValue::from).collect::<Array>();
+        let socket = match WebSocket::new_with_str_sequence(&url, &protocols) {
+            Ok(socket) => socket,
+            // 地址或协议不合法, 重连也会失败, 不再重连
+            Err(err) => {
+                Self::emit(this, "error", &err);
+                Self::emit(this, "close", &JsValue::NULL);
+                Self::finish(this);
+                return Err(err);
+            }
+        };
+
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let weak = Rc::downgrade(this);
+        let on_open = Closure::wrap(Box::new(move |_: Event| {
+            if let Some(this) = weak.upgrade() {
+                Self::on_open(&this, generation);
+            }
+        }) as Box<dyn FnMut(Event)>);
+
+        let weak = Rc::downgrade(this);
+        let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Some(this) = weak.upgrade() {
+                Self::on_message(&this, generation, event);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+
+        let weak = Rc::downgrade(this);
+        let on_close = Closure::wrap(Box::new(move |event: CloseEvent| {
+            if let Some(this) = weak.upgrade() {
+                let detail = Object::new();
+                let _ = Reflect::set(&detail, &JsValue::from_str("code"), &JsValue::from(event.code()));
+                let _ = Reflect::set(&detail, &JsValue::from_str("reason"), &JsValue::from_str(&event.reason()));
+                let _ = Reflect::set(&detail, &JsValue::from_str("wasClean"), &JsValue::from_bool(event.was_clean()));
+                Self::on_closed(&this, generation, detail.into());
+            }
+        }) as Box<dyn FnMut(CloseEvent)>);
+
+        let weak = Rc::downgrade(this);
+        let on_error = Closure::wrap(Box::new(move |event: Event| {
+            if let Some(this) = weak.upgrade() {
+                if this.borrow().generation == generation {
+                    Self::emit(&this, "error", &event);
+                }
+            }
+        }) as Box<dyn FnMut(Event)>);
+
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        socket.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        let mut inner = this.borrow_mut();
+        inner.socket = Some(socket);
+        inner.handlers = Some(Handlers {
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+            _on_error: on_error,
+        });
+        Ok(())
+    }
+
+    fn on_open(this: &Rc<RefCell<Inner>>, generation: u32) {
+        {
+            let mut inner = this.borrow_mut();
+            if inner.generation != generation {
+                return;
+            }
+
+            inner.state = State::Open;
+            inner.attempts = 0;
+            inner.last_received = now();
+
+            // 发送缓冲区中的消息
+            if let Some(socket) = inner.socket.clone() {
+                while let Some(message) = inner.buffer.pop_front() {
+                    if Self::send_frame(&socket, &message).is_err() {
+                        inner.buffer.push_front(message);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Self::heartbeat(this, generation);
+        Self::emit(this, "open", &JsValue::UNDEFINED);
+    }
+
+    fn on_message(this: &Rc<RefCell<Inner>>, generation: u32, event: MessageEvent) {
+        let message = {
+            let mut inner = this.borrow_mut();
+            if inner.generation != generation {
+                return;
+            }
+
+            inner.last_received = now();
+            let data = event.data();
+            match data.as_string() {
+                Some(text) => {
+                    // 心跳回复不分发
+                    if inner.options.heartbeat.as_ref().map(|heartbeat| heartbeat.pong == text).unwrap_or(false) {
+                        return;
+                    }
+
+                    match inner.options.message_type {
+                        MessageType::Json => serde_json::from_str::<Value>(&text).map(Message::Json).unwrap_or(Message::Text(text)),
+                        MessageType::Text => Message::Text(text),
+                    }
+                }
+                None => Message::Binary(Uint8Array::new(&data).to_vec()),
+            }
+        };
+
+        this.borrow_mut().subscribers.retain(|subscriber| subscriber.unbounded_send(message.clone()).is_ok());
+        Self::emit(this, "message", &message.to_js());
+    }
+
+    fn on_closed(this: &Rc<RefCell<Inner>>, generation: u32, detail: JsValue) {
+        let reconnect = {
+            let mut inner = this.borrow_mut();
+            if inner.generation != generation {
+                return;
+            }
+
+            inner.socket = None;
+            let can_retry = inner.options.reconnect.as_ref().map(|reconnect| reconnect.max_retries.map(|max| inner.attempts < max).unwrap_or(true)).unwrap_or(false);
+            if inner.manual_close || !can_retry {
+                None
+            } else {
+                inner.attempts += 1;
+                inner.state = State::Reconnecting;
+                inner.options.reconnect.as_ref().map(|reconnect| (inner.attempts, reconnect.delay(inner.attempts, js_sys::Math::random())))
+            }
+        };
+
+        Self::emit(this, "close", &detail);
+
+        match reconnect {
+            Some((attempt, delay)) => {
+                let detail = Object::new();
+                let _ = Reflect::set(&detail, &JsValue::from_str("attempt"), &JsValue::from(attempt));
+                let _ = Reflect::set(&detail, &JsValue::from_str("delay"), &JsValue::from(delay));
+                Self::emit(this, "reconnect", &detail);
+
+                let weak = Rc::downgrade(this);
+                timer::once(delay, move || {
+                    if let Some(this) = weak.upgrade() {
+                        let reconnecting = {
+                            let inner = this.borrow();
+                            inner.state == State::Reconnecting && !inner.manual_close && inner.generation == generation
+                        };
+
+                        if reconnecting {
+                            let _ = Self::open(&this);
+                        }
+                    }
+                });
+            }
+            None => Self::finish(this),
+        }
+    }
+
+    /// 不再重连, 结束所有的 `Stream`
+    fn finish(this: &Rc<RefCell<Inner>>) {
+        let mut inner = this.borrow_mut();
+        inner.state = State::Closed;
+        inner.generation += 1;
+        inner.buffer.clear();
+        for subscriber in inner.subscribers.drain(..) {
+            subscriber.close_channel();
+        }
+    }
+
+    /// 心跳, 每隔 `interval` 发送一次 `ping`, `timeout` 内没有收到任何消息时关闭连接并重连
+    fn heartbeat(this: &Rc<RefCell<Inner>>, generation: u32) {
+        let heartbeat = match this.borrow().options.heartbeat.clone() {
+            Some(heartbeat) => heartbeat,
+            None => return,
+        };
+
+        let weak = Rc::downgrade(this);
+        timer::once(heartbeat.interval, move || {
+            let this = match weak.upgrade() {
+                Some(this) => this,
+                None => return,
+            };
+
+            let socket = {
+                let inner = this.borrow();
+                if inner.generation != generation || inner.state != State::Open {
+                    return;
+                }
+                inner.socket.clone()
+            };
+
+            let sent_at = now();
+            if let Some(socket) = socket {
+                let _ = socket.send_with_str(&heartbeat.ping);
+            }
+
+            let weak = Rc::downgrade(&this);
+            timer::once(heartbeat.timeout, move || {
+                if let Some(this) = weak.upgrade() {
+                    let timeout = {
+                        let inner = this.borrow();
+                        inner.generation == generation && inner.state == State::Open && inner.last_received < sent_at
+                    };
+
+                    if timeout {
+                        Self::on_timeout(&this);
+                    }
+                }
+            });
+
+            Self::heartbeat(&this, generation);
+        });
+    }
+
+    /// 心跳超时, 连接已断开时关闭握手可能迟迟不完成, 不等待 `onclose`, 解绑旧连接的回调后直接按断开处理
+    fn on_timeout(this: &Rc<RefCell<Inner>>) {
+        let (socket, handlers, generation) = {
+            let mut inner = this.borrow_mut();
+            inner.generation += 1;
+            (inner.socket.clone(), inner.handlers.take(), inner.generation)
+        };
+
+        if let Some(socket) = socket {
+            socket.set_onopen(None);
+            socket.set_onmessage(None);
+            socket.set_onclose(None);
+            socket.set_onerror(None);
+            let _ = socket.close_with_code_and_reason(HEARTBEAT_TIMEOUT_CODE, "heartbeat timeout");
+        }
+        drop(handlers);
+
+        let detail = Object::new();
+        let _ = Reflect::set(&detail, &JsValue::from_str("code"), &JsValue::from(HEARTBEAT_TIMEOUT_CODE));
+        let _ = Reflect::set(&detail, &JsValue::from_str("reason"), &JsValue::from_str("heartbeat timeout"));
+        let _ = Reflect::set(&detail, &JsValue::from_str("wasClean"), &JsValue::from_bool(false));
+        Self::on_closed(this, generation, detail.into());
+    }
+
+    /// 调用 `js` 回调, 调用前释放借用, 回调中可以再次调用客户端的方法
+    fn emit(this: &Rc<RefCell<Inner>>, event: &str, value: &JsValue) {
+        let listeners: Vec<Function> = this.borrow().listeners.iter().filter(|(name, _)| name == event).map(|(_, listener)| listener.clone()).collect();
+        for listener in listeners {
+            let _ = listener.call1(&JsValue::NULL, value);
+        }
+    }
+}
+
+/// 用于发送消息的 `Sink`, 关闭时同时关闭连接
+pub struct WebSocketSink {
+    client: WebSocketClient,
+}
+
+impl Sink<Message> for WebSocketSink {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: Message) -> Result<(), Self::Error> {
+        self.client.send_message(message)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.client.close(None, None);
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,425 @@
+/*!
+    基于 `Range` 请求的并行、可续传下载。
+    - 先通过 `HEAD` 获取 `Accept-Ranges`、`Content-Length`、`ETag`/`Last-Modified`, 不支持 `Range` 时直接下载整个文件
+    - 按 `rangeSize` 拆分成多个范围, 同时下载 `parallel` 个, 每个范围失败后单独重试
+    - 每个范围都带上 `If-Range`, 资源发生变化时返回 `Error::ResourceChanged`
+    - 已下载的范围保存在实例中, 失败后再次调用 `start` 只下载剩余的范围, 最后按顺序合并成一个 `Blob`
+    - 请求经过熔断、`HAR`、指标等 `tower` `Layer`, 与 `send` 相同
+    - 设置了 `verifyDigest` 时校验合并后的文件, 使用 `HEAD` 响应的 `Repr-Digest`、`Digest`, 不一致时丢弃已下载的范围
+*/
+
+use crate::client::timer;
+use crate::error::Error;
+use crate::instance::HttpInstance;
+use crate::request::HttpRequest;
+use crate::response::digest::{self, Hash, VerifyDigest};
+use crate::send::HttpClient;
+use crate::{HttpRequestOptions, HttpRequestType, HttpResponseOptions};
+use futures::stream::{self, TryStreamExt};
+use http::HeaderMap;
+use js_sys::{Array, Function, Object, Promise};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::{Blob, BlobPropertyBag};
+
+/// 默认的范围大小, 8M
+const RANGE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// 默认的并行数
+const PARALLEL: usize = 4;
+
+/// `options` 中 `opts` 和 `request` 以外的字段
+const DOWNLOAD_FIELDS: &[&str] = &["parallel", "rangeSize", "retryDelays", "onProgress"];
+
+/// 按 `range_size` 拆分, 返回每个范围的 `[start, end)`
+pub fn plan_ranges(size: u64, range_size: u64) -> Vec<(u64, u64)> {
+    let range_size = range_size.max(1);
+    (0..size.div_ceil(range_size)).map(|index| (index * range_size, ((index + 1) * range_size).min(size))).collect()
+}
+
+/// `Range` 请求头, `end` 不包含在内
+#[doc = "https://www.rfc-editor.org/rfc/rfc9110#section-14.2"]
+pub fn range_header(start: u64, end: u64) -> String {
+    format!("bytes={}-{}", start, end.saturating_sub(1))
+}
+
+/// 解析 `Content-Range: bytes 0-99/1000`, 返回 `[start, end)` 及总大小(未知时为 `None`)
+#[doc = "https://www.rfc-editor.org/rfc/rfc9110#section-14.4"]
+pub fn parse_content_range(value: &str) -> Option<(u64, u64, Option<u64>)> {
+    let value = value.trim().strip_prefix("bytes")?.trim();
+    let (range, size) = value.split_once('/')?;
+    let (start, end) = range.trim().split_once('-')?;
+    let start = start.trim().parse::<u64>().ok()?;
+    let end = end.trim().parse::<u64>().ok()? + 1;
+    let size = match size.trim() {
+        "*" => None,
+        size => Some(size.parse::<u64>().ok()?),
+    };
+
+    Some((start, end, size))
+}
+
+/// `If-Range` 只能使用强 `ETag` 或 `Last-Modified`
+#[doc = "https://www.rfc-editor.org/rfc/rfc9110#section-13.1.5"]
+pub fn if_range_validator(etag: Option<&str>, last_modified: Option<&str>) -> Option<String> {
+    match etag.filter(|etag| !etag.trim().is_empty()) {
+        Some(etag) if !etag.trim_start().starts_with("W/") => Some(etag.trim().to_string()),
+        Some(_) => None,
+        None => last_modified.filter(|last_modified| !last_modified.trim().is_empty()).map(|last_modified| last_modified.trim().to_string()),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub parallel: usize,        // 同时下载的范围数
+    pub range_size: u64,        // 每个范围的大小
+    pub retry_delays: Vec<u32>, // 每个范围失败后的重试间隔(毫秒)
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            parallel: PARALLEL,
+            range_size: RANGE_SIZE,
+            retry_delays: vec![0, 1000, 3000, 5000],
+        }
+    }
+}
+
+/// `HEAD` 的结果
+#[derive(Debug, Clone, Default)]
+struct Probe {
+    size: Option<u64>,
+    ranges: bool, // 是否支持 `Range`
+    etag: Option<String>,
+    validator: Option<String>, // `If-Range` 的值
+    content_type: String,
+    digests: Vec<Hash>, // `Repr-Digest`、`Digest` 中整个文件的摘要
+    content_encoding: Option<String>,
+}
+
+#[derive(Default)]
+struct DownloadState {
+    size: Option<u64>,
+    validator: Option<String>,
+    ranges: BTreeMap<u64, (u64, Blob)>, // 已下载的范围, `start` => (`end`, `Blob`)
+    version: u32,                       // 丢弃已下载的范围时递增, 之前发出的范围请求的结果被忽略
+    aborted: bool,
+}
+
+impl DownloadState {
+    /// 丢弃已下载的范围
+    fn reset(&mut self) {
+        self.ranges.clear();
+        self.version += 1;
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Download {
+    url: String,
+    options: DownloadOptions,
+    http_options: HttpRequestOptions,
+    request: HttpRequest,
+    on_progress: Option<Function>,
+    state: Rc<RefCell<DownloadState>>,
+}
+
+#[wasm_bindgen]
+impl Download {
+    /**
+      创建下载, `options` 中可以设置 `parallel`、`rangeSize`、`retryDelays`、`onProgress`, 以及 `opts` 和 `request` 的字段
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String, options: JsValue) -> Result<Download, JsValue> {
+        let (_, http_options, request) = HttpInstance::get_config(&options, DOWNLOAD_FIELDS)?;
+        let mut download_options = DownloadOptions::default();
+        let mut on_progress: Option<Function> = None;
+
+        if let Some(obj) = options.dyn_ref::<Object>() {
+            let number = |name: &str| HttpClient::get_value(obj, name).and_then(|value| value.as_f64()).map(|value| value.max(0.0));
+            if let Some(parallel) = number("parallel") {
+                download_options.parallel = (parallel as usize).max(1);
+            }
+
+            if let Some(range_size) = number("rangeSize").filter(|range_size| *range_size >= 1.0) {
+                download_options.range_size = range_size as u64;
+            }
+
+            if let Some(retry_delays) = HttpClient::get_value(obj, "retryDelays") {
+                download_options.retry_delays = Array::from(&retry_delays).iter().filter_map(|delay| delay.as_f64()).map(|delay| delay.max(0.0) as u32).collect();
+            }
+
+            on_progress = HttpClient::get_value(obj, "onProgress").and_then(|on_progress| on_progress.dyn_into::<Function>().ok());
+        }
+
+        let mut download = Download::from_options(&url, download_options, http_options);
+        download.request = request;
+        download.on_progress = on_progress;
+        Ok(download)
+    }
+
+    /**
+      开始或继续下载, 返回 `Blob`
+    */
+    #[wasm_bindgen(js_name = start)]
+    pub fn start_js(&self) -> Promise {
+        let download = self.clone();
+        future_to_promise(async move { download.start().await.map(JsValue::from).map_err(|err| JsValue::from_str(&err.to_string())) })
+    }
+
+    /**
+      暂停下载, 正在下载的范围完成后停止, 之后可以再次调用 `start` 继续
+    */
+    pub fn abort(&self) {
+        self.state.borrow_mut().aborted = true;
+    }
+
+    /**
+      已下载的大小
+    */
+    #[wasm_bindgen(getter)]
+    pub fn loaded(&self) -> f64 {
+        self.state.borrow().ranges.iter().map(|(start, (end, _))| end - start).sum::<u64>() as f64
+    }
+}
+
+impl Download {
+    pub fn from_options(url: &str, options: DownloadOptions, http_options: HttpRequestOptions) -> Self {
+        Self {
+            url: url.to_string(),
+            options,
+            http_options,
+            request: HttpRequest::default(),
+            on_progress: None,
+            state: Rc::new(RefCell::new(DownloadState::default())),
+        }
+    }
+
+    /// 开始或继续下载
+    pub async fn start(&self) -> Result<Blob, Error> {
+        self.state.borrow_mut().aborted = false;
+
+        let probe = self.probe().await?;
+        let size = match probe.size.filter(|size| probe.ranges && *size > 0) {
+            Some(size) => size,
+            None => return self.fetch_all().await,
+        };
+
+        // 资源发生变化时丢弃已下载的范围
+        let (pending, version): (Vec<(u64, u64)>, u32) = {
+            let mut state = self.state.borrow_mut();
+            if state.size != Some(size) || state.validator != probe.validator {
+                state.reset();
+                state.size = Some(size);
+                state.validator = probe.validator.clone();
+            }
+
+            let pending = plan_ranges(size, self.options.range_size)
+                .into_iter()
+                .filter(|(start, end)| state.ranges.get(start).map(|(stored, _)| stored != end).unwrap_or(true))
+                .collect();
+            (pending, state.version)
+        };
+
+        self.progress(self.loaded(), size as f64);
+        stream::iter(pending.into_iter().map(Ok::<(u64, u64), Error>))
+            .try_for_each_concurrent(self.options.parallel, |(start, end)| {
+                let probe = &probe;
+                async move {
+                    let blob = self.fetch_range_with_retry(start, end, probe).await?;
+                    let mut state = self.state.borrow_mut();
+                    // 请求期间丢弃了已下载的范围(资源发生变化)
+                    if state.version != version {
+                        return Err(Error::ResourceChanged(format!("range `{}` was downloaded from a previous version", range_header(start, end))));
+                    }
+
+                    state.ranges.insert(start, (end, blob));
+                    drop(state);
+                    self.progress(self.loaded(), size as f64);
+                    Ok(())
+                }
+            })
+            .await?;
+
+        // 按顺序合并
+        let parts: Array = self.state.borrow().ranges.values().map(|(_, blob)| JsValue::from(blob)).collect();
+        let bag = BlobPropertyBag::new();
+        bag.set_type(&probe.content_type);
+        let blob = Blob::new_with_blob_sequence_and_options(&parts, &bag).map_err(Error::js_error)?;
+
+        // 校验失败时下次重新下载
+        let hashes = match &self.http_options.verify_digest {
+            Some(VerifyDigest::Expected(hashes)) => Some(hashes),
+            Some(VerifyDigest::Header) => Some(&probe.digests),
+            None => None,
+        };
+
+        if let Some(hashes) = hashes {
+            // 响应头中的摘要按编码后的内容计算
+            let content_encoding = probe.content_encoding.as_deref().filter(|_| self.http_options.verify_digest == Some(VerifyDigest::Header));
+            let verified = match digest::check_encoding(content_encoding) {
+                Ok(()) => digest::verify_blob(&blob, hashes).await,
+                Err(err) => Err(err),
+            };
+
+            if let Err(err) = verified {
+                self.state.borrow_mut().reset();
+                return Err(err);
+            }
+        }
+
+        Ok(blob)
+    }
+
+    /// 获取大小、是否支持 `Range` 及 `ETag`
+    async fn probe(&self) -> Result<Probe, Error> {
+        let response = self.fetch("HEAD", Vec::new(), false).await?;
+        if !response.ok {
+            return Ok(Probe::default());
+        }
+
+        let header = |name: &str| get_header(&response.headers, name);
+        let etag = header("etag");
+        Ok(Probe {
+            size: header("content-length").and_then(|size| size.trim().parse::<u64>().ok()),
+            ranges: header("accept-ranges").map(|ranges| ranges.split(',').any(|unit| unit.trim().eq_ignore_ascii_case("bytes"))).unwrap_or(false),
+            validator: if_range_validator(etag.as_deref(), header("last-modified").as_deref()),
+            etag,
+            content_type: header("content-type").unwrap_or_default(),
+            digests: digest::from_headers(&["repr-digest", "digest"], header),
+            content_encoding: header("content-encoding"),
+        })
+    }
+
+    /// 不支持 `Range` 时下载整个文件
+    async fn fetch_all(&self) -> Result<Blob, Error> {
+        // 由 `Call::exec` 校验摘要
+        let response = self.fetch("GET", Vec::new(), true).await?;
+        if !response.ok {
+            return Err(Error::Error(format!("download failed with status {} !", response.status_code)));
+        }
+
+        let blob = Self::read_blob(response)?;
+        self.progress(blob.size(), blob.size());
+        Ok(blob)
+    }
+
+    async fn fetch_range_with_retry(&self, start: u64, end: u64, probe: &Probe) -> Result<Blob, Error> {
+        let mut attempt = 0;
+        loop {
+            if self.state.borrow().aborted {
+                return Err(Error::Error("download aborted !".to_string()));
+            }
+
+            let err = match self.fetch_range(start, end, probe).await {
+                Ok(blob) => return Ok(blob),
+                // 资源发生变化时重试没有意义
+                Err(err @ Error::ResourceChanged(_)) => {
+                    self.state.borrow_mut().reset();
+                    return Err(err);
+                }
+                Err(err) => err,
+            };
+
+            let delay = match self.options.retry_delays.get(attempt) {
+                Some(delay) => *delay,
+                None => return Err(err),
+            };
+
+            attempt += 1;
+            timer::sleep(delay).await;
+        }
+    }
+
+    async fn fetch_range(&self, start: u64, end: u64, probe: &Probe) -> Result<Blob, Error> {
+        let mut headers = vec![("Range", range_header(start, end))];
+        if let Some(validator) = &probe.validator {
+            headers.push(("If-Range", validator.clone()));
+        }
+
+        let response = self.fetch("GET", headers, false).await?;
+        match response.status_code {
+            206 => {}
+            // `If-Range` 不匹配时服务端返回整个资源
+            200 => return Err(Error::ResourceChanged(format!("expected a partial response for `{}`", range_header(start, end)))),
+            status => return Err(Error::Error(format!("download range `{}` failed with status {} !", range_header(start, end), status))),
+        }
+
+        let header = |name: &str| get_header(&response.headers, name);
+        if let (Some(expected), Some(etag)) = (&probe.etag, header("etag")) {
+            if expected.trim() != etag.trim() {
+                return Err(Error::ResourceChanged(format!("etag changed from {} to {}", expected, etag)));
+            }
+        }
+
+        if let Some((range_start, range_end, size)) = header("content-range").as_deref().and_then(parse_content_range) {
+            if range_start != start || range_end != end {
+                return Err(Error::Error(format!("download range `{}` got unexpected content range `bytes {}-{}` !", range_header(start, end), range_start, range_end - 1)));
+            }
+
+            if let (Some(size), Some(expected)) = (size, probe.size) {
+                if size != expected {
+                    return Err(Error::ResourceChanged(format!("size changed from {} to {}", expected, size)));
+                }
+            }
+        }
+
+        let blob = Self::read_blob(response)?;
+        if blob.size() as u64 != end - start {
+            return Err(Error::Error(format!("download range `{}` got {} bytes !", range_header(start, end), blob.size())));
+        }
+
+        Ok(blob)
+    }
+
+    /// 经过 `HttpClient::call` 的 `Layer` 发送, `verify` 为 `false` 时不校验摘要(范围请求在合并后校验)
+    async fn fetch(&self, method: &str, headers: Vec<(&str, String)>, verify: bool) -> Result<HttpResponseOptions, Error> {
+        let mut options = self.http_options.clone();
+        options.url = self.url.clone();
+        options.method = Some(method.to_string());
+        options.request_type = None;
+        options.data = None;
+        options.response_type = Some(HttpRequestType::Blob);
+        options.raw_blob = true;
+        options.schema = None;
+        if !verify {
+            options.verify_digest = None;
+        }
+
+        let mut request_headers = match options.headers.take() {
+            Some(Value::Object(headers)) => headers,
+            _ => Map::new(),
+        };
+
+        for (name, value) in headers {
+            request_headers.retain(|key, _| !key.eq_ignore_ascii_case(name));
+            request_headers.insert(name.to_string(), Value::String(value));
+        }
+
+        options.headers = Some(Value::Object(request_headers));
+        HttpClient::call(options, self.request.clone()).await
+    }
+
+    fn read_blob(mut response: HttpResponseOptions) -> Result<Blob, Error> {
+        response.blobs.pop().map(|(_, blob)| blob).ok_or(Error::MissingResponseBody)
+    }
+
+    /// 回调 `onProgress(loaded, total)`
+    fn progress(&self, loaded: f64, total: f64) {
+        if let Some(on_progress) = &self.on_progress {
+            let _ = on_progress.call2(&JsValue::NULL, &JsValue::from_f64(loaded), &JsValue::from_f64(total));
+        }
+    }
+}
+
+/// 同名的多个值用 `, ` 连接, 与 `Headers.get` 相同
+fn get_header(headers: &HeaderMap, name: &str) -> Option<String> {
+    let values: Vec<&str> = headers.get_all(name).iter().filter_map(|value| value.to_str().ok()).collect();
+    Some(values.join(", ")).filter(|_| !values.is_empty())
+}
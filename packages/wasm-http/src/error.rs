@@ -1,8 +1,13 @@
 //! custom error
 
-use js_sys::Object;
+use crate::client::fetch;
+use crate::graphql::GraphQLError;
+use crate::jsonrpc::JsonRpcError;
+use crate::schema::Violation;
+use js_sys::{Object, TypeError};
 use thiserror::Error;
 use wasm_bindgen::{JsCast, JsValue};
+use web_sys::DomException;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -13,19 +18,103 @@ pub enum Error {
     #[error("js api error: {0}")]
     JsError(String),
 
+    /// 网络错误, `fetch` 失败时为 `TypeError`, 如断网、`DNS` 解析失败、`CORS` 被拒绝
+    #[error("network error: {0}")]
+    Network(String),
+
+    /// 请求超时, 由 `timeout` 或调用方传入的 `AbortSignal.timeout()` 取消
+    #[error("request timeout")]
+    Timeout,
+
     #[error("http error")]
     HttpError(#[from] http::Error),
 
     /// Missing response body in HTTP call
     #[error("missing response body in HTTP call")]
     MissingResponseBody,
+
+    /// `no-cors` 跨域请求或 `Redirect::Manual` 遇到重定向时得到的 `opaque` 响应, 无法读取 `headers` 和 `body`
+    #[error("opaque response, the status, headers and body of a `no-cors` cross-origin or manual redirect response are not readable")]
+    OpaqueResponse,
+
+    /// 不支持的编码
+    #[error("unsupported encoding: {0}")]
+    UnsupportedEncoding(String),
+
+    /// 响应不符合 `schema`
+    #[error("response validation failed: {}", .0.iter().map(|violation| violation.to_string()).collect::<Vec<String>>().join("; "))]
+    Validation(Vec<Violation>),
+
+    /// `GraphQL` 响应中的 `errors`, `HTTP` 状态码为 `200` 时也可能出现
+    #[error("graphql error: {}", .0.iter().map(|err| err.to_string()).collect::<Vec<String>>().join("; "))]
+    GraphQL(Vec<GraphQLError>),
+
+    /// `JSON-RPC` 响应中的 `error`
+    #[error("json-rpc error: {0}")]
+    JsonRpc(JsonRpcError),
+
+    /// 分段下载期间资源发生了变化(`ETag` 或 `Last-Modified` 不一致), 已下载的部分无法继续使用
+    #[error("resource changed during download: {0}")]
+    ResourceChanged(String),
+
+    /// 熔断器处于 `open` 状态, 请求没有发送, `retry_after` 为剩余的冷却时间(毫秒)
+    #[error("circuit open for `{key}`, retry after {retry_after}ms")]
+    CircuitOpen { key: String, retry_after: f64 },
+
+    /// 响应 `body` 与 `Content-Digest`、`Digest` 响应头或调用方传入的摘要不一致, 或缺少摘要
+    #[error("integrity mismatch: {0}")]
+    IntegrityMismatch(String),
+
+    /// `opts` 或 `request` 中的字段不合法, 包含所有字段的错误
+    #[error("invalid options: {}", .0.join("; "))]
+    InvalidOptions(Vec<String>),
 }
 
 impl Error {
     pub(crate) fn js_error(value: JsValue) -> Self {
+        // 超时取消请求时的 `reason`, 读取 `body` 时超时同样返回
+        if value.dyn_ref::<DomException>().is_some_and(|err| err.name() == fetch::TIMEOUT_ERROR) {
+            return Self::Timeout;
+        }
+
         let message = js_object_display(&value);
         Self::JsError(message)
     }
+
+    /// `fetch` 失败时的错误, `TypeError` 为网络错误
+    pub(crate) fn fetch_error(value: JsValue) -> Self {
+        if value.is_instance_of::<TypeError>() {
+            return Self::Network(js_object_display(&value));
+        }
+
+        Self::js_error(value)
+    }
+
+    /// 复制一份错误, 用于把同一个错误分发给多个调用方, 保留原来的类型; `HttpError` 无法复制, 转换为相同信息的 `Error`
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::Error(message) => Self::Error(message.clone()),
+            Self::JsError(message) => Self::JsError(message.clone()),
+            Self::Network(message) => Self::Network(message.clone()),
+            Self::Timeout => Self::Timeout,
+            Self::HttpError(_) => Self::Error(self.to_string()),
+            Self::MissingResponseBody => Self::MissingResponseBody,
+            Self::OpaqueResponse => Self::OpaqueResponse,
+            Self::UnsupportedEncoding(encoding) => Self::UnsupportedEncoding(encoding.clone()),
+            Self::Validation(violations) => Self::Validation(violations.clone()),
+            Self::GraphQL(errors) => Self::GraphQL(errors.clone()),
+            Self::JsonRpc(error) => Self::JsonRpc(error.clone()),
+            Self::ResourceChanged(message) => Self::ResourceChanged(message.clone()),
+            Self::CircuitOpen { key, retry_after } => Self::CircuitOpen { key: key.clone(), retry_after: *retry_after },
+            Self::IntegrityMismatch(message) => Self::IntegrityMismatch(message.clone()),
+            Self::InvalidOptions(errors) => Self::InvalidOptions(errors.clone()),
+        }
+    }
+
+    /// 是否为网络错误或超时, 主动取消、响应校验失败等不能说明服务异常
+    pub(crate) fn is_network_error(&self) -> bool {
+        matches!(self, Self::Network(_) | Self::Timeout)
+    }
 }
 
 fn js_object_display(option: &JsValue) -> String {
@@ -0,0 +1,105 @@
+//! 故障注入的 `tower` `Layer`, 未命中规则时直接调用内部的服务
+
+use crate::client::call::Call;
+use crate::client::timer;
+use crate::error::Error;
+use crate::fault::{self, FaultKind};
+use crate::{HttpRequestOptions, HttpResponseOptions};
+use http::{Request, Response, StatusCode};
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::Layer;
+use tower_service::Service;
+
+#[derive(Debug, Clone)]
+pub(crate) struct FaultLayer {
+    method: &'static str,
+    url: String,
+    timeout: u32, // 请求的超时时间(毫秒)
+}
+
+impl FaultLayer {
+    pub(crate) fn new(options: &HttpRequestOptions) -> Self {
+        let (method, _) = Call::prepare_method(options);
+        Self {
+            method,
+            url: options.url.clone(),
+            timeout: Call::prepare_request_timeout(options.timeout).max(0) as u32 * 1000,
+        }
+    }
+}
+
+impl<S> Layer<S> for FaultLayer {
+    type Service = FaultService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        FaultService { inner, layer: self.clone() }
+    }
+}
+
+pub(crate) struct FaultService<S> {
+    inner: S,
+    layer: FaultLayer,
+}
+
+impl<S> Service<Request<Value>> for FaultService<S>
+where
+    S: Service<Request<Value>, Response = Response<HttpResponseOptions>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = Response<HttpResponseOptions>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Value>) -> Self::Future {
+        let rule = match fault::pick(self.layer.method, &self.layer.url) {
+            Some(rule) => rule,
+            None => return Box::pin(self.inner.call(request)),
+        };
+
+        // 只有延迟及截断时才发送请求
+        let future = match rule.kind {
+            None | Some(FaultKind::Truncate(_)) => Some(self.inner.call(request)),
+            _ => None,
+        };
+
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            if rule.latency > 0 {
+                timer::sleep(rule.latency).await;
+            }
+
+            match (rule.kind, future) {
+                (Some(FaultKind::Timeout(timeout)), _) => {
+                    timer::sleep(timeout.unwrap_or(layer.timeout)).await;
+                    Err(Error::Timeout)
+                }
+                (Some(FaultKind::Error(message)), _) => Err(Error::Network(message)),
+                (Some(FaultKind::Status(status_code, body)), _) => {
+                    let response = HttpResponseOptions {
+                        status_code,
+                        status_text: StatusCode::from_u16(status_code).ok().and_then(|status| status.canonical_reason()).unwrap_or_default().to_string(),
+                        ok: (200..300).contains(&status_code),
+                        url: layer.url,
+                        body,
+                        ..Default::default()
+                    };
+                    Response::builder().status(status_code).body(response).map_err(Error::HttpError)
+                }
+                (Some(FaultKind::Truncate(ratio)), Some(future)) => {
+                    let mut response = future.await?;
+                    fault::truncate(response.body_mut(), ratio)?;
+                    Ok(response)
+                }
+                (_, Some(future)) => future.await,
+                (_, None) => unreachable!(),
+            }
+        })
+    }
+}
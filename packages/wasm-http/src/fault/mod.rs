@@ -0,0 +1,218 @@
+/*!
+    故障注入, 用于测试页面在延迟、失败、错误状态码、截断的响应及超时下的表现, 只在开启 `fault-injection` feature 时编译。
+    调用 `setFaultInjection(rules)` 后生效, 按顺序匹配第一条规则, 可以通过 `setFaultInjectionEnabled` 在运行时开关。
+    - `url`: 匹配的地址, `*` 匹配任意字符, 以 `/` 开头时只匹配路径及 `query`, 为空时匹配所有请求
+    - `method`: 匹配的请求方法, 为空时匹配所有方法
+    - `probability`: 命中后注入的概率, `0-1`, 默认为 `1`
+    - `latency`: 发送前等待的毫秒数, 可以和其他故障同时使用
+    - `timeout`: 等待请求的超时时间(或指定的毫秒数)后以 `Error::Timeout` 失败
+    - `error`: 不发送请求, 以 `Error::Network` 失败, 错误信息默认为 `TypeError: Failed to fetch`
+    - `status`: 不发送请求, 返回该状态码, `body` 为规则中的 `body`
+    - `truncate`: 正常发送请求, 响应 `body` 只保留前面的比例(`0-1`), `JSON` 响应会解析失败
+*/
+
+pub(crate) mod layer;
+
+use crate::error::Error;
+use crate::send::HttpClient;
+use crate::HttpResponseOptions;
+use js_sys::{Array, Object};
+use serde_json::Value;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static FAULTS: RefCell<Option<Faults>> = const { RefCell::new(None) };
+}
+
+const NETWORK_ERROR: &str = "TypeError: Failed to fetch";
+
+/// 故障类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaultKind {
+    Error(String),        // 请求失败
+    Status(u16, Value),   // 返回状态码及 `body`
+    Truncate(f64),        // 截断响应 `body`
+    Timeout(Option<u32>), // 超时, 默认使用请求的超时时间
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultRule {
+    pub url: Option<String>,     // 地址
+    pub method: Option<String>,  // 请求方法
+    pub probability: f64,        // 注入的概率
+    pub latency: u32,            // 延迟(毫秒)
+    pub kind: Option<FaultKind>, // 为 `None` 时只有延迟
+}
+
+impl Default for FaultRule {
+    fn default() -> Self {
+        Self {
+            url: None,
+            method: None,
+            probability: 1.0,
+            latency: 0,
+            kind: None,
+        }
+    }
+}
+
+impl FaultRule {
+    /// 是否匹配请求
+    pub fn matches(&self, method: &str, url: &str) -> bool {
+        let method_matched = self.method.as_ref().map(|expected| expected.eq_ignore_ascii_case(method)).unwrap_or(true);
+        let url_matched = match &self.url {
+            Some(pattern) if pattern.starts_with('/') => glob(pattern, path(url)),
+            Some(pattern) => glob(pattern, url),
+            None => true,
+        };
+
+        method_matched && url_matched
+    }
+}
+
+/// 故障注入规则
+#[derive(Debug, Clone, Default)]
+pub struct Faults {
+    pub enabled: bool,
+    pub rules: Vec<FaultRule>,
+}
+
+impl Faults {
+    pub fn new(rules: Vec<FaultRule>) -> Self {
+        Self { enabled: true, rules }
+    }
+
+    /// 按顺序查找第一条匹配的规则, 未命中概率时返回 `None`, `random` 返回 `[0, 1)` 的随机数
+    pub fn pick(&self, method: &str, url: &str, random: impl FnOnce() -> f64) -> Option<FaultRule> {
+        if !self.enabled {
+            return None;
+        }
+
+        let rule = self.rules.iter().find(|rule| rule.matches(method, url))?;
+        if rule.probability >= 1.0 || random() < rule.probability {
+            Some(rule.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// `*` 匹配任意字符
+pub fn glob(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || text.len() < first.len() + last.len() || !text.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// 去掉协议和域名
+fn path(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|index| &rest[index..]).unwrap_or("/"),
+        None => url,
+    }
+}
+
+/// 截断响应 `body`, 已解析的 `JSON` 截断后无法解析, 返回 `Error::MissingResponseBody`
+pub fn truncate(response: &mut HttpResponseOptions, ratio: f64) -> Result<(), Error> {
+    let cut = |text: &mut String| {
+        let mut length = (text.len() as f64 * ratio.clamp(0.0, 1.0)) as usize;
+        while !text.is_char_boundary(length) {
+            length -= 1;
+        }
+        text.truncate(length);
+    };
+
+    if let Some(raw) = response.raw.as_mut() {
+        cut(raw);
+        return Ok(());
+    }
+
+    match &mut response.body {
+        Value::Null => Ok(()),
+        Value::String(body) => {
+            cut(body);
+            Ok(())
+        }
+        _ if ratio >= 1.0 => Ok(()),
+        _ => Err(Error::MissingResponseBody),
+    }
+}
+
+/// 故障注入未开启时返回 `None`
+pub(crate) fn pick(method: &str, url: &str) -> Option<FaultRule> {
+    FAULTS.with(|faults| faults.borrow().as_ref().and_then(|faults| faults.pick(method, url, js_sys::Math::random)))
+}
+
+fn parse_rule(rule: &JsValue) -> Option<FaultRule> {
+    let obj = rule.dyn_ref::<Object>()?;
+    let number = |name: &str| HttpClient::get_value(obj, name).and_then(|value| value.as_f64()).map(|value| value.max(0.0));
+    let string = |name: &str| HttpClient::get_value(obj, name).and_then(|value| value.as_string()).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+
+    let kind = if let Some(timeout) = HttpClient::get_value(obj, "timeout").filter(|timeout| timeout.as_bool() == Some(true) || timeout.as_f64().is_some()) {
+        Some(FaultKind::Timeout(timeout.as_f64().map(|timeout| timeout.max(0.0) as u32)))
+    } else if let Some(error) = HttpClient::get_value(obj, "error").filter(|error| error.as_bool() == Some(true) || error.as_string().is_some()) {
+        Some(FaultKind::Error(error.as_string().unwrap_or_else(|| NETWORK_ERROR.to_string())))
+    } else if let Some(status) = number("status") {
+        let body = HttpClient::get_value(obj, "body").and_then(|body| serde_wasm_bindgen::from_value(body).ok()).unwrap_or(Value::Null);
+        Some(FaultKind::Status(status as u16, body))
+    } else {
+        number("truncate").map(|ratio| FaultKind::Truncate(ratio.min(1.0)))
+    };
+
+    Some(FaultRule {
+        url: string("url"),
+        method: string("method"),
+        probability: number("probability").map(|probability| probability.min(1.0)).unwrap_or(1.0),
+        latency: number("latency").unwrap_or_default() as u32,
+        kind,
+    })
+}
+
+/**
+  设置故障注入规则并开启, 为 `null` 时清空规则
+  `rules` 为规则数组, 每条规则可以设置 `url`、`method`、`probability`、`latency`、`timeout`、`error`、`status`、`body`、`truncate`
+*/
+#[wasm_bindgen(js_name = setFaultInjection)]
+pub fn set_fault_injection(rules: JsValue) -> Result<(), JsValue> {
+    if rules.is_null() || rules.is_undefined() {
+        FAULTS.with(|faults| *faults.borrow_mut() = None);
+        return Ok(());
+    }
+
+    if !Array::is_array(&rules) {
+        return Err(JsValue::from_str(&Error::Error("`rules` is not a array !".to_string()).to_string()));
+    }
+
+    let rules = Array::from(&rules).iter().filter_map(|rule| parse_rule(&rule)).collect();
+    FAULTS.with(|faults| *faults.borrow_mut() = Some(Faults::new(rules)));
+    Ok(())
+}
+
+/**
+  开关故障注入, 保留已设置的规则
+*/
+#[wasm_bindgen(js_name = setFaultInjectionEnabled)]
+pub fn set_fault_injection_enabled(enabled: bool) {
+    FAULTS.with(|faults| {
+        if let Some(faults) = faults.borrow_mut().as_mut() {
+            faults.enabled = enabled;
+        }
+    });
+}
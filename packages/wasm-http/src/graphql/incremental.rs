@@ -0,0 +1,150 @@
+/*!
+    `@defer`/`@stream` 的增量响应。
+    服务端以 `multipart/mixed` 分批返回结果(由 `multipart::MultipartParser` 解析), 每个 `part` 是一个 `JSON`: 第一个包含 `data`, 之后的包含 `incremental` 列表, `hasNext` 为 `false` 时结束。
+    同时兼容旧格式, 即 `path`、`data`/`items` 直接位于顶层。
+*/
+
+use serde_json::{Map, Value};
+
+#[doc = "https://github.com/graphql/graphql-over-http/blob/main/rfcs/IncrementalDelivery.md"]
+pub const ACCEPT: &str = "multipart/mixed;deferSpec=20220824, application/json";
+
+/// 查询中是否使用了 `@defer` 或 `@stream`
+pub fn is_incremental(query: &str) -> bool {
+    query.contains("@defer") || query.contains("@stream")
+}
+
+/// 把增量结果合并到 `result` 中, `result` 为 `{data, errors, extensions, hasNext}`
+pub fn apply(result: &mut Value, payload: Value) {
+    let mut payload = match payload {
+        Value::Object(payload) => payload,
+        _ => return,
+    };
+
+    if !result.is_object() {
+        *result = Value::Object(Map::new());
+    }
+
+    let has_next = payload.remove("hasNext");
+    let errors = payload.remove("errors");
+    let extensions = payload.remove("extensions");
+
+    match payload.remove("incremental") {
+        Some(Value::Array(items)) => {
+            for item in items {
+                apply(result, item);
+            }
+        }
+        _ => {
+            let path = payload.remove("path");
+            let data = payload.remove("data");
+            let items = payload.remove("items");
+
+            match (path, data, items) {
+                // 旧格式或 `incremental` 中的 `@defer`
+                (Some(Value::Array(path)), Some(data), _) => {
+                    if let Some(target) = at_path(data_mut(result), &path) {
+                        merge(target, data);
+                    }
+                }
+                // `@stream`, `path` 的最后一项为第一个元素的下标
+                (Some(Value::Array(path)), None, Some(Value::Array(items))) => extend(data_mut(result), &path, items),
+                // 第一个结果
+                (None, Some(data), _) => merge(data_mut(result), data),
+                _ => {}
+            }
+        }
+    }
+
+    let result = result.as_object_mut().unwrap();
+    if let Some(Value::Array(errors)) = errors {
+        if !errors.is_empty() {
+            match result.entry("errors").or_insert_with(|| Value::Array(Vec::new())) {
+                Value::Array(list) => list.extend(errors),
+                value => *value = Value::Array(errors),
+            }
+        }
+    }
+
+    if let Some(extensions) = extensions.filter(|extensions| !extensions.is_null()) {
+        merge(result.entry("extensions").or_insert(Value::Null), extensions);
+    }
+
+    if let Some(has_next) = has_next {
+        result.insert(String::from("hasNext"), has_next);
+    }
+}
+
+fn data_mut(result: &mut Value) -> &mut Value {
+    result.as_object_mut().unwrap().entry("data").or_insert(Value::Null)
+}
+
+/// 按照 `path` 找到对应的值, 不存在时创建; 下标超出列表长度时返回 `None`, 只允许在末尾追加一项
+fn at_path<'a>(value: &'a mut Value, path: &[Value]) -> Option<&'a mut Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        Value::Number(index) => {
+            let index = index.as_u64()? as usize;
+            if !value.is_array() {
+                *value = Value::Array(Vec::new());
+            }
+
+            let list = value.as_array_mut().unwrap();
+            if index == list.len() {
+                list.push(Value::Null);
+            }
+            list.get_mut(index)
+        }
+        segment => {
+            let key = segment.as_str().map(|key| key.to_string()).unwrap_or_else(|| segment.to_string());
+            if !value.is_object() {
+                *value = Value::Object(Map::new());
+            }
+
+            Some(value.as_object_mut().unwrap().entry(key).or_insert(Value::Null))
+        }
+    })
+}
+
+/// 深度合并对象, 其他类型直接覆盖
+fn merge(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+/// 把 `@stream` 的 `items` 写入列表, `path` 的最后一项为下标时从该下标开始, 否则追加; 下标超出列表长度时忽略
+fn extend(data: &mut Value, path: &[Value], items: Vec<Value>) {
+    let (list, start) = match path.split_last() {
+        Some((Value::Number(index), parent)) => (at_path(data, parent), index.as_u64().map(|index| index as usize)),
+        _ => (at_path(data, path), None),
+    };
+
+    let list = match list {
+        Some(list) => list,
+        None => return,
+    };
+
+    if !list.is_array() {
+        *list = Value::Array(Vec::new());
+    }
+
+    let list = list.as_array_mut().unwrap();
+    let start = start.unwrap_or(list.len());
+    if start > list.len() {
+        return;
+    }
+
+    for (offset, item) in items.into_iter().enumerate() {
+        let index = start + offset;
+        if index < list.len() {
+            list[index] = item;
+        } else {
+            list.push(item);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+/*!
+    `GraphQL` 客户端, 在 `send` 之上组装 `{query, variables}` 请求体, 并以 `JSON` 类型发送。
+    - 响应中的 `errors` 即使 `HTTP` 状态码为 `200` 也会作为 `Error::GraphQL` 返回, `errorPolicy` 为 `all` 时同时返回 `data` 和 `errors`
+    - 持久化查询(`APQ`): 先只发送查询的 `sha256`, 服务端没有缓存时再发送完整的查询
+    - `@defer`/`@stream`: 以 `multipart/mixed` 增量接收, 每收到一部分合并后回调 `onNext`
+*/
+
+pub mod incremental;
+
+use crate::error::Error;
+use crate::instance::HttpInstance;
+use crate::multipart::{Part, PartListener};
+use crate::request::HttpRequest;
+use crate::send::HttpClient;
+use crate::{HttpRequestOptions, HttpRequestType};
+use js_sys::{Function, Object, Promise, Reflect};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+/// `onNext` 回调
+type OnNext = Rc<dyn Fn(&GraphQLResponse)>;
+
+/// 查询中的操作及名称, 忽略注释、字符串、`fragment` 及参数中的内容, `{ ... }` 简写为匿名的 `query`
+fn operations(query: &str) -> Vec<(&'static str, Option<String>)> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut operations: Vec<(&'static str, Option<String>)> = Vec::new();
+    let mut depth = 0; // `{}` 及 `()` 的层级
+    let mut definition = false; // 是否在 `query`、`fragment` 等关键字之后, 还未进入 `{`
+    let mut expect_name = false; // 下一个名称为操作的名称
+    let mut index = 0;
+
+    while index < chars.len() {
+        let char = chars[index];
+        match char {
+            // 注释
+            '#' => {
+                while index < chars.len() && chars[index] != '\n' && chars[index] != '\r' {
+                    index += 1;
+                }
+                continue;
+            }
+            // 字符串及块字符串
+            '"' => {
+                let block = chars[index..].starts_with(&['"', '"', '"']);
+                index += if block { 3 } else { 1 };
+                while index < chars.len() {
+                    if block && chars[index..].starts_with(&['"', '"', '"']) {
+                        index += 3;
+                        break;
+                    }
+                    if !block && chars[index] == '"' {
+                        index += 1;
+                        break;
+                    }
+                    index += if chars[index] == '\\' { 2 } else { 1 };
+                }
+                continue;
+            }
+            '{' | '(' | '[' => {
+                if depth == 0 && char == '{' && !definition {
+                    operations.push(("query", None));
+                }
+                depth += 1;
+                expect_name = false;
+            }
+            '}' | ')' | ']' => {
+                depth -= 1;
+                if depth == 0 && char == '}' {
+                    definition = false;
+                }
+            }
+            char if char == '_' || char.is_ascii_alphabetic() => {
+                let start = index;
+                while index < chars.len() && (chars[index] == '_' || chars[index].is_ascii_alphanumeric()) {
+                    index += 1;
+                }
+
+                if depth == 0 {
+                    let name: String = chars[start..index].iter().collect();
+                    if expect_name {
+                        if let Some((_, operation)) = operations.last_mut() {
+                            *operation = Some(name);
+                        }
+                        expect_name = false;
+                    } else if !definition {
+                        let kind = match name.as_str() {
+                            "query" => Some("query"),
+                            "mutation" => Some("mutation"),
+                            "subscription" => Some("subscription"),
+                            _ => None,
+                        };
+
+                        definition = true;
+                        if let Some(kind) = kind {
+                            operations.push((kind, None));
+                            expect_name = true;
+                        }
+                    }
+                }
+                continue;
+            }
+            // `$`、`@`、`:` 等之后不再是操作的名称
+            char if !char.is_whitespace() && char != ',' => expect_name = false,
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    operations
+}
+
+/// 持久化查询的版本
+const PERSISTED_QUERY_VERSION: u32 = 1;
+
+/// `config` 中 `opts` 和 `request` 以外的字段
+const CONFIG_FIELDS: &[&str] = &["persistedQueries", "errorPolicy"];
+
+/// `query`、`mutate` 的 `opts` 中 `opts` 和 `request` 以外的字段
+const QUERY_FIELDS: &[&str] = &["operationName", "extensions", "errorPolicy", "onNext"];
+
+/// 请求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphQLRequest {
+    pub query: String,
+    #[serde(rename = "operationName", skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+}
+
+impl GraphQLRequest {
+    pub fn new(query: &str, variables: Option<Value>) -> Self {
+        Self {
+            query: query.to_string(),
+            variables,
+            ..Default::default()
+        }
+    }
+
+    /// 执行的操作的类型, 有多个操作时按 `operationName` 选择, 无法确定时返回 `None`
+    pub fn operation_type(&self) -> Option<&'static str> {
+        let operations = operations(&self.query);
+        let operation = match &self.operation_name {
+            Some(name) => operations.iter().find(|(_, operation)| operation.as_deref() == Some(name.as_str())),
+            None if operations.len() == 1 => operations.first(),
+            None => None,
+        };
+
+        operation.map(|(kind, _)| *kind)
+    }
+
+    /// 是否为 `mutation`, `mutation` 不使用 `GET` 发送; 无法确定执行的操作时, 包含 `mutation` 即视为 `mutation`
+    pub fn is_mutation(&self) -> bool {
+        match self.operation_type() {
+            Some(kind) => kind == "mutation",
+            None => operations(&self.query).iter().any(|(kind, _)| *kind == "mutation"),
+        }
+    }
+
+    /// 请求体, `hash` 不为空时带上持久化查询的 `extensions`, `include_query` 为 `false` 时只发送 `hash`
+    pub fn body(&self, hash: Option<&str>, include_query: bool) -> Value {
+        let mut body = Map::new();
+        if include_query || hash.is_none() {
+            body.insert(String::from("query"), Value::String(self.query.clone()));
+        }
+
+        if let Some(operation_name) = &self.operation_name {
+            body.insert(String::from("operationName"), Value::String(operation_name.clone()));
+        }
+
+        if let Some(variables) = &self.variables {
+            body.insert(String::from("variables"), variables.clone());
+        }
+
+        let mut extensions = match &self.extensions {
+            Some(Value::Object(extensions)) => extensions.clone(),
+            _ => Map::new(),
+        };
+
+        if let Some(hash) = hash {
+            extensions.insert(String::from("persistedQuery"), serde_json::json!({ "version": PERSISTED_QUERY_VERSION, "sha256Hash": hash }));
+        }
+
+        if !extensions.is_empty() {
+            body.insert(String::from("extensions"), Value::Object(extensions));
+        }
+
+        Value::Object(body)
+    }
+}
+
+/// 查询的 `sha256`, 用于持久化查询
+#[doc = "https://github.com/apollographql/apollo-link-persisted-queries#protocol"]
+pub fn hash_query(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+/// 错误的位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// 响应中的错误
+#[doc = "https://spec.graphql.org/October2021/#sec-Errors"]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+}
+
+impl GraphQLError {
+    /// `extensions.code`
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref().and_then(|extensions| extensions.get("code")).and_then(|code| code.as_str())
+    }
+
+    fn is_persisted_query_not_found(&self) -> bool {
+        self.message == "PersistedQueryNotFound" || self.code() == Some("PERSISTED_QUERY_NOT_FOUND")
+    }
+
+    fn is_persisted_query_not_supported(&self) -> bool {
+        self.message == "PersistedQueryNotSupported" || self.code() == Some("PERSISTED_QUERY_NOT_SUPPORTED")
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            return write!(f, "{}", self.message);
+        }
+
+        let path: Vec<String> = self.path.iter().map(|segment| segment.as_str().map(|segment| segment.to_string()).unwrap_or_else(|| segment.to_string())).collect();
+        write!(f, "{} (at {})", self.message, path.join("."))
+    }
+}
+
+/// 响应
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLResponse {
+    #[serde(default)]
+    pub data: Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<GraphQLError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Value>,
+    #[serde(rename = "hasNext", default, skip_serializing_if = "Option::is_none")]
+    pub has_next: Option<bool>,
+}
+
+impl GraphQLResponse {
+    /// 解析响应 `body`, 既没有 `data` 也没有 `errors` 时返回错误
+    pub fn from_body(status_code: u16, ok: bool, body: Value) -> Result<Self, Error> {
+        let is_graphql = body.as_object().map(|body| body.contains_key("data") || body.contains_key("errors")).unwrap_or(false);
+        if !is_graphql {
+            if !ok {
+                return Err(Error::Error(format!("graphql request failed with status {} !", status_code)));
+            }
+
+            return Err(Error::MissingResponseBody);
+        }
+
+        serde_json::from_value(body).map_err(|err| Error::Error(format!("invalid graphql response: {}", err)))
+    }
+
+    /// 有 `errors` 时返回 `Error::GraphQL`
+    pub fn into_result(self) -> Result<Self, Error> {
+        if self.errors.is_empty() {
+            return Ok(self);
+        }
+
+        Err(Error::GraphQL(self.errors))
+    }
+
+    fn to_js(&self) -> Result<JsValue, Error> {
+        self.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| Error::Error(err.to_string()))
+    }
+}
+
+/// 响应中有 `errors` 时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ErrorPolicy {
+    /// 返回 `Error::GraphQL`
+    #[default]
+    None,
+
+    /// 同时返回 `data` 和 `errors`
+    All,
+}
+
+impl ErrorPolicy {
+    pub fn get_error_policy(policy: String) -> ErrorPolicy {
+        match policy.trim().to_lowercase().as_str() {
+            "all" => ErrorPolicy::All,
+            _ => ErrorPolicy::None,
+        }
+    }
+}
+
+/// 持久化查询配置
+#[derive(Debug, Clone, Default)]
+pub struct PersistedQueries {
+    pub use_get: bool, // 只发送 `hash` 时是否使用 `GET`, 便于 `CDN` 缓存
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct GraphQLClient {
+    url: String,
+    options: HttpRequestOptions,
+    request: HttpRequest,
+    error_policy: ErrorPolicy,
+    persisted_queries: Option<PersistedQueries>,
+    persisted_supported: Rc<Cell<bool>>, // 服务端返回 `PersistedQueryNotSupported` 后不再使用持久化查询
+}
+
+#[wasm_bindgen]
+impl GraphQLClient {
+    /**
+      创建客户端, `config` 中可以包含 `opts` 和 `request` 的字段, 以及 `persistedQueries`、`errorPolicy`
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String, config: JsValue) -> Result<GraphQLClient, JsValue> {
+        let (_, options, request) = HttpInstance::get_config(&config, CONFIG_FIELDS)?;
+        let mut client = GraphQLClient::from_options(&url, options);
+        client.request = request;
+
+        if let Some(config) = config.dyn_ref::<Object>() {
+            client.apply_config(config);
+        }
+
+        Ok(client)
+    }
+
+    /**
+      发送查询, `opts` 中可以包含 `operationName`、`extensions`、`errorPolicy`、`onNext`, 以及 `opts` 和 `request` 的字段
+    */
+    pub fn query(&self, query: String, variables: JsValue, opts: JsValue) -> Promise {
+        self.call(query, variables, opts)
+    }
+
+    /**
+      发送 `mutation`, 同 `query`
+    */
+    pub fn mutate(&self, mutation: String, variables: JsValue, opts: JsValue) -> Promise {
+        self.call(mutation, variables, opts)
+    }
+}
+
+impl GraphQLClient {
+    pub fn from_options(url: &str, options: HttpRequestOptions) -> Self {
+        Self {
+            url: url.to_string(),
+            options,
+            request: HttpRequest::default(),
+            error_policy: ErrorPolicy::default(),
+            persisted_queries: None,
+            persisted_supported: Rc::new(Cell::new(true)),
+        }
+    }
+
+    /// 开启持久化查询
+    pub fn persisted_queries(mut self, persisted_queries: Option<PersistedQueries>) -> Self {
+        self.persisted_queries = persisted_queries;
+        self
+    }
+
+    pub fn error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// 发送请求, 按照 `errorPolicy` 处理 `errors`
+    pub async fn execute(&self, request: &GraphQLRequest) -> Result<GraphQLResponse, Error> {
+        let response = self.execute_with(request, &self.options, &self.request, None).await?;
+        match self.error_policy {
+            ErrorPolicy::None => response.into_result(),
+            ErrorPolicy::All => Ok(response),
+        }
+    }
+
+    /// 发送请求, 返回的响应中可能包含 `errors`
+    async fn execute_with(&self, request: &GraphQLRequest, options: &HttpRequestOptions, http_request: &HttpRequest, on_next: Option<&OnNext>) -> Result<GraphQLResponse, Error> {
+        let persisted_queries = self.persisted_queries.as_ref().filter(|_| self.persisted_supported.get());
+        let persisted_queries = match persisted_queries {
+            Some(persisted_queries) => persisted_queries,
+            None => return self.post(&request.body(None, true), false, options, http_request, on_next).await,
+        };
+
+        // 先只发送 `hash`
+        let hash = hash_query(&request.query);
+        let use_get = persisted_queries.use_get && !request.is_mutation();
+        let response = self.post(&request.body(Some(&hash), false), use_get, options, http_request, on_next).await?;
+
+        if response.errors.iter().any(|err| err.is_persisted_query_not_supported()) {
+            self.persisted_supported.set(false);
+            return self.post(&request.body(None, true), false, options, http_request, on_next).await;
+        }
+
+        if response.errors.iter().any(|err| err.is_persisted_query_not_found()) {
+            return self.post(&request.body(Some(&hash), true), false, options, http_request, on_next).await;
+        }
+
+        Ok(response)
+    }
+
+    /// 发送请求体, `use_get` 时以 `query string` 的形式发送
+    async fn post(&self, body: &Value, use_get: bool, options: &HttpRequestOptions, http_request: &HttpRequest, on_next: Option<&OnNext>) -> Result<GraphQLResponse, Error> {
+        let mut options = options.clone();
+        if use_get {
+            options.method = Some(String::from("GET"));
+            options.url = Self::get_url(&options.url, body);
+            options.data = None;
+            options.request_type = None;
+        } else {
+            options.method = Some(String::from("POST"));
+            options.request_type = Some(HttpRequestType::Json);
+            options.data = Some(body.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| Error::Error(err.to_string()))?);
+        }
+
+        HttpClient::resolve_idempotency_key(&mut options);
+        let is_incremental = body.get("query").and_then(|query| query.as_str()).map(incremental::is_incremental).unwrap_or(false);
+        if is_incremental {
+            options.headers = Self::set_accept(options.headers, incremental::ACCEPT);
+            return Self::fetch_incremental(options, http_request, on_next).await;
+        }
+
+        let response = HttpClient::call(options, http_request.clone()).await?;
+        GraphQLResponse::from_body(response.status_code, response.ok, response.body)
+    }
+
+    /// `GET` 请求的地址, `variables` 和 `extensions` 为 `JSON` 字符串
+    #[doc = "https://graphql.github.io/graphql-over-http/draft/#sec-GET"]
+    fn get_url(url: &str, body: &Value) -> String {
+        let mut params: Vec<String> = Vec::new();
+        if let Some(body) = body.as_object() {
+            for (key, value) in body {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+
+                params.push(format!("{}={}", key, String::from(js_sys::encode_uri_component(&value))));
+            }
+        }
+
+        let (url, fragment) = match url.split_once('#') {
+            Some((url, fragment)) => (url, format!("#{}", fragment)),
+            None => (url, String::new()),
+        };
+
+        let separator = if url.contains('?') { "&" } else { "?" };
+        format!("{}{}{}{}", url, separator, params.join("&"), fragment)
+    }
+
+    /// 没有设置 `Accept` 时设置
+    fn set_accept(headers: Option<Value>, accept: &str) -> Option<Value> {
+        let mut headers = match headers {
+            Some(Value::Object(headers)) => headers,
+            _ => Map::new(),
+        };
+
+        if !headers.keys().any(|name| name.eq_ignore_ascii_case("accept")) {
+            headers.insert(String::from("Accept"), Value::String(accept.to_string()));
+        }
+
+        Some(Value::Object(headers))
+    }
+
+    /// 增量接收 `@defer`/`@stream` 的结果, 服务端不支持时按普通的 `JSON` 响应处理
+    async fn fetch_incremental(mut options: HttpRequestOptions, http_request: &HttpRequest, on_next: Option<&OnNext>) -> Result<GraphQLResponse, Error> {
+        let result: Rc<RefCell<Option<Value>>> = Rc::new(RefCell::new(None));
+        let listener = {
+            let result = result.clone();
+            let on_next = on_next.cloned();
+            move |part: Part| -> Result<bool, Error> {
+                // 忽略空的 `part`
+                if part.body.iter().all(|byte| byte.is_ascii_whitespace()) {
+                    return Ok(true);
+                }
+
+                let payload: Value = serde_json::from_slice(&part.body).map_err(|err| Error::Error(format!("invalid graphql incremental payload: {}", err)))?;
+                let merged = {
+                    let mut result = result.borrow_mut();
+                    let result = result.get_or_insert_with(|| Value::Object(Map::new()));
+                    incremental::apply(result, payload);
+                    result.clone()
+                };
+
+                if let Some(on_next) = &on_next {
+                    on_next(&serde_json::from_value(merged.clone()).map_err(|err| Error::Error(format!("invalid graphql response: {}", err)))?);
+                }
+
+                Ok(merged.get("hasNext") != Some(&Value::Bool(false)))
+            }
+        };
+
+        // 经过 `HttpClient::call` 的 `Layer`, 在 `Call::exec` 中流式读取
+        options.on_part = Some(PartListener(Rc::new(listener)));
+        let response = HttpClient::call(options, http_request.clone()).await?;
+        let body = result.borrow_mut().take().unwrap_or(response.body);
+        GraphQLResponse::from_body(response.status_code, response.ok, body)
+    }
+
+    /// 解析 `persistedQueries`、`errorPolicy`
+    fn apply_config(&mut self, config: &Object) {
+        if let Some(persisted_queries) = HttpClient::get_value(config, "persistedQueries") {
+            if persisted_queries.as_bool() == Some(false) {
+                self.persisted_queries = None;
+            } else {
+                let use_get = persisted_queries
+                    .dyn_ref::<Object>()
+                    .and_then(|obj| HttpClient::get_value(obj, "useGETForHashedQueries"))
+                    .map(|use_get| use_get.is_truthy())
+                    .unwrap_or(false);
+                self.persisted_queries = Some(PersistedQueries { use_get });
+            }
+        }
+
+        if let Some(error_policy) = HttpClient::get_value(config, "errorPolicy") {
+            self.error_policy = ErrorPolicy::get_error_policy(HttpClient::get_str(error_policy));
+        }
+    }
+
+    fn call(&self, query: String, variables: JsValue, opts: JsValue) -> Promise {
+        let client = self.clone();
+        future_to_promise(async move {
+            let (_, options, request) = HttpInstance::get_config(&opts, QUERY_FIELDS)?;
+            let mut options = HttpInstance::merge_options(&client.options, options);
+            options.url = client.url.clone();
+
+            let mut graphql_request = GraphQLRequest::new(&query, None);
+            if !variables.is_undefined() && !variables.is_null() {
+                graphql_request.variables = Some(serde_wasm_bindgen::from_value(variables).map_err(|err| JsValue::from_str(&err.to_string()))?);
+            }
+
+            let mut error_policy = client.error_policy;
+            let mut on_next: Option<Function> = None;
+            if let Some(obj) = opts.dyn_ref::<Object>() {
+                graphql_request.operation_name = HttpClient::get_value(obj, "operationName").map(HttpClient::get_str);
+                if let Some(extensions) = HttpClient::get_value(obj, "extensions") {
+                    graphql_request.extensions = Some(serde_wasm_bindgen::from_value(extensions).map_err(|err| JsValue::from_str(&err.to_string()))?);
+                }
+
+                if let Some(policy) = HttpClient::get_value(obj, "errorPolicy") {
+                    error_policy = ErrorPolicy::get_error_policy(HttpClient::get_str(policy));
+                }
+
+                on_next = HttpClient::get_value(obj, "onNext").and_then(|on_next| on_next.dyn_into::<Function>().ok());
+            }
+
+            let callback = on_next.map(|on_next| -> OnNext {
+                Rc::new(move |response: &GraphQLResponse| {
+                    if let Ok(value) = response.to_js() {
+                        let _ = on_next.call1(&JsValue::NULL, &value);
+                    }
+                })
+            });
+
+            let request = request.merge(&client.request);
+            let response = client.execute_with(&graphql_request, &options, &request, callback.as_ref()).await.map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+            if error_policy == ErrorPolicy::None && !response.errors.is_empty() {
+                return Err(Self::to_js_error(&response));
+            }
+
+            response.to_js().map_err(|err| JsValue::from_str(&err.to_string()))
+        })
+    }
+
+    /// `js` 的 `Error`, `name` 为 `GraphQLError`, 并带上 `errors`、`data`、`extensions`
+    fn to_js_error(response: &GraphQLResponse) -> JsValue {
+        let err = js_sys::Error::new(&Error::GraphQL(response.errors.clone()).to_string());
+        err.set_name("GraphQLError");
+
+        if let Ok(value) = response.to_js() {
+            for name in ["errors", "data", "extensions"] {
+                if let Ok(field) = Reflect::get(&value, &JsValue::from_str(name)) {
+                    let _ = Reflect::set(&err, &JsValue::from_str(name), &field);
+                }
+            }
+        }
+
+        err.into()
+    }
+}
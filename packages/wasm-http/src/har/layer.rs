@@ -0,0 +1,108 @@
+//! 录制和回放的 `tower` `Layer`, 未录制且未回放时直接调用内部的服务
+
+use crate::client::call::Call;
+use crate::error::Error;
+use crate::har::{self, replay, Entry, HarRequest, HarResponse, Wire};
+use crate::{HttpRequestOptions, HttpResponseOptions};
+use http::header::CONTENT_TYPE;
+use http::{Request, Response};
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::Layer;
+use tower_service::Service;
+
+#[derive(Debug, Clone)]
+pub(crate) struct HarLayer {
+    method: &'static str,
+    options: HttpRequestOptions,
+    request: Option<HarRequest>, // 录制时的请求
+    wire: Option<Wire>,          // 录制时 `Call::exec` 保存的原始响应
+}
+
+impl HarLayer {
+    pub(crate) fn new(options: &HttpRequestOptions) -> Self {
+        let (method, is_method_get) = Call::prepare_method(options);
+        let request = if har::is_recording() {
+            let mime_type = match (&options.headers, &options.request_type) {
+                (Some(Value::Object(headers)), _) if headers.keys().any(|name| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str())) => headers
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+                    .and_then(|(_, value)| value.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                (_, Some(request_type)) => request_type.get_content_type(),
+                _ => String::new(),
+            };
+
+            let body = if is_method_get { None } else { har::post_data(options.data.as_ref(), &mime_type) };
+            Some(HarRequest::new(method, &options.url, options.headers.as_ref(), body))
+        } else {
+            None
+        };
+
+        Self {
+            method,
+            options: options.clone(),
+            request,
+            wire: options.har_wire.clone(),
+        }
+    }
+}
+
+impl<S> Layer<S> for HarLayer {
+    type Service = HarService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HarService { inner, layer: self.clone() }
+    }
+}
+
+pub(crate) struct HarService<S> {
+    inner: S,
+    layer: HarLayer,
+}
+
+impl<S> Service<Request<Value>> for HarService<S>
+where
+    S: Service<Request<Value>, Response = Response<HttpResponseOptions>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = Response<HttpResponseOptions>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Value>) -> Self::Future {
+        let layer = self.layer.clone();
+        let started_date_time = layer.request.as_ref().map(|_| String::from(js_sys::Date::new_0().to_iso_string()));
+        let replayed = if replay::is_replaying() { replay::entry(layer.method, &layer.options.url) } else { None };
+        let future = if replayed.is_none() { Some(self.inner.call(request)) } else { None };
+
+        Box::pin(async move {
+            let result = match (replayed, future) {
+                (Some(Ok(entry)), _) => replay::replay_response(&entry, layer.method, &layer.options).await,
+                (Some(Err(err)), _) => Err(err),
+                (None, Some(future)) => future.await,
+                (None, None) => unreachable!(),
+            };
+
+            if let (Some(request), Some(started_date_time)) = (layer.request, started_date_time) {
+                let response = result.as_ref().map(|response| response.body());
+                let mut entry = Entry::new(&started_date_time, request, response, layer.options.response_type.as_ref());
+                // 收到的原始响应, 处理失败时也记录
+                if let Some(wire) = layer.wire.as_ref().and_then(Wire::take) {
+                    entry.response = HarResponse::from_wire(&wire);
+                }
+
+                har::record(entry);
+            }
+
+            result
+        })
+    }
+}
@@ -0,0 +1,543 @@
+/*!
+    `HAR 1.2` 录制与回放。
+    - 录制: 调用 `startHarRecording` 后, 每个请求的 `headers`、`body`、耗时及错误都保存在内存中的环形缓冲区中, 超出 `capacity` 时丢弃最早的记录,
+      通过 `exportHar` 导出 `HAR` 对象。响应记录收到的原始内容(不是 `UTF-8` 时为 `base64`), 与解码、转换、校验的结果无关。敏感的 `header` 和 `body` 中的字段按规则替换成 `[REDACTED]`
+    - 回放: 调用 `replayHar` 后, 请求不再发送, 而是从 `HAR` 中按 `method` 和 `url` 查找响应, 与实际的响应一样经过解码、转换及校验, 用于离线复现问题
+*/
+
+pub(crate) mod layer;
+pub mod replay;
+
+use crate::error::Error;
+use crate::{HttpRequestType, HttpResponseOptions};
+use base64::Engine;
+use http::HeaderMap;
+use js_sys::{Array, Object, JSON};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+pub use replay::Replay;
+
+/// `HAR` 版本
+pub const HAR_VERSION: &str = "1.2";
+
+/// 默认保存的请求数
+pub const CAPACITY: usize = 100;
+
+/// 替换后的值
+pub const REDACTED: &str = "[REDACTED]";
+
+/// 默认替换的 `header`
+const REDACT_HEADERS: [&str; 4] = ["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+#[doc = "http://www.softwareishard.com/blog/har-12-spec/"]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Har {
+    pub log: Log,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    #[serde(default)]
+    pub started_date_time: String,
+    #[serde(default)]
+    pub time: f64, // 耗时(毫秒)
+    pub request: HarRequest,
+    pub response: HarResponse,
+    #[serde(default)]
+    pub cache: Value,
+    #[serde(default)]
+    pub timings: Timings,
+    #[serde(default, rename = "_error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>, // 请求失败或响应校验失败时的错误
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<Value>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    #[serde(default)]
+    pub query_string: Vec<NameValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    #[serde(default)]
+    pub headers_size: i64,
+    #[serde(default)]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub http_version: String,
+    #[serde(default)]
+    pub cookies: Vec<Value>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    #[serde(default)]
+    pub content: Content,
+    #[serde(default, rename = "redirectURL")]
+    pub redirect_url: String,
+    #[serde(default)]
+    pub headers_size: i64,
+    #[serde(default)]
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Content {
+    #[serde(default)]
+    pub size: i64,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>, // 二进制内容为 `base64`
+}
+
+/// `fetch` 无法获取各阶段的耗时, 全部记录在 `wait` 中
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timings {
+    #[serde(default)]
+    pub send: f64,
+    #[serde(default)]
+    pub wait: f64,
+    #[serde(default)]
+    pub receive: f64,
+}
+
+impl NameValue {
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl HarRequest {
+    /// 创建请求, `headers` 为 `opts.headers`, `body` 为序列化后的 `body`
+    pub fn new(method: &str, url: &str, headers: Option<&Value>, body: Option<PostData>) -> Self {
+        let headers: Vec<NameValue> = match headers {
+            Some(Value::Object(headers)) => headers.iter().map(|(name, value)| NameValue::new(name, value.as_str().unwrap_or_default())).collect(),
+            _ => Vec::new(),
+        };
+
+        let query_string: Vec<NameValue> = match url.split_once('?') {
+            Some((_, query)) => query
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| match pair.split_once('=') {
+                    Some((name, value)) => NameValue::new(name, value),
+                    None => NameValue::new(pair, ""),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Self {
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            http_version: String::from("HTTP/1.1"),
+            headers,
+            query_string,
+            body_size: body.as_ref().map(|body| body.text.len() as i64).unwrap_or(0),
+            post_data: body,
+            headers_size: -1,
+            ..Default::default()
+        }
+    }
+}
+
+impl Entry {
+    /// 根据请求和结果创建记录
+    pub fn new(started_date_time: &str, request: HarRequest, result: Result<&HttpResponseOptions, &Error>, response_type: Option<&HttpRequestType>) -> Self {
+        let mut entry = Self {
+            started_date_time: started_date_time.to_string(),
+            request,
+            cache: Value::Object(Default::default()),
+            ..Default::default()
+        };
+
+        match result {
+            Ok(response) => {
+                entry.time = response.timing.duration;
+                entry.timings.wait = response.timing.duration;
+                entry.response = HarResponse::from_response(response, response_type);
+                entry.error = Some(response.error.clone()).filter(|error| !error.is_empty());
+            }
+            Err(err) => {
+                entry.response = HarResponse {
+                    http_version: String::from("HTTP/1.1"),
+                    headers_size: -1,
+                    body_size: -1,
+                    ..Default::default()
+                };
+                entry.error = Some(err.to_string());
+            }
+        }
+
+        entry
+    }
+}
+
+impl HarResponse {
+    fn new(status: u16, status_text: &str, headers: &HeaderMap, content: Content) -> Self {
+        Self {
+            status,
+            status_text: status_text.to_string(),
+            http_version: String::from("HTTP/1.1"),
+            headers: headers.iter().map(|(name, value)| NameValue::new(name.as_str(), value.to_str().unwrap_or_default())).collect(),
+            content,
+            redirect_url: headers.get(http::header::LOCATION).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string(),
+            headers_size: -1,
+            body_size: -1,
+            ..Default::default()
+        }
+    }
+
+    fn mime_type(headers: &HeaderMap) -> String {
+        headers.get(http::header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string()
+    }
+
+    /// 收到的原始响应, 不是 `UTF-8` 文本时使用 `base64`
+    pub(crate) fn from_wire(wire: &WireResponse) -> Self {
+        let mut content = Content {
+            size: wire.body.len() as i64,
+            mime_type: Self::mime_type(&wire.headers),
+            ..Default::default()
+        };
+
+        match std::str::from_utf8(&wire.body) {
+            _ if wire.body.is_empty() => {}
+            Ok(text) => content.text = Some(text.to_string()),
+            Err(_) => {
+                content.text = Some(base64::engine::general_purpose::STANDARD.encode(&wire.body));
+                content.encoding = Some(String::from("base64"));
+            }
+        }
+
+        Self::new(wire.status, &wire.status_text, &wire.headers, content)
+    }
+
+    /// 没有原始响应时(如故障注入), 按处理后的 `body` 记录
+    fn from_response(response: &HttpResponseOptions, response_type: Option<&HttpRequestType>) -> Self {
+        let mut content = Content {
+            mime_type: Self::mime_type(&response.headers),
+            ..Default::default()
+        };
+        match (&response.body, response_type) {
+            // 未解析的 `JSON` 文本
+            _ if response.raw.is_some() => {
+                content.size = response.raw.as_ref().map(|raw| raw.len()).unwrap_or_default() as i64;
+                content.text = response.raw.clone();
+            }
+            (Value::Null, _) => {}
+            (Value::Object(body), Some(HttpRequestType::Blob)) => {
+                let bytes: Vec<u8> = body
+                    .get("binary")
+                    .and_then(|binary| binary.as_array())
+                    .map(|binary| binary.iter().filter_map(|byte| byte.as_u64()).map(|byte| byte as u8).collect())
+                    .unwrap_or_default();
+                content.size = bytes.len() as i64;
+                content.text = Some(base64::engine::general_purpose::STANDARD.encode(bytes));
+                content.encoding = Some(String::from("base64"));
+            }
+            (Value::String(body), _) => {
+                content.size = body.len() as i64;
+                content.text = Some(body.clone());
+            }
+            (body, _) => {
+                let body = body.to_string();
+                content.size = body.len() as i64;
+                content.text = Some(body);
+            }
+        }
+
+        Self::new(response.status_code, &response.status_text, &response.headers, content)
+    }
+}
+
+/// 收到的原始响应, `body` 未经过解码、转换及校验
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WireResponse {
+    pub(crate) status: u16,
+    pub(crate) status_text: String,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Vec<u8>,
+}
+
+/// 录制时由 `HarLayer` 创建, `Call::exec` 写入原始响应, 请求失败(如 `schema` 校验失败)时也能记录收到的响应
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Wire(Rc<RefCell<Option<WireResponse>>>);
+
+impl Wire {
+    pub(crate) fn set(&self, response: WireResponse) {
+        *self.0.borrow_mut() = Some(response);
+    }
+
+    pub(crate) fn take(&self) -> Option<WireResponse> {
+        self.0.borrow_mut().take()
+    }
+}
+
+/// 脱敏规则, 名称忽略大小写
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redaction {
+    pub headers: Vec<String>,   // 替换的 `header`
+    pub body_keys: Vec<String>, // 替换 `JSON` `body` 中的字段, 包括嵌套的字段
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            headers: REDACT_HEADERS.iter().map(|header| header.to_string()).collect(),
+            body_keys: Vec::new(),
+        }
+    }
+}
+
+impl Redaction {
+    pub fn apply(&self, entry: &mut Entry) {
+        let headers: Vec<String> = self.headers.iter().map(|header| header.to_lowercase()).collect();
+        let redact_headers = |values: &mut Vec<NameValue>| {
+            values.iter_mut().filter(|header| headers.contains(&header.name.to_lowercase())).for_each(|header| header.value = REDACTED.to_string());
+        };
+
+        redact_headers(&mut entry.request.headers);
+        redact_headers(&mut entry.response.headers);
+
+        if self.body_keys.is_empty() {
+            return;
+        }
+
+        if let Some(post_data) = entry.request.post_data.as_mut() {
+            post_data.text = self.redact_text(&post_data.text);
+        }
+
+        if entry.response.content.encoding.is_none() {
+            if let Some(text) = entry.response.content.text.as_mut() {
+                *text = self.redact_text(text);
+            }
+        }
+    }
+
+    /// 不是 `JSON` 时不处理
+    fn redact_text(&self, text: &str) -> String {
+        match serde_json::from_str::<Value>(text) {
+            Ok(mut value) => {
+                self.redact_value(&mut value);
+                value.to_string()
+            }
+            Err(_) => text.to_string(),
+        }
+    }
+
+    fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.body_keys.iter().any(|body_key| body_key.eq_ignore_ascii_case(key)) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.redact_value(value)),
+            _ => {}
+        }
+    }
+}
+
+/// 录制器, 保存最近的 `capacity` 个请求
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    capacity: usize,
+    redaction: Redaction,
+    entries: VecDeque<Entry>,
+}
+
+impl Recorder {
+    pub fn new(capacity: usize, redaction: Redaction) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            redaction,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// 脱敏后保存, 超出 `capacity` 时丢弃最早的记录
+    pub fn record(&mut self, mut entry: Entry) {
+        self.redaction.apply(&mut entry);
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    pub fn entries(&self) -> &VecDeque<Entry> {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn to_har(&self) -> Har {
+        Har {
+            log: Log {
+                version: HAR_VERSION.to_string(),
+                creator: Creator {
+                    name: env!("CARGO_PKG_NAME").to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries: self.entries.iter().cloned().collect(),
+            },
+        }
+    }
+}
+
+/// 是否正在录制
+pub(crate) fn is_recording() -> bool {
+    RECORDER.with(|recorder| recorder.borrow().is_some())
+}
+
+/// 保存一条记录, 未录制时忽略
+pub(crate) fn record(entry: Entry) {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.record(entry);
+        }
+    });
+}
+
+/// 序列化 `body` 用于 `postData`, `Blob`、`FormData` 等只记录类型
+pub(crate) fn post_data(data: Option<&JsValue>, mime_type: &str) -> Option<PostData> {
+    let data = data.filter(|data| !data.is_undefined() && !data.is_null())?;
+    let text = if let Some(text) = data.as_string() {
+        text
+    } else if data.is_instance_of::<web_sys::Blob>() || data.is_instance_of::<web_sys::FormData>() || data.is_instance_of::<web_sys::ReadableStream>() {
+        String::new()
+    } else if let Some(data) = data.dyn_ref::<Object>() {
+        JSON::stringify(data).ok().map(String::from).unwrap_or_default()
+    } else {
+        String::new()
+    };
+
+    Some(PostData { mime_type: mime_type.to_string(), text })
+}
+
+/**
+  开始录制, `options` 中可以设置 `capacity`(默认为 `100`)、`redactHeaders`(追加到默认的 `authorization`、`proxy-authorization`、`cookie`、`set-cookie` 中)、`redactBody`(`JSON` `body` 中替换的字段)
+  已经在录制时会清空之前的记录
+*/
+#[wasm_bindgen(js_name = startHarRecording)]
+pub fn start_har_recording(options: JsValue) {
+    let mut capacity = CAPACITY;
+    let mut redaction = Redaction::default();
+    if let Some(obj) = options.dyn_ref::<Object>() {
+        let strings = |name: &str| -> Vec<String> {
+            crate::send::HttpClient::get_value(obj, name)
+                .map(|value| Array::from(&value).iter().filter_map(|value| value.as_string()).collect())
+                .unwrap_or_default()
+        };
+
+        if let Some(value) = crate::send::HttpClient::get_value(obj, "capacity").and_then(|value| value.as_f64()) {
+            capacity = value.max(1.0) as usize;
+        }
+
+        redaction.headers.extend(strings("redactHeaders"));
+        redaction.body_keys = strings("redactBody");
+    }
+
+    RECORDER.with(|recorder| *recorder.borrow_mut() = Some(Recorder::new(capacity, redaction)));
+}
+
+/**
+  停止录制, 返回录制的 `HAR`
+*/
+#[wasm_bindgen(js_name = stopHarRecording)]
+pub fn stop_har_recording() -> Result<JsValue, JsValue> {
+    let har = RECORDER.with(|recorder| recorder.borrow_mut().take().map(|recorder| recorder.to_har()));
+    to_js(&har.unwrap_or_else(|| Recorder::new(CAPACITY, Redaction::default()).to_har()))
+}
+
+/**
+  导出当前录制的 `HAR`, 不停止录制
+*/
+#[wasm_bindgen(js_name = exportHar)]
+pub fn export_har() -> Result<JsValue, JsValue> {
+    let har = RECORDER.with(|recorder| recorder.borrow().as_ref().map(|recorder| recorder.to_har()));
+    to_js(&har.unwrap_or_else(|| Recorder::new(CAPACITY, Redaction::default()).to_har()))
+}
+
+/**
+  清空录制的请求
+*/
+#[wasm_bindgen(js_name = clearHar)]
+pub fn clear_har() {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.clear();
+        }
+    });
+}
+
+fn to_js(har: &Har) -> Result<JsValue, JsValue> {
+    har.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| JsValue::from_str(&err.to_string()))
+}
@@ -0,0 +1,121 @@
+//! 从 `HAR` 中回放响应, 按 `method` 和 `url` 匹配, 同一个请求有多条记录时按顺序返回, 用完后一直返回最后一条。
+//! 回放时用记录的内容创建原生的 `Response`, 与实际的响应一样经过 `Call::read_response` 处理。
+
+use crate::client::call::Call;
+use crate::error::Error;
+use crate::har::{Entry, Har};
+use crate::response::timing::Timing;
+use crate::{HttpRequestOptions, HttpResponseOptions};
+use base64::Engine;
+use http::Response;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+use web_sys::{Headers, ResponseInit};
+
+thread_local! {
+    static REPLAY: RefCell<Option<Replay>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    entries: Vec<Entry>,
+    used: Vec<bool>, // 已经返回过的记录
+}
+
+impl Replay {
+    pub fn new(har: Har) -> Self {
+        let used = vec![false; har.log.entries.len()];
+        Self { entries: har.log.entries, used }
+    }
+
+    /// 查找记录, `method` 忽略大小写
+    pub fn find(&mut self, method: &str, url: &str) -> Option<&Entry> {
+        let matched: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.request.method.eq_ignore_ascii_case(method) && entry.request.url == url)
+            .map(|(index, _)| index)
+            .collect();
+        let index = matched.iter().find(|index| !self.used[**index]).or(matched.last()).copied()?;
+        self.used[index] = true;
+        self.entries.get(index)
+    }
+
+    /// 查找记录, 找不到时返回错误
+    pub fn entry(&mut self, method: &str, url: &str) -> Result<Entry, Error> {
+        self.find(method, url).cloned().ok_or_else(|| Error::Error(format!("no recorded response in har for {} {}", method.to_uppercase(), url)))
+    }
+}
+
+/// 记录中的原始内容, `encoding` 为 `base64` 时解码
+fn content_bytes(entry: &Entry) -> Result<Vec<u8>, Error> {
+    let content = &entry.response.content;
+    let text = content.text.clone().unwrap_or_default();
+    if content.encoding.as_deref().map(|encoding| encoding.eq_ignore_ascii_case("base64")).unwrap_or(false) {
+        return base64::engine::general_purpose::STANDARD.decode(text.trim()).map_err(|err| Error::Error(format!("invalid base64 content in har: {}", err)));
+    }
+
+    Ok(text.into_bytes())
+}
+
+/// 用记录创建原生的 `Response`, 按 `options` 处理, 与实际的响应相同
+pub(crate) async fn replay_response(entry: &Entry, method: &str, options: &HttpRequestOptions) -> Result<Response<HttpResponseOptions>, Error> {
+    let har_response = &entry.response;
+    if har_response.status == 0 {
+        return Err(Error::Error(entry.error.clone().unwrap_or_else(|| String::from("recorded request failed"))));
+    }
+
+    let headers = Headers::new().map_err(Error::js_error)?;
+    for header in har_response.headers.iter() {
+        // 不能设置的 `header`(如 `Set-Cookie`)忽略
+        let _ = headers.append(&header.name, &header.value);
+    }
+
+    let init = ResponseInit::new();
+    init.set_status(har_response.status);
+    init.set_status_text(&har_response.status_text);
+    init.set_headers(&headers);
+
+    // `204`、`304` 等状态码不能有 `body`
+    let mut bytes = content_bytes(entry)?;
+    let body = Some(bytes.as_mut_slice()).filter(|bytes| !bytes.is_empty() && !matches!(har_response.status, 101 | 103 | 204 | 205 | 304));
+    let response = web_sys::Response::new_with_opt_u8_array_and_init(body, &init).map_err(Error::js_error)?;
+
+    let timing = Timing { duration: entry.time, ..Default::default() };
+    let mut result = Call::read_response(method, response, options, timing).await?;
+    result.body_mut().url = entry.request.url.clone();
+    Ok(result)
+}
+
+/// 是否正在回放
+pub(crate) fn is_replaying() -> bool {
+    REPLAY.with(|replay| replay.borrow().is_some())
+}
+
+/// 查找回放的记录, 未回放时返回 `None`
+pub(crate) fn entry(method: &str, url: &str) -> Option<Result<Entry, Error>> {
+    REPLAY.with(|replay| replay.borrow_mut().as_mut().map(|replay| replay.entry(method, url)))
+}
+
+/**
+  开始回放, `har` 为 `HAR` 对象或 `JSON` 字符串, 之后的请求都从 `har` 中返回, 找不到时返回错误
+*/
+#[wasm_bindgen(js_name = replayHar)]
+pub fn replay_har(har: JsValue) -> Result<(), JsValue> {
+    let har: Har = match har.as_string() {
+        Some(har) => serde_json::from_str(&har).map_err(|err| JsValue::from_str(&Error::Error(format!("invalid har: {}", err)).to_string()))?,
+        None => serde_wasm_bindgen::from_value(har).map_err(|err| JsValue::from_str(&Error::Error(format!("invalid har: {}", err)).to_string()))?,
+    };
+
+    REPLAY.with(|replay| *replay.borrow_mut() = Some(Replay::new(har)));
+    Ok(())
+}
+
+/**
+  停止回放, 恢复发送请求
+*/
+#[wasm_bindgen(js_name = stopReplay)]
+pub fn stop_replay() {
+    REPLAY.with(|replay| *replay.borrow_mut() = None);
+}
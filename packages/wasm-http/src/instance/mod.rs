@@ -0,0 +1,229 @@
+/*!
+    `HttpClient` 实例, 保存 `baseURL`、`headers`、`timeout` 及 `request` 等默认配置。
+    每次请求的参数会合并在默认配置之上: 相对地址按照 `RFC 3986` 基于 `baseURL` 解析, `headers` 忽略大小写合并。
+*/
+
+pub mod url;
+
+use crate::client::beacon;
+use crate::client::call::Call;
+use crate::error::Error;
+use crate::options::{self, OPTIONS_FIELDS, REQUEST_FIELDS};
+use crate::pagination::Paginator;
+use crate::request::HttpRequest;
+use crate::send::HttpClient;
+use crate::HttpRequestOptions;
+use js_sys::{Object, Promise};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+#[wasm_bindgen(js_name = HttpClient)]
+#[derive(Debug, Clone, Default)]
+pub struct HttpInstance {
+    base_url: Option<String>,    // baseURL
+    options: HttpRequestOptions, // 默认 options
+    request: HttpRequest,        // 默认 request
+}
+
+#[wasm_bindgen(js_class = HttpClient)]
+impl HttpInstance {
+    /**
+      创建实例, `config` 中可以同时包含 `opts` 和 `request` 的字段, 以及 `baseURL`
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: JsValue) -> Result<HttpInstance, JsValue> {
+        HttpInstance::default().extend(config)
+    }
+
+    /**
+      基于当前实例派生子实例, `config` 合并在当前实例的默认配置之上, 相对的 `baseURL` 基于当前实例的 `baseURL` 解析
+    */
+    pub fn extend(&self, config: JsValue) -> Result<HttpInstance, JsValue> {
+        let (base_url, options, request) = Self::parse_config(&config, Some(&[&["baseURL"], OPTIONS_FIELDS, REQUEST_FIELDS]))?;
+        let base_url = match (&self.base_url, base_url) {
+            (Some(parent), Some(base_url)) => Some(url::resolve(parent, &base_url)),
+            (parent, base_url) => base_url.or(parent.clone()),
+        };
+
+        Ok(HttpInstance {
+            base_url,
+            options: Self::merge_options(&self.options, options),
+            request: request.merge(&self.request),
+        })
+    }
+
+    /**
+      同 `extend`
+    */
+    pub fn create(&self, config: JsValue) -> Result<HttpInstance, JsValue> {
+        self.extend(config)
+    }
+
+    /**
+      发送请求, `opts` 中需要包含 `url`
+    */
+    pub fn request(&self, opts: JsValue) -> Promise {
+        self.call(None, None, None, opts)
+    }
+
+    pub fn get(&self, url: String, opts: JsValue) -> Promise {
+        self.call(Some("GET"), Some(url), None, opts)
+    }
+
+    pub fn delete(&self, url: String, opts: JsValue) -> Promise {
+        self.call(Some("DELETE"), Some(url), None, opts)
+    }
+
+    pub fn post(&self, url: String, data: JsValue, opts: JsValue) -> Promise {
+        self.call(Some("POST"), Some(url), Some(data), opts)
+    }
+
+    pub fn put(&self, url: String, data: JsValue, opts: JsValue) -> Promise {
+        self.call(Some("PUT"), Some(url), Some(data), opts)
+    }
+
+    pub fn patch(&self, url: String, data: JsValue, opts: JsValue) -> Promise {
+        self.call(Some("PATCH"), Some(url), Some(data), opts)
+    }
+
+    /**
+      `beacon` 模式发送 `POST` 请求, 不等待响应, 返回请求是否会在页面卸载后继续发送
+    */
+    pub fn beacon(&self, url: String, data: JsValue, opts: JsValue) -> Result<bool, JsValue> {
+        let (mut options, request) = self.prepare(Some("POST"), Some(url), Some(data), opts)?;
+        HttpClient::resolve_idempotency_key(&mut options);
+        beacon::send(&options, request).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /**
+      分页请求, 返回异步迭代器, 分页方式见 `opts.pagination`
+    */
+    pub fn paginate(&self, url: String, opts: JsValue) -> Result<Object, JsValue> {
+        let (options, request) = self.prepare(None, Some(url), None, opts.clone())?;
+        Paginator::from_js(options, request, &opts).into_js()
+    }
+}
+
+impl HttpInstance {
+    /// 解析配置, 返回 `baseURL`、`opts` 和 `request`, `fields` 为调用方自己的字段, 其他未知字段视为错误
+    pub(crate) fn get_config(config: &JsValue, fields: &[&str]) -> Result<(Option<String>, HttpRequestOptions, HttpRequest), JsValue> {
+        Self::parse_config(config, Some(&[OPTIONS_FIELDS, REQUEST_FIELDS, fields]))
+    }
+
+    /// 解析配置, 收集所有字段的错误, `fields` 不为空时同时校验未知字段
+    fn parse_config(config: &JsValue, fields: Option<&[&[&str]]>) -> Result<(Option<String>, HttpRequestOptions, HttpRequest), JsValue> {
+        if config.is_undefined() || config.is_null() {
+            return Ok((None, HttpRequestOptions::default(), HttpRequest::default()));
+        }
+
+        let obj = config.dyn_ref::<Object>().ok_or_else(|| JsValue::from_str(&Error::Error("`config` is not a object !".to_string()).to_string()))?;
+        let mut errors = Vec::new();
+        if let Some(fields) = fields {
+            options::check_fields(obj, fields, &mut errors);
+        }
+
+        let base_url = match HttpClient::get_value(obj, "baseURL").map(|base_url| base_url.as_string().ok_or(base_url)) {
+            Some(Ok(base_url)) => Some(base_url).filter(|base_url| !base_url.trim().is_empty()),
+            Some(Err(_)) => {
+                errors.push("`baseURL`: invalid type, expected a string".to_string());
+                None
+            }
+            None => None,
+        };
+
+        let options = options::parse_options(obj, &mut errors);
+        let request = options::parse_request(obj, &mut errors);
+        options::finish((base_url, options, request), errors)
+    }
+
+    /// 合并 `options`, 当前请求的配置优先, 默认配置中的 `data` 只用于 `POST`、`PUT`、`PATCH` 请求
+    pub(crate) fn merge_options(defaults: &HttpRequestOptions, options: HttpRequestOptions) -> HttpRequestOptions {
+        let defaults = defaults.clone();
+        let mut merged = HttpRequestOptions {
+            url: options.url,
+            method: options.method.or(defaults.method),
+            data: options.data,
+            headers: Self::merge_headers(defaults.headers, options.headers),
+            timeout: options.timeout.or(defaults.timeout),
+            request_type: options.request_type.or(defaults.request_type),
+            response_type: options.response_type.or(defaults.response_type),
+            response_encoding: options.response_encoding.or(defaults.response_encoding),
+            schema: options.schema.or(defaults.schema),
+            schema_mode: options.schema_mode.or(defaults.schema_mode),
+            compress_request: options.compress_request.or(defaults.compress_request),
+            compress_threshold: options.compress_threshold.or(defaults.compress_threshold),
+            circuit_key: options.circuit_key.or(defaults.circuit_key),
+            transform_request_keys: options.transform_request_keys.or(defaults.transform_request_keys),
+            transform_response_keys: options.transform_response_keys.or(defaults.transform_response_keys),
+            transform_skip: options.transform_skip.or(defaults.transform_skip),
+            idempotency_key: options.idempotency_key.or(defaults.idempotency_key),
+            source: options.source.or(defaults.source),
+            verify_digest: options.verify_digest.or(defaults.verify_digest),
+            raw_json: options.raw_json,
+            raw_blob: options.raw_blob,
+            on_part: options.on_part,
+            har_wire: options.har_wire,
+        };
+
+        let (method, _) = Call::prepare_method(&merged);
+        if merged.data.is_none() && matches!(method, "POST" | "PUT" | "PATCH") {
+            merged.data = defaults.data;
+        }
+
+        merged
+    }
+
+    /// 忽略大小写合并 `headers`, 值为 `null` 时删除默认配置中的同名 `header`
+    fn merge_headers(defaults: Option<Value>, headers: Option<Value>) -> Option<Value> {
+        match (defaults, headers) {
+            (Some(Value::Object(mut defaults)), Some(Value::Object(headers))) => {
+                for (key, value) in headers {
+                    defaults.retain(|name, _| !name.eq_ignore_ascii_case(&key));
+                    if !value.is_null() {
+                        defaults.insert(key, value);
+                    }
+                }
+
+                Some(Value::Object(defaults))
+            }
+            (defaults, None) => defaults,
+            (_, headers) => headers,
+        }
+    }
+
+    /// 合并配置并获取最终的 `opts` 和 `request`
+    fn prepare(&self, method: Option<&str>, url: Option<String>, data: Option<JsValue>, opts: JsValue) -> Result<(HttpRequestOptions, HttpRequest), JsValue> {
+        let (_, mut options, request) = Self::parse_config(&opts, Some(&[OPTIONS_FIELDS, REQUEST_FIELDS]))?;
+        if let Some(url) = url {
+            options.url = url;
+        }
+
+        if let Some(data) = data.filter(|data| !data.is_undefined()) {
+            options.data = Some(data);
+        }
+
+        if let Some(method) = method {
+            options.method = Some(method.to_string());
+        }
+
+        let mut options = Self::merge_options(&self.options, options);
+        if let Some(base_url) = &self.base_url {
+            options.url = url::resolve(base_url, &options.url);
+        }
+
+        if options.url.is_empty() {
+            return Err(JsValue::from_str(&Error::Error("`url` is empty !".to_string()).to_string()));
+        }
+
+        Ok((options, request.merge(&self.request)))
+    }
+
+    fn call(&self, method: Option<&str>, url: Option<String>, data: Option<JsValue>, opts: JsValue) -> Promise {
+        let prepared = self.prepare(method, url, data, opts);
+        future_to_promise(async move {
+            let (options, request) = prepared?;
+            HttpClient::execute(options, request).await
+        })
+    }
+}
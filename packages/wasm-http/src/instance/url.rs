@@ -0,0 +1,151 @@
+/*!
+    按照 `RFC 3986` 第 5 节的规则, 把相对地址解析为基于 `baseURL` 的地址。
+    `baseURL` 本身可以是相对地址(如 `/api/`), 此时结果同样是相对地址, 由 `fetch` 基于当前页面再次解析。
+*/
+
+#[doc = "https://www.rfc-editor.org/rfc/rfc3986#section-3"]
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Components<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> Components<'a> {
+    #[doc = "https://www.rfc-editor.org/rfc/rfc3986#appendix-B"]
+    fn parse(uri: &'a str) -> Self {
+        let mut components = Components::default();
+        let mut rest = uri;
+
+        // fragment
+        if let Some(index) = rest.find('#') {
+            components.fragment = Some(&rest[index + 1..]);
+            rest = &rest[..index];
+        }
+
+        // query
+        if let Some(index) = rest.find('?') {
+            components.query = Some(&rest[index + 1..]);
+            rest = &rest[..index];
+        }
+
+        // scheme, 必须以字母开头, 且出现在第一个 `/` 之前
+        if let Some(index) = rest.find(':') {
+            let scheme = &rest[..index];
+            let mut chars = scheme.chars();
+            let is_scheme = chars.next().map(|c| c.is_ascii_alphabetic()).unwrap_or(false) && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+            if is_scheme {
+                components.scheme = Some(scheme);
+                rest = &rest[index + 1..];
+            }
+        }
+
+        // authority
+        if let Some(stripped) = rest.strip_prefix("//") {
+            let index = stripped.find('/').unwrap_or(stripped.len());
+            components.authority = Some(&stripped[..index]);
+            rest = &stripped[index..];
+        }
+
+        components.path = rest;
+        components
+    }
+}
+
+/// 去掉路径中的 `.` 和 `..`
+#[doc = "https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4"]
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output: Vec<&str> = Vec::new();
+
+    while !input.is_empty() {
+        if let Some(stripped) = input.strip_prefix("../") {
+            input = stripped;
+        } else if let Some(stripped) = input.strip_prefix("./") {
+            input = stripped;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // 移动第一个路径段(包括开头的 `/`)到输出
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..].find('/').map(|index| index + start).unwrap_or(input.len());
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+
+    output.concat()
+}
+
+/// 合并 `base` 路径与相对路径
+#[doc = "https://www.rfc-editor.org/rfc/rfc3986#section-5.2.3"]
+fn merge_paths(base: &Components, reference: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{}", reference);
+    }
+
+    match base.path.rfind('/') {
+        Some(index) => format!("{}{}", &base.path[..=index], reference),
+        None => reference.to_string(),
+    }
+}
+
+/// 把 `reference` 解析为基于 `base` 的地址
+#[doc = "https://www.rfc-editor.org/rfc/rfc3986#section-5.2.2"]
+pub fn resolve(base: &str, reference: &str) -> String {
+    let base = base.trim();
+    let reference = reference.trim();
+    if base.is_empty() {
+        return reference.to_string();
+    }
+
+    let b = Components::parse(base);
+    let r = Components::parse(reference);
+
+    let (scheme, authority, path, query) = if r.scheme.is_some() {
+        (r.scheme, r.authority, remove_dot_segments(r.path), r.query)
+    } else if r.authority.is_some() {
+        (b.scheme, r.authority, remove_dot_segments(r.path), r.query)
+    } else if r.path.is_empty() {
+        (b.scheme, b.authority, b.path.to_string(), if r.query.is_some() { r.query } else { b.query })
+    } else if r.path.starts_with('/') {
+        (b.scheme, b.authority, remove_dot_segments(r.path), r.query)
+    } else {
+        (b.scheme, b.authority, remove_dot_segments(&merge_paths(&b, r.path)), r.query)
+    };
+
+    // 组装, https://www.rfc-editor.org/rfc/rfc3986#section-5.3
+    let mut result = String::new();
+    if let Some(scheme) = scheme {
+        result.push_str(scheme);
+        result.push(':');
+    }
+
+    if let Some(authority) = authority {
+        result.push_str("//");
+        result.push_str(authority);
+    }
+
+    result.push_str(&path);
+
+    if let Some(query) = query {
+        result.push('?');
+        result.push_str(query);
+    }
+
+    if let Some(fragment) = r.fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+
+    result
+}
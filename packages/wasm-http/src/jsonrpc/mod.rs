@@ -0,0 +1,401 @@
+/*!
+    `JSON-RPC 2.0` 客户端。
+    - 自动分配 `id`, 并按照 `id` 把响应分发给对应的调用
+    - 响应中的 `error` 转换为 `Error::JsonRpc`
+    - 同一个微任务(或 `batchWindow` 毫秒)内的调用合并为一个批量请求, 以一次 `POST` 发送
+*/
+
+use crate::client::timer;
+use crate::error::Error;
+use crate::instance::HttpInstance;
+use crate::request::HttpRequest;
+use crate::send::HttpClient;
+use crate::{HttpRequestOptions, HttpRequestType};
+use futures::channel::oneshot;
+use js_sys::{Object, Promise, Reflect};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{future_to_promise, spawn_local};
+
+#[doc = "https://www.jsonrpc.org/specification"]
+const JSON_RPC_VERSION: &str = "2.0";
+
+/// 默认的最大批量数
+const MAX_BATCH_SIZE: usize = 50;
+
+/// `config` 中 `opts` 和 `request` 以外的字段
+const CONFIG_FIELDS: &[&str] = &["batch", "batchWindow", "maxBatchSize"];
+
+/// 请求, `id` 为 `None` 时为通知, 服务端不会响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+}
+
+impl JsonRpcRequest {
+    pub fn new(method: &str, params: Option<Value>, id: Option<u64>) -> Self {
+        Self {
+            jsonrpc: JSON_RPC_VERSION.to_string(),
+            method: method.to_string(),
+            params,
+            id,
+        }
+    }
+}
+
+/// 错误对象
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl JsonRpcError {
+    pub fn new(code: i64, message: &str) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    /// 预定义的错误类型
+    pub fn kind(&self) -> JsonRpcErrorKind {
+        match self.code {
+            -32700 => JsonRpcErrorKind::Parse,
+            -32600 => JsonRpcErrorKind::InvalidRequest,
+            -32601 => JsonRpcErrorKind::MethodNotFound,
+            -32602 => JsonRpcErrorKind::InvalidParams,
+            -32603 => JsonRpcErrorKind::Internal,
+            -32099..=-32000 => JsonRpcErrorKind::Server,
+            _ => JsonRpcErrorKind::Application,
+        }
+    }
+}
+
+impl fmt::Display for JsonRpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+/// 错误类型
+#[doc = "https://www.jsonrpc.org/specification#error_object"]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonRpcErrorKind {
+    Parse,          // -32700
+    InvalidRequest, // -32600
+    MethodNotFound, // -32601
+    InvalidParams,  // -32602
+    Internal,       // -32603
+    Server,         // -32000 ~ -32099
+    Application,    // 其他, 由应用定义
+}
+
+/// 响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRpcResponse {
+    #[serde(default)]
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+    #[serde(default)]
+    pub id: Value,
+}
+
+impl JsonRpcResponse {
+    pub fn into_result(self) -> Result<Value, Error> {
+        match self.error {
+            Some(err) => Err(Error::JsonRpc(err)),
+            None => Ok(self.result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// 把批量响应按照 `id` 拆分, 返回的顺序与 `ids` 相同
+#[doc = "https://www.jsonrpc.org/specification#batch"]
+pub fn split_batch(ids: &[u64], body: Value) -> Vec<Result<Value, Error>> {
+    let responses: Vec<Value> = match body {
+        Value::Array(responses) => responses,
+        body => vec![body],
+    };
+
+    let mut results: HashMap<u64, JsonRpcResponse> = HashMap::new();
+    let mut batch_error: Option<JsonRpcError> = None;
+    for response in responses {
+        let response: JsonRpcResponse = match serde_json::from_value(response) {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+
+        match response.id.as_u64() {
+            Some(id) => {
+                results.insert(id, response);
+            }
+            // `id` 为 `null` 的错误(如解析失败)作用于整个批量请求
+            None => batch_error = batch_error.or(response.error),
+        }
+    }
+
+    ids.iter()
+        .map(|id| match results.remove(id) {
+            Some(response) => response.into_result(),
+            None => match &batch_error {
+                Some(err) => Err(Error::JsonRpc(err.clone())),
+                None => Err(Error::Error(format!("missing json-rpc response for id {} !", id))),
+            },
+        })
+        .collect()
+}
+
+/// 等待发送的调用
+struct Pending {
+    request: JsonRpcRequest,
+    sender: Option<oneshot::Sender<Result<Value, Error>>>, // 通知没有 `sender`
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<Pending>,
+    scheduled: bool, // 是否已经安排了发送
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct JsonRpcClient {
+    url: String,
+    options: HttpRequestOptions,
+    request: HttpRequest,
+    batch: bool,           // 是否合并请求
+    batch_window: u32,     // 合并的时间窗口(毫秒), `0` 为同一个微任务
+    max_batch_size: usize, // 最大批量数, 达到后立即发送
+    next_id: Rc<Cell<u64>>,
+    queue: Rc<RefCell<Queue>>,
+}
+
+#[wasm_bindgen]
+impl JsonRpcClient {
+    /**
+      创建客户端, `config` 中可以包含 `opts` 和 `request` 的字段, 以及 `batch`、`batchWindow`、`maxBatchSize`
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(url: String, config: JsValue) -> Result<JsonRpcClient, JsValue> {
+        let (_, options, request) = HttpInstance::get_config(&config, CONFIG_FIELDS)?;
+        let mut client = JsonRpcClient::from_options(&url, options);
+        client.request = request;
+
+        if let Some(config) = config.dyn_ref::<Object>() {
+            if let Some(batch) = HttpClient::get_value(config, "batch") {
+                client.batch = batch.is_truthy();
+            }
+
+            if let Some(batch_window) = HttpClient::get_value(config, "batchWindow").and_then(|value| value.as_f64()) {
+                client.batch_window = batch_window.max(0.0) as u32;
+            }
+
+            if let Some(max_batch_size) = HttpClient::get_value(config, "maxBatchSize").and_then(|value| value.as_f64()) {
+                client.max_batch_size = (max_batch_size as usize).max(1);
+            }
+        }
+
+        Ok(client)
+    }
+
+    /**
+      调用方法, 返回 `result`, 失败时 `reject` 一个 `name` 为 `JsonRpcError` 的 `Error`, 其中带有 `code` 和 `data`
+    */
+    #[wasm_bindgen(js_name = call)]
+    pub fn call_js(&self, method: String, params: JsValue) -> Promise {
+        let client = self.clone();
+        future_to_promise(async move {
+            let params = Self::get_params(params)?;
+            let result = client.call(&method, params).await.map_err(Self::to_js_error)?;
+            result.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| JsValue::from_str(&err.to_string()))
+        })
+    }
+
+    /**
+      发送通知, 服务端不会响应
+    */
+    #[wasm_bindgen(js_name = notify)]
+    pub fn notify_js(&self, method: String, params: JsValue) -> Promise {
+        let client = self.clone();
+        future_to_promise(async move {
+            let params = Self::get_params(params)?;
+            client.notify(&method, params).await.map_err(Self::to_js_error)?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+}
+
+impl JsonRpcClient {
+    pub fn from_options(url: &str, options: HttpRequestOptions) -> Self {
+        Self {
+            url: url.to_string(),
+            options,
+            request: HttpRequest::default(),
+            batch: true,
+            batch_window: 0,
+            max_batch_size: MAX_BATCH_SIZE,
+            next_id: Rc::new(Cell::new(1)),
+            queue: Rc::new(RefCell::new(Queue::default())),
+        }
+    }
+
+    /// 设置是否合并请求及合并的时间窗口
+    pub fn batch(mut self, batch: bool, batch_window: u32) -> Self {
+        self.batch = batch;
+        self.batch_window = batch_window;
+        self
+    }
+
+    /// 调用方法
+    pub async fn call(&self, method: &str, params: Option<Value>) -> Result<Value, Error> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let (sender, receiver) = oneshot::channel();
+        self.enqueue(Pending {
+            request: JsonRpcRequest::new(method, params, Some(id)),
+            sender: Some(sender),
+        });
+
+        receiver.await.map_err(|_| Error::Error("json-rpc request was cancelled !".to_string()))?
+    }
+
+    /// 发送通知
+    pub async fn notify(&self, method: &str, params: Option<Value>) -> Result<(), Error> {
+        let request = JsonRpcRequest::new(method, params, None);
+        if self.batch {
+            self.enqueue(Pending { request, sender: None });
+            return Ok(());
+        }
+
+        self.clone().send(vec![Pending { request, sender: None }]).await;
+        Ok(())
+    }
+
+    /// 加入队列, 达到最大批量数时立即发送, 否则在当前微任务或时间窗口结束后发送
+    fn enqueue(&self, pending: Pending) {
+        if !self.batch {
+            spawn_local(self.clone().send(vec![pending]));
+            return;
+        }
+
+        let mut queue = self.queue.borrow_mut();
+        queue.pending.push(pending);
+
+        if queue.pending.len() >= self.max_batch_size {
+            let batch = std::mem::take(&mut queue.pending);
+            spawn_local(self.clone().send(batch));
+            return;
+        }
+
+        if queue.scheduled {
+            return;
+        }
+
+        queue.scheduled = true;
+        let client = self.clone();
+        spawn_local(async move {
+            if client.batch_window > 0 {
+                timer::sleep(client.batch_window).await;
+            }
+
+            let batch = {
+                let mut queue = client.queue.borrow_mut();
+                queue.scheduled = false;
+                std::mem::take(&mut queue.pending)
+            };
+
+            if !batch.is_empty() {
+                client.send(batch).await;
+            }
+        });
+    }
+
+    /// 发送请求, 并把结果分发给每个调用
+    async fn send(self, batch: Vec<Pending>) {
+        let body = match batch.as_slice() {
+            [pending] => serde_json::to_value(&pending.request),
+            batch => serde_json::to_value(batch.iter().map(|pending| &pending.request).collect::<Vec<_>>()),
+        };
+
+        let result = match body {
+            Ok(body) => self.post(&body).await,
+            Err(err) => Err(Error::Error(err.to_string())),
+        };
+
+        let ids: Vec<u64> = batch.iter().filter_map(|pending| pending.request.id).collect();
+        let mut results = match result {
+            Ok(body) => split_batch(&ids, body).into_iter(),
+            // 全部是通知时服务端可能不返回 `body`
+            Err(Error::MissingResponseBody) if ids.is_empty() => Vec::new().into_iter(),
+            // 每个调用都得到相同类型的错误, 如 `CircuitOpen`、`Timeout`
+            Err(err) => ids.iter().map(|_| Err(err.duplicate())).collect::<Vec<_>>().into_iter(),
+        };
+
+        for pending in batch {
+            if let (Some(sender), Some(_)) = (pending.sender, pending.request.id) {
+                if let Some(result) = results.next() {
+                    let _ = sender.send(result);
+                }
+            }
+        }
+    }
+
+    async fn post(&self, body: &Value) -> Result<Value, Error> {
+        let mut options = self.options.clone();
+        options.url = self.url.clone();
+        options.method = Some(String::from("POST"));
+        options.request_type = Some(HttpRequestType::Json);
+        options.data = Some(body.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| Error::Error(err.to_string()))?);
+        HttpClient::resolve_idempotency_key(&mut options);
+
+        let response = HttpClient::call(options, self.request.clone()).await?;
+        if !response.ok && response.body.is_null() {
+            return Err(Error::Error(format!("json-rpc request failed with status {} !", response.status_code)));
+        }
+
+        Ok(response.body)
+    }
+
+    fn get_params(params: JsValue) -> Result<Option<Value>, JsValue> {
+        if params.is_undefined() || params.is_null() {
+            return Ok(None);
+        }
+
+        serde_wasm_bindgen::from_value(params).map(Some).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// `js` 的 `Error`, `Error::JsonRpc` 的 `name` 为 `JsonRpcError`, 并带上 `code` 和 `data`
+    fn to_js_error(err: Error) -> JsValue {
+        let rpc_error = match &err {
+            Error::JsonRpc(rpc_error) => rpc_error.clone(),
+            _ => return JsValue::from_str(&err.to_string()),
+        };
+
+        let js_error = js_sys::Error::new(&err.to_string());
+        js_error.set_name("JsonRpcError");
+        let _ = Reflect::set(&js_error, &JsValue::from_str("code"), &JsValue::from_f64(rpc_error.code as f64));
+        if let Some(data) = rpc_error.data.and_then(|data| data.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).ok()) {
+            let _ = Reflect::set(&js_error, &JsValue::from_str("data"), &data);
+        }
+
+        js_error.into()
+    }
+}
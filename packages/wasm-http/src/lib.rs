@@ -1,13 +1,44 @@
+pub mod case;
+pub mod circuit;
 mod client;
+pub mod download;
 mod error;
-mod request;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+pub mod graphql;
+pub mod har;
+pub mod instance;
+pub mod jsonrpc;
+pub mod metrics;
+pub mod multipart;
+pub mod options;
+pub mod pagination;
+pub mod request;
+pub mod response;
+pub mod schema;
 mod send;
+pub mod tus;
 
-use crate::error::Error;
+use crate::case::Case;
+pub use crate::client::websocket;
+pub use crate::error::Error;
+use crate::har::Wire;
+use crate::multipart::{Blobs, PartListener};
+pub use crate::request::compression;
+use crate::request::compression::Compression;
+pub use crate::request::idempotency;
+use crate::request::idempotency::IdempotencyKey;
+pub use crate::request::stream;
+pub use crate::request::HttpRequest;
+use crate::response::digest::VerifyDigest;
+use crate::response::response_type::ResponseType;
+use crate::response::timing::Timing;
+use crate::schema::{Schema, SchemaMode};
 use crate::send::HttpClient;
+use http::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 
 #[derive(Debug, Clone)]
@@ -21,7 +52,7 @@ pub enum HttpRequestType {
 }
 
 impl HttpRequestType {
-    fn get_content_type(&self) -> String {
+    pub(crate) fn get_content_type(&self) -> String {
         match self {
             HttpRequestType::Json => String::from("application/json;charset=UTF-8"),
             HttpRequestType::FormSubmit => String::from("application/x-www-form-urlencoded"),
@@ -46,21 +77,58 @@ impl HttpRequestType {
 
 #[derive(Default, Debug, Clone)]
 pub struct HttpRequestOptions {
-    pub url: String,                            // url
-    pub method: Option<String>,                 // method: post、get
-    pub data: Option<JsValue>,                  // data
-    pub headers: Option<Value>,                 // headers
-    pub timeout: Option<i32>,                   // timeout
-    pub request_type: Option<HttpRequestType>,  // request type
-    pub response_type: Option<HttpRequestType>, // response type
+    pub url: String,                             // url
+    pub method: Option<String>,                  // method: post、get
+    pub data: Option<JsValue>,                   // data, 可以为 `ReadableStream`
+    pub headers: Option<Value>,                  // headers
+    pub timeout: Option<i32>,                    // timeout
+    pub request_type: Option<HttpRequestType>,   // request type
+    pub response_type: Option<HttpRequestType>,  // response type
+    pub response_encoding: Option<String>,       // response encoding, 如 `gbk`、`gb18030`、`big5`、`shift_jis`、`utf-16`
+    pub schema: Option<Schema>,                  // 响应 `body` 的 schema
+    pub schema_mode: Option<SchemaMode>,         // schema 校验失败时的处理方式
+    pub compress_request: Option<Compression>,   // 请求 `body` 的压缩格式: gzip、deflate、br
+    pub compress_threshold: Option<u32>,         // 压缩阈值(字节), 默认为 `1024`
+    pub circuit_key: Option<String>,             // 熔断器的分组, 默认按 `host` 分组
+    pub transform_request_keys: Option<Case>,    // 请求 `body` 及 `query` 参数的 `key` 的命名风格
+    pub transform_response_keys: Option<Case>,   // 响应 `body` 的 `key` 的命名风格
+    pub transform_skip: Option<Vec<String>>,     // 不转换的 `key` 或路径
+    pub idempotency_key: Option<IdempotencyKey>, // `Idempotency-Key`, 自动生成或调用方提供
+    pub source: Option<web_sys::Request>,        // 原生的 `Request`, 未设置 `data` 时沿用其 `body`
+    pub verify_digest: Option<VerifyDigest>,     // 校验响应 `body` 的摘要
+    pub(crate) raw_json: bool,                   // `JSON` 响应保留原始文本, 由 `JS` 调用时使用 `JSON.parse` 解析
+    pub(crate) raw_blob: bool,                   // `Blob` 响应保留原始的 `Blob`, 写入 `blobs`, 不转换为字节数组
+    pub(crate) on_part: Option<PartListener>,    // 流式读取 `multipart/mixed` 响应, 每收到一个 `part` 回调一次
+    pub(crate) har_wire: Option<Wire>,           // 录制 `HAR` 时保存收到的原始响应
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct HttpResponseOptions {
     pub status_code: u16,
-    pub headers: HashMap<String, String>,
+    #[serde(rename = "statusText")]
+    pub status_text: String, // 状态信息
+    pub ok: bool,         // 状态码是否在 200-299 之间
+    pub url: String,      // 最终的 url, 重定向后为重定向后的地址
+    pub redirected: bool, // 是否经过重定向
+    #[serde(rename = "type")]
+    pub kind: ResponseType, // 响应类型: basic、cors、opaque、opaqueredirect
+    #[serde(with = "crate::response::headers")]
+    pub headers: HeaderMap, // 响应头, 同名多值
+    pub filtered: bool,   // 响应头是否被 `CORS` 过滤
     pub body: Value,
     pub error: String,
+    pub timing: Timing, // 请求耗时
+    #[serde(skip)]
+    pub blobs: Blobs, // `multipart` 响应中的文件, 转换成 `JsValue` 时写入 `body`
+    #[serde(skip)]
+    pub raw: Option<String>, // 未解析的 `JSON` 文本, 转换成 `JsValue` 时使用 `JSON.parse` 解析
+}
+
+impl HttpResponseOptions {
+    /// 把 `body` 转换为 `serde` 类型, 需要校验时在 `options.schema` 中使用 `Schema::of::<T>()`
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_value(self.body.clone()).map_err(|err| Error::Error(format!("invalid response body: {}", err)))
+    }
 }
 
 const TIMEOUT: i32 = 30;
@@ -69,9 +137,36 @@ const TIMEOUT: i32 = 30;
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
+
+    #[wasm_bindgen(js_namespace = console)]
+    fn warn(s: &str);
 }
 
+/// 发送请求, `opts` 为 `Request` 或 `URL` 时, `request` 中可以同时包含 `opts` 的字段
 #[wasm_bindgen]
-pub async fn send(opts: JsValue, request: JsValue) -> Result<JsValue, JsValue> {
+pub async fn send(
+    #[wasm_bindgen(unchecked_param_type = "HttpOptions | Request | URL")] opts: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "HttpRequestInit | (Partial<HttpOptions> & HttpRequestInit)")] request: JsValue,
+) -> Result<JsValue, JsValue> {
     HttpClient::send(opts, request).await
 }
+
+/// `Rust` 中发送请求, `data` 可以为 `stream::from_stream` 转换的 `ReadableStream`, 返回解析后的响应
+/// `options` 可以由 `HttpRequestOptions::from_request`、`from_url` 创建, `body` 可以使用 `HttpResponseOptions::json` 转换为 `serde` 类型
+pub async fn send_request(mut options: HttpRequestOptions, request: HttpRequest) -> Result<HttpResponseOptions, Error> {
+    HttpClient::resolve_idempotency_key(&mut options);
+    HttpClient::call(options, request).await
+}
+
+/// `beacon` 模式发送请求, 用于页面卸载时的统计上报, 不等待响应
+/// 返回 `true` 表示使用了 `keepalive` 或 `sendBeacon`, 页面卸载后浏览器会继续发送
+#[wasm_bindgen]
+pub fn beacon(#[wasm_bindgen(unchecked_param_type = "HttpOptions | Request | URL")] opts: JsValue, #[wasm_bindgen(unchecked_param_type = "HttpRequestInit | (Partial<HttpOptions> & HttpRequestInit)")] request: JsValue) -> Result<bool, JsValue> {
+    HttpClient::beacon(opts, request)
+}
+
+/// 分页请求, 返回按页返回响应的异步迭代器, 分页方式见 `opts.pagination`
+#[wasm_bindgen]
+pub fn paginate(opts: JsValue, request: JsValue) -> Result<js_sys::Object, JsValue> {
+    HttpClient::paginate(opts, request)
+}
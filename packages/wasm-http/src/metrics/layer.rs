@@ -0,0 +1,77 @@
+//! 统计指标的 `tower` `Layer`, 未设置回调时直接调用内部的服务
+
+use crate::client::call::Call;
+use crate::error::Error;
+use crate::metrics;
+use crate::response::timing;
+use crate::{HttpRequestOptions, HttpResponseOptions};
+use http::{Request, Response};
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::Layer;
+use tower_service::Service;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MetricsLayer {
+    method: &'static str,
+    url: String,
+}
+
+impl MetricsLayer {
+    pub(crate) fn new(options: &HttpRequestOptions) -> Self {
+        let (method, _) = Call::prepare_method(options);
+        Self { method, url: options.url.clone() }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService { inner, layer: self.clone() }
+    }
+}
+
+pub(crate) struct MetricsService<S> {
+    inner: S,
+    layer: MetricsLayer,
+}
+
+impl<S> Service<Request<Value>> for MetricsService<S>
+where
+    S: Service<Request<Value>, Response = Response<HttpResponseOptions>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = Response<HttpResponseOptions>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Value>) -> Self::Future {
+        let layer = self.layer.clone();
+        let enabled = metrics::is_enabled();
+        let start_time = if enabled { timing::now() } else { 0.0 };
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let result = future.await;
+            if enabled {
+                match &result {
+                    Ok(response) => {
+                        let response = response.body();
+                        let error = Some(response.error.clone()).filter(|error| !error.is_empty());
+                        metrics::record(layer.method, &layer.url, response.status_code, response.ok, response.timing.duration, error);
+                    }
+                    Err(err) => metrics::record(layer.method, &layer.url, 0, false, timing::now() - start_time, Some(err.to_string())),
+                }
+            }
+
+            result
+        })
+    }
+}
@@ -0,0 +1,241 @@
+/*!
+    请求指标, 调用 `setMetricsCallback` 后开始统计。
+    - 按 `method` 和路由模板(如 `/users/:id`)分组, 统计请求数、错误数、错误率及耗时直方图
+    - 路由模板优先匹配 `routes` 中的模板, 匹配不到时把数字、`UUID` 及较长的十六进制路径段替换成 `:id`
+    - 每个请求结束后回调 `callback(sample, metrics)`, `sample` 为本次请求, `metrics` 为所在分组的统计, 可以转发到监控系统
+*/
+
+pub(crate) mod layer;
+
+use js_sys::{Array, Function, Object};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// 默认的直方图分桶(毫秒)
+pub const BUCKETS: [f64; 8] = [50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0];
+
+thread_local! {
+    static METRICS: RefCell<Option<(Metrics, Function)>> = const { RefCell::new(None) };
+}
+
+/// 一次请求
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Sample {
+    pub route: String,
+    pub method: String,
+    pub url: String,
+    pub status: u16, // 请求失败时为 `0`
+    pub ok: bool,
+    pub duration: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Sample {
+    /// 请求失败或状态码不在 `200-299` 之间
+    pub fn is_error(&self) -> bool {
+        self.error.is_some() || !self.ok
+    }
+}
+
+/// 一个分组的统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RouteMetrics {
+    pub route: String,
+    pub method: String,
+    pub count: u64,
+    pub errors: u64,
+    #[serde(rename = "errorRate")]
+    pub error_rate: f64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub buckets: Vec<f64>, // 直方图上界, 包含该值
+    pub counts: Vec<u64>,  // 每个分桶的请求数, 最后一个为超出最大上界的请求数
+}
+
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    routes: Vec<String>,
+    buckets: Vec<f64>,
+    stats: BTreeMap<(String, String), RouteMetrics>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new(Vec::new(), BUCKETS.to_vec())
+    }
+}
+
+impl Metrics {
+    pub fn new(routes: Vec<String>, mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|bucket| bucket.is_finite());
+        buckets.sort_by(|a, b| a.total_cmp(b));
+        buckets.dedup();
+        Self { routes, buckets, stats: BTreeMap::new() }
+    }
+
+    /// `url` 对应的路由模板
+    pub fn route(&self, url: &str) -> String {
+        route_template(url, &self.routes)
+    }
+
+    /// 记录一次请求, 返回所在分组的统计
+    pub fn record(&mut self, sample: &Sample) -> &RouteMetrics {
+        let buckets = &self.buckets;
+        let metrics = self.stats.entry((sample.route.clone(), sample.method.clone())).or_insert_with(|| RouteMetrics {
+            route: sample.route.clone(),
+            method: sample.method.clone(),
+            min: f64::MAX,
+            buckets: buckets.clone(),
+            counts: vec![0; buckets.len() + 1],
+            ..Default::default()
+        });
+
+        metrics.count += 1;
+        if sample.is_error() {
+            metrics.errors += 1;
+        }
+
+        metrics.error_rate = metrics.errors as f64 / metrics.count as f64;
+        metrics.sum += sample.duration;
+        metrics.min = metrics.min.min(sample.duration);
+        metrics.max = metrics.max.max(sample.duration);
+
+        let index = metrics.buckets.iter().position(|bucket| sample.duration <= *bucket).unwrap_or(metrics.buckets.len());
+        metrics.counts[index] += 1;
+        metrics
+    }
+
+    pub fn snapshot(&self) -> Vec<RouteMetrics> {
+        self.stats.values().cloned().collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.stats.clear();
+    }
+}
+
+/// 路由模板, 去掉协议、域名、`query` 及 `hash`
+pub fn route_template(url: &str, routes: &[String]) -> String {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|index| &rest[index..]).unwrap_or("/"),
+        None => url,
+    };
+
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+    // 模板中 `:` 开头的路径段匹配任意值, `*` 匹配剩余的路径
+    let matched = routes.iter().find(|route| {
+        let route_segments: Vec<&str> = route.split(['?', '#']).next().unwrap_or_default().split('/').filter(|segment| !segment.is_empty()).collect();
+        if route_segments.last() == Some(&"*") {
+            let prefix = &route_segments[..route_segments.len() - 1];
+            return segments.len() >= prefix.len() && prefix.iter().zip(segments.iter()).all(|(route, segment)| route.starts_with(':') || route == segment);
+        }
+
+        route_segments.len() == segments.len() && route_segments.iter().zip(segments.iter()).all(|(route, segment)| route.starts_with(':') || route == segment)
+    });
+
+    if let Some(route) = matched {
+        return route.clone();
+    }
+
+    let segments: Vec<&str> = segments.into_iter().map(|segment| if is_id(segment) { ":id" } else { segment }).collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// 数字、`UUID` 或不少于 `16` 位的十六进制
+fn is_id(segment: &str) -> bool {
+    let is_number = segment.chars().all(|c| c.is_ascii_digit());
+    let is_uuid = segment.len() == 36 && segment.chars().enumerate().all(|(index, c)| if [8, 13, 18, 23].contains(&index) { c == '-' } else { c.is_ascii_hexdigit() });
+    let is_hex = segment.len() >= 16 && segment.chars().all(|c| c.is_ascii_hexdigit());
+    is_number || is_uuid || is_hex
+}
+
+/// 是否正在统计
+pub(crate) fn is_enabled() -> bool {
+    METRICS.with(|metrics| metrics.borrow().is_some())
+}
+
+/// 记录一次请求并回调, 回调的异常被忽略
+pub(crate) fn record(method: &str, url: &str, status: u16, ok: bool, duration: f64, error: Option<String>) {
+    let result = METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        let (metrics, callback) = metrics.as_mut()?;
+        let sample = Sample {
+            route: metrics.route(url),
+            method: method.to_string(),
+            url: url.to_string(),
+            status,
+            ok,
+            duration,
+            error,
+        };
+
+        let route_metrics = metrics.record(&sample).clone();
+        Some((callback.clone(), sample, route_metrics))
+    });
+
+    // 释放 `borrow` 后再回调, 回调中可能调用 `getMetrics`
+    if let Some((callback, sample, route_metrics)) = result {
+        if let (Ok(sample), Ok(route_metrics)) = (to_js(&sample), to_js(&route_metrics)) {
+            let _ = callback.call2(&JsValue::NULL, &sample, &route_metrics);
+        }
+    }
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/**
+  设置指标回调, 每个请求结束后调用 `callback(sample, metrics)`, 为 `null` 时停止统计
+  `options` 中可以设置 `routes`(路由模板, 如 `/users/:id`, 末尾的 `*` 匹配剩余的路径)和 `buckets`(直方图上界, 毫秒)
+*/
+#[wasm_bindgen(js_name = setMetricsCallback)]
+pub fn set_metrics_callback(callback: JsValue, options: JsValue) -> Result<(), JsValue> {
+    if callback.is_null() || callback.is_undefined() {
+        METRICS.with(|metrics| *metrics.borrow_mut() = None);
+        return Ok(());
+    }
+
+    let callback = callback.dyn_into::<Function>().map_err(|_| JsValue::from_str(&crate::Error::Error("`callback` is not a function !".to_string()).to_string()))?;
+    let mut routes: Vec<String> = Vec::new();
+    let mut buckets: Vec<f64> = BUCKETS.to_vec();
+    if let Some(obj) = options.dyn_ref::<Object>() {
+        if let Some(value) = crate::send::HttpClient::get_value(obj, "routes") {
+            routes = Array::from(&value).iter().filter_map(|route| route.as_string()).collect();
+        }
+
+        if let Some(value) = crate::send::HttpClient::get_value(obj, "buckets") {
+            buckets = Array::from(&value).iter().filter_map(|bucket| bucket.as_f64()).collect();
+        }
+    }
+
+    METRICS.with(|metrics| *metrics.borrow_mut() = Some((Metrics::new(routes, buckets), callback)));
+    Ok(())
+}
+
+/**
+  获取当前的统计, 按路由模板和 `method` 分组
+*/
+#[wasm_bindgen(js_name = getMetrics)]
+pub fn get_metrics() -> Result<JsValue, JsValue> {
+    let snapshot = METRICS.with(|metrics| metrics.borrow().as_ref().map(|(metrics, _)| metrics.snapshot()).unwrap_or_default());
+    to_js(&snapshot)
+}
+
+/**
+  清空统计, 不影响回调
+*/
+#[wasm_bindgen(js_name = resetMetrics)]
+pub fn reset_metrics() {
+    METRICS.with(|metrics| {
+        if let Some((metrics, _)) = metrics.borrow_mut().as_mut() {
+            metrics.reset();
+        }
+    });
+}
@@ -0,0 +1,492 @@
+/*!
+    `multipart` 请求与响应。
+    - `Multipart` 构建 `FormData`, 支持文本、`Blob`/`File`(可以指定文件名及 `Content-Type`)及 `JSON` 字段, `Rust` 和 `JS` 都可以使用
+    - 响应为 `multipart/form-data` 时, 文本字段为字符串, 文件字段为 `File`, 同名字段为数组
+    - 响应为 `multipart/mixed` 等其他 `multipart` 类型时, 返回 `part` 数组, 每个 `part` 包含 `headers` 和 `body`,
+      `body` 按 `part` 的 `Content-Type` 解析为 `JSON`、文本或 `Blob`
+    - `MultipartParser` 流式解析, `GraphQL` 的 `@defer`/`@stream` 等增量响应每收到一个 `part` 回调一次
+*/
+
+use crate::error::Error;
+use crate::response::charset;
+use js_sys::{Array, Reflect, Uint8Array, JSON};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, BlobPropertyBag, File, FormData, ReadableStreamDefaultReader};
+
+/// 响应中的二进制数据, 转换成 `JsValue` 时替换 `body` 中 `path` 处的值
+pub type Blobs = Vec<(Vec<String>, Blob)>;
+
+/// 流式读取 `multipart/mixed` 响应时的回调, 每收到一个 `part` 调用一次, 返回 `false` 时停止读取
+#[derive(Clone)]
+pub(crate) struct PartListener(pub(crate) Rc<dyn Fn(Part) -> Result<bool, Error>>);
+
+impl fmt::Debug for PartListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PartListener")
+    }
+}
+
+/// 从 `Content-Type` 中获取 `boundary`, 不是 `multipart` 时返回 `None`
+#[doc = "https://www.rfc-editor.org/rfc/rfc2046#section-5.1.1"]
+pub fn get_boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    let mime = params.next()?.trim().to_lowercase();
+    if !mime.starts_with("multipart/") {
+        return None;
+    }
+
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+/// 是否为 `multipart/mixed`
+pub fn is_mixed(content_type: &str) -> bool {
+    content_type.split(';').next().map(|mime| mime.trim().eq_ignore_ascii_case("multipart/mixed")).unwrap_or(false)
+}
+
+/// 是否为 `multipart/form-data`
+pub fn is_form_data(content_type: &str) -> bool {
+    content_type.split(';').next().map(|mime| mime.trim().eq_ignore_ascii_case("multipart/form-data")).unwrap_or(false)
+}
+
+/// 响应中的一个 `part`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Part {
+    pub headers: Vec<(String, String)>, // 名称小写
+    pub body: Vec<u8>,
+}
+
+impl Part {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(header, _)| header.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// `Content-Disposition` 中的 `name`
+    pub fn name(&self) -> Option<String> {
+        self.disposition_param("name")
+    }
+
+    /// `Content-Disposition` 中的 `filename`
+    pub fn filename(&self) -> Option<String> {
+        self.disposition_param("filename")
+    }
+
+    fn disposition_param(&self, name: &str) -> Option<String> {
+        self.header("content-disposition")?
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.split_once('='))
+            .find(|(param, _)| param.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+    }
+
+    /// 文本 `part` 的值, `JSON` 解析为对象, 其他文本为字符串, 二进制返回 `None`
+    pub fn value(&self) -> Option<Value> {
+        let content_type = self.content_type().unwrap_or("text/plain").to_lowercase();
+        let mime = content_type.split(';').next().unwrap_or_default().trim();
+        let text = || charset::decode_text(&self.body, Some(&content_type), None).ok();
+
+        if mime == "application/json" || mime.ends_with("+json") {
+            let text = text()?;
+            return Some(serde_json::from_str(&text).unwrap_or(Value::String(text)));
+        }
+
+        if mime.starts_with("text/") || mime == "application/xml" || mime.ends_with("+xml") || mime == "application/x-www-form-urlencoded" {
+            return text().map(Value::String);
+        }
+
+        None
+    }
+}
+
+/// 解析完整的 `multipart` `body`, 忽略第一个分隔符之前及结束分隔符之后的内容
+pub fn parse(body: &[u8], boundary: &str) -> Vec<Part> {
+    let mut parser = MultipartParser::new(boundary);
+    let mut parts = parser.feed(body);
+    parts.extend(parser.finish());
+    parts
+}
+
+/// 流式解析, 每次写入一段数据, 返回已经完整的 `part`, 用于 `multipart/mixed` 的增量响应
+#[derive(Debug)]
+pub struct MultipartParser {
+    delimiter: Vec<u8>, // `\r\n--boundary`
+    buffer: Vec<u8>,
+    started: bool, // 是否已经读到第一个分隔符
+    done: bool,    // 是否已经读到结束分隔符 `--boundary--`
+}
+
+impl MultipartParser {
+    pub fn new(boundary: &str) -> Self {
+        Self {
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            // 第一个分隔符前面可能没有换行
+            buffer: b"\r\n".to_vec(),
+            started: false,
+            done: false,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Part> {
+        let mut parts: Vec<Part> = Vec::new();
+        if self.done {
+            return parts;
+        }
+
+        self.buffer.extend_from_slice(chunk);
+        while let Some(position) = find(&self.buffer, &self.delimiter) {
+            // 需要分隔符后面的两个字节来判断是否结束
+            let end = position + self.delimiter.len();
+            if self.buffer.len() < end + 2 {
+                break;
+            }
+
+            let segment: Vec<u8> = self.buffer.drain(..end).take(position).collect();
+            if self.started {
+                parts.extend(Self::get_part(&segment));
+            }
+
+            self.started = true;
+            if self.buffer.starts_with(b"--") {
+                self.done = true;
+                self.buffer.clear();
+                break;
+            }
+        }
+
+        parts
+    }
+
+    /// 没有结束分隔符时, 剩余的内容作为最后一个 `part`
+    pub fn finish(&mut self) -> Option<Part> {
+        if self.done || !self.started {
+            return None;
+        }
+
+        self.done = true;
+        let segment = std::mem::take(&mut self.buffer);
+        let end = find(&segment, &self.delimiter).unwrap_or(segment.len());
+        Self::get_part(&segment[..end])
+    }
+
+    /// 去掉分隔符所在行的剩余部分
+    fn get_part(segment: &[u8]) -> Option<Part> {
+        let start = find(segment, b"\r\n")? + 2;
+        Some(parse_part(&segment[start..]))
+    }
+}
+
+fn parse_part(part: &[u8]) -> Part {
+    let (head, body) = if part.starts_with(b"\r\n") {
+        (&part[..0], &part[2..])
+    } else {
+        match find(part, b"\r\n\r\n") {
+            Some(position) => (&part[..position], &part[position + 4..]),
+            None => (part, &part[part.len()..]),
+        }
+    };
+
+    let headers = String::from_utf8_lossy(head)
+        .split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    Part { headers, body: body.to_vec() }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// 二进制数据在 `body` 中的占位值
+fn placeholder(name: Option<String>, content_type: &str, size: f64) -> Value {
+    let mut value = Map::new();
+    if let Some(name) = name {
+        value.insert(String::from("name"), Value::String(name));
+    }
+
+    value.insert(String::from("type"), Value::String(content_type.to_string()));
+    value.insert(String::from("size"), Value::from(size as u64));
+    Value::Object(value)
+}
+
+/// 把 `FormData` 转换成对象, 同名字段为数组
+pub(crate) fn from_form_data(form_data: &FormData) -> Result<(Value, Blobs), Error> {
+    let mut fields: Vec<(String, Vec<Result<String, File>>)> = Vec::new();
+    if let Some(iterator) = js_sys::try_iter(form_data).map_err(Error::js_error)? {
+        for item in iterator {
+            let entry = Array::from(&item.map_err(Error::js_error)?);
+            let name = entry.get(0).as_string().unwrap_or_default();
+            let value = entry.get(1);
+            let value = match value.as_string() {
+                Some(value) => Ok(value),
+                None => Err(value.dyn_into::<File>().map_err(Error::js_error)?),
+            };
+
+            match fields.iter_mut().find(|(field, _)| *field == name) {
+                Some((_, values)) => values.push(value),
+                None => fields.push((name, vec![value])),
+            }
+        }
+    }
+
+    let mut body = Map::new();
+    let mut blobs: Blobs = Vec::new();
+    for (name, values) in fields {
+        let multiple = values.len() > 1;
+        let mut items: Vec<Value> = Vec::new();
+        for (index, value) in values.into_iter().enumerate() {
+            match value {
+                Ok(text) => items.push(Value::String(text)),
+                Err(file) => {
+                    let mut path = vec![name.clone()];
+                    if multiple {
+                        path.push(index.to_string());
+                    }
+
+                    items.push(placeholder(Some(file.name()), &file.type_(), file.size()));
+                    blobs.push((path, file.into()));
+                }
+            }
+        }
+
+        let value = if multiple { Value::Array(items) } else { items.pop().unwrap_or(Value::Null) };
+        body.insert(name, value);
+    }
+
+    Ok((Value::Object(body), blobs))
+}
+
+/// 把 `part` 转换成 `[{headers, body}]`, 二进制的 `body` 为 `Blob`
+pub(crate) fn from_parts(parts: Vec<Part>) -> Result<(Value, Blobs), Error> {
+    let mut items: Vec<Value> = Vec::new();
+    let mut blobs: Blobs = Vec::new();
+    for (index, part) in parts.into_iter().enumerate() {
+        let headers: Map<String, Value> = part.headers.iter().map(|(name, value)| (name.clone(), Value::String(value.clone()))).collect();
+        let body = match part.value() {
+            Some(value) => value,
+            None => {
+                let content_type = part.content_type().unwrap_or("application/octet-stream").to_string();
+                let options = BlobPropertyBag::new();
+                options.set_type(&content_type);
+                let bytes = Uint8Array::from(part.body.as_slice());
+                let blob = Blob::new_with_u8_array_sequence_and_options(&Array::of1(&bytes), &options).map_err(Error::js_error)?;
+                blobs.push((vec![index.to_string(), String::from("body")], blob));
+                placeholder(part.filename(), &content_type, part.body.len() as f64)
+            }
+        };
+
+        let mut item = Map::new();
+        item.insert(String::from("headers"), Value::Object(headers));
+        item.insert(String::from("body"), body);
+        items.push(Value::Object(item));
+    }
+
+    Ok((Value::Array(items), blobs))
+}
+
+/// 把 `Blob` 写入 `body` 中的对应位置
+pub(crate) fn set_blobs(body: &JsValue, blobs: &Blobs) -> Result<(), JsValue> {
+    for (path, blob) in blobs {
+        let mut target = body.clone();
+        for (index, key) in path.iter().enumerate() {
+            let key = JsValue::from_str(key);
+            if index == path.len() - 1 {
+                js_sys::Reflect::set(&target, &key, blob)?;
+            } else {
+                target = js_sys::Reflect::get(&target, &key)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 字段
+#[derive(Debug, Clone)]
+enum Field {
+    Text(String),
+    Blob { blob: Blob, filename: Option<String>, content_type: Option<String> },
+    Json(String),
+}
+
+/// `multipart/form-data` 构建器, 发送时 `type` 为 `2`, 不要手动设置 `Content-Type`, 浏览器会自动添加 `boundary`
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct Multipart {
+    fields: Vec<(String, Field)>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// 文本字段
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.fields.push((name.to_string(), Field::Text(value.to_string())));
+        self
+    }
+
+    /// `Blob`/`File` 字段, `content_type` 覆盖 `Blob` 的 `type`
+    pub fn blob(mut self, name: &str, blob: Blob, filename: Option<&str>, content_type: Option<&str>) -> Self {
+        self.fields.push((
+            name.to_string(),
+            Field::Blob {
+                blob,
+                filename: filename.map(String::from),
+                content_type: content_type.map(String::from),
+            },
+        ));
+        self
+    }
+
+    /// `JSON` 字段, 以 `application/json` 的 `Blob` 发送
+    pub fn json(mut self, name: &str, value: &Value) -> Self {
+        self.fields.push((name.to_string(), Field::Json(value.to_string())));
+        self
+    }
+
+    pub fn into_form_data(&self) -> Result<FormData, Error> {
+        let form_data = FormData::new().map_err(Error::js_error)?;
+        for (name, field) in self.fields.iter() {
+            match field {
+                Field::Text(value) => form_data.append_with_str(name, value).map_err(Error::js_error)?,
+                Field::Blob { blob, filename, content_type } => {
+                    // 没有文件名时使用 `File` 的 `name`, 重新设置 `type` 后 `name` 会丢失
+                    let filename = filename.clone().or_else(|| blob.dyn_ref::<File>().map(|file| file.name()));
+                    let blob = match content_type {
+                        Some(content_type) => {
+                            let options = BlobPropertyBag::new();
+                            options.set_type(content_type);
+                            Blob::new_with_blob_sequence_and_options(&Array::of1(blob), &options).map_err(Error::js_error)?
+                        }
+                        None => blob.clone(),
+                    };
+
+                    match filename {
+                        Some(filename) => form_data.append_with_blob_and_filename(name, &blob, &filename).map_err(Error::js_error)?,
+                        None => form_data.append_with_blob(name, &blob).map_err(Error::js_error)?,
+                    }
+                }
+                Field::Json(value) => {
+                    let options = BlobPropertyBag::new();
+                    options.set_type("application/json");
+                    let blob = Blob::new_with_str_sequence_and_options(&Array::of1(&JsValue::from_str(value)), &options).map_err(Error::js_error)?;
+                    form_data.append_with_blob(name, &blob).map_err(Error::js_error)?;
+                }
+            }
+        }
+
+        Ok(form_data)
+    }
+}
+
+#[wasm_bindgen]
+impl Multipart {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Multipart {
+        Multipart::new()
+    }
+
+    /**
+      添加文本字段
+    */
+    #[wasm_bindgen(js_name = text)]
+    pub fn add_text(&mut self, name: String, value: String) {
+        self.fields.push((name, Field::Text(value)));
+    }
+
+    /**
+      添加 `Blob`/`File` 字段, `filename` 和 `contentType` 可选
+    */
+    #[wasm_bindgen(js_name = file)]
+    pub fn add_file(&mut self, name: String, blob: Blob, filename: Option<String>, content_type: Option<String>) {
+        self.fields.push((name, Field::Blob { blob, filename, content_type }));
+    }
+
+    /**
+      添加 `JSON` 字段
+    */
+    #[wasm_bindgen(js_name = json)]
+    pub fn add_json(&mut self, name: String, value: JsValue) -> Result<(), JsValue> {
+        let value = JSON::stringify(&value)?;
+        self.fields.push((name, Field::Json(String::from(value))));
+        Ok(())
+    }
+
+    /**
+      生成 `FormData`, 作为 `data` 发送
+    */
+    pub fn build(&self) -> Result<FormData, JsValue> {
+        self.into_form_data().map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+/// 读取 `multipart` 响应, `multipart/form-data` 使用 `Response.formData()`, 其他类型自行解析
+pub(crate) async fn read_response(response: &web_sys::Response, content_type: &str) -> Result<(Value, Blobs), Error> {
+    if let (false, Some(boundary)) = (is_form_data(content_type), get_boundary(content_type)) {
+        let array_buffer = JsFuture::from(response.array_buffer().map_err(Error::js_error)?).await.map_err(Error::js_error)?;
+        let bytes = Uint8Array::new(&array_buffer).to_vec();
+        return from_parts(parse(&bytes, &boundary));
+    }
+
+    let form_data = JsFuture::from(response.form_data().map_err(Error::js_error)?).await.map_err(Error::js_error)?;
+    let form_data = form_data.dyn_into::<FormData>().map_err(Error::js_error)?;
+    from_form_data(&form_data)
+}
+
+/// 流式读取 `multipart/mixed` 响应, 每收到一个 `part` 回调 `listener`, 未指定 `boundary` 时为 `-`
+pub(crate) async fn read_stream(response: &web_sys::Response, content_type: &str, listener: &PartListener) -> Result<(), Error> {
+    let boundary = get_boundary(content_type).unwrap_or_else(|| String::from("-"));
+    let stream = response.body().ok_or(Error::MissingResponseBody)?;
+    let reader: ReadableStreamDefaultReader = stream.get_reader().unchecked_into();
+    let mut parser = MultipartParser::new(&boundary);
+
+    loop {
+        let chunk = JsFuture::from(reader.read()).await.map_err(Error::js_error)?;
+        let done = Reflect::get(&chunk, &JsValue::from_str("done")).map(|done| done.is_truthy()).unwrap_or(true);
+        let parts = if done {
+            parser.finish().into_iter().collect()
+        } else {
+            let value = Reflect::get(&chunk, &JsValue::from_str("value")).map_err(Error::js_error)?;
+            parser.feed(&Uint8Array::new(&value).to_vec())
+        };
+
+        for part in parts {
+            if !(listener.0)(part)? {
+                let _ = reader.cancel();
+                return Ok(());
+            }
+        }
+
+        if done {
+            return Ok(());
+        }
+
+        if parser.is_done() {
+            let _ = reader.cancel();
+            return Ok(());
+        }
+    }
+}
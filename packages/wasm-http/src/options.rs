@@ -0,0 +1,368 @@
+/*!
+    `JS` 传入的 `opts` 和 `request` 通过 `serde_wasm_bindgen` 解析成类型化的结构体。
+    每个字段单独解析, 收集所有字段的错误(未知字段、类型错误、不支持的枚举值等)后一起返回 `Error::InvalidOptions`。
+    `data` 和 `pagination` 等需要保留原始 `JsValue` 的字段不在结构体中, 由调用方读取。
+*/
+
+use crate::case::Case;
+use crate::error::Error;
+use crate::request::cache::Cache;
+use crate::request::compression::Compression;
+use crate::request::credentials::Credentials;
+use crate::request::idempotency::IdempotencyKey;
+use crate::request::mode::Mode;
+use crate::request::priority::Priority;
+use crate::request::redirect::Redirect;
+use crate::request::referrer_policy::ReferrerPolicy;
+use crate::request::HttpRequest;
+use crate::response::digest::{self, VerifyDigest};
+use crate::schema::{Schema, SchemaMode};
+use crate::{HttpRequestOptions, HttpRequestType};
+use js_sys::Object;
+use serde::de::{value::StrDeserializer, Error as _};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use web_sys::AbortSignal;
+
+/// `opts` 的字段
+pub(crate) const OPTIONS_FIELDS: &[&str] = &[
+    "url",
+    "method",
+    "data",
+    "headers",
+    "timeout",
+    "type",
+    "responseType",
+    "responseEncoding",
+    "schema",
+    "schemaMode",
+    "compressRequest",
+    "compressThreshold",
+    "circuitKey",
+    "transformRequestKeys",
+    "transformResponseKeys",
+    "transformSkip",
+    "idempotencyKey",
+    "verifyDigest",
+    "pagination",
+];
+
+/// `request` 的字段
+pub(crate) const REQUEST_FIELDS: &[&str] = &["cache", "credentials", "integrity", "mode", "redirect", "referrer", "referrerPolicy", "keepalive", "priority", "signal"];
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_OPTIONS: &'static str = r#"
+export type HttpRequestType = 0 | 1 | 2 | 3 | 4 | 5;
+
+export interface HttpOptions {
+  url: string;
+  method?: 'get' | 'post' | 'put' | 'delete' | 'patch' | 'head' | 'options' | string;
+  data?: any; // `ReadableStream` 时流式发送
+  headers?: Record<string, string | null>;
+  timeout?: number | string;
+  type?: HttpRequestType | `${HttpRequestType}`;
+  responseType?: HttpRequestType | `${HttpRequestType}`;
+  responseEncoding?: string;
+  schema?: object;
+  schemaMode?: 'error' | 'warn';
+  compressRequest?: 'gzip' | 'deflate' | 'br';
+  compressThreshold?: number;
+  circuitKey?: string;
+  transformRequestKeys?: 'camel' | 'snake' | 'kebab' | 'pascal';
+  transformResponseKeys?: 'camel' | 'snake' | 'kebab' | 'pascal';
+  transformSkip?: string[];
+  idempotencyKey?: boolean | string;
+  verifyDigest?: boolean | string;
+  pagination?: object;
+}
+
+export interface HttpRequestInit {
+  cache?: 'default' | 'no-store' | 'reload' | 'no-cache' | 'force-cache' | 'only-if-cached';
+  credentials?: 'omit' | 'same-origin' | 'include';
+  integrity?: string;
+  mode?: 'same-origin' | 'cors' | 'no-cors' | 'navigate';
+  redirect?: 'follow' | 'error' | 'manual';
+  referrer?: string;
+  keepalive?: boolean;
+  priority?: 'high' | 'low' | 'auto';
+  signal?: AbortSignal;
+  referrerPolicy?: '' | 'none' | 'no-referrer' | 'no-referrer-when-downgrade' | 'origin' | 'origin-when-cross-origin' | 'unsafe-url' | 'same-origin' | 'strict-origin' | 'strict-origin-when-cross-origin';
+}
+"#;
+
+/// 单个字段的解析结果, 解析失败时保留错误信息, 不影响其他字段
+#[derive(Debug, Default)]
+pub(crate) enum Field<T> {
+    #[default]
+    Missing, // 未设置、`undefined` 或 `null`
+    Value(T),
+    Invalid(String),
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Field<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Option::<T>::deserialize(deserializer) {
+            Ok(Some(value)) => Field::Value(value),
+            Ok(None) => Field::Missing,
+            Err(err) => Field::Invalid(err.to_string()),
+        })
+    }
+}
+
+impl<T> Field<T> {
+    /// 取出值, 错误写入 `errors`
+    fn take(self, name: &str, errors: &mut Vec<String>) -> Option<T> {
+        match self {
+            Field::Missing => None,
+            Field::Value(value) => Some(value),
+            Field::Invalid(err) => {
+                errors.push(format!("`{}`: {}", name, err));
+                None
+            }
+        }
+    }
+}
+
+/// 枚举值, 去除首尾空白并忽略大小写, 空字符串视为未设置(`referrerPolicy` 的 `""` 除外)
+#[derive(Debug)]
+pub(crate) struct Keyword<T>(Option<T>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Keyword<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?.trim().to_lowercase();
+        let parse = |value: &str| T::deserialize(StrDeserializer::<serde::de::value::Error>::new(value));
+        match parse(&value) {
+            Ok(value) => Ok(Keyword(Some(value))),
+            Err(_) if value.is_empty() => Ok(Keyword(None)),
+            Err(err) => Err(D::Error::custom(err)),
+        }
+    }
+}
+
+impl<T> Field<Keyword<T>> {
+    /// 取出枚举值, 错误写入 `errors`
+    fn take_keyword(self, name: &str, errors: &mut Vec<String>) -> Option<T> {
+        self.take(name, errors).and_then(|keyword| keyword.0)
+    }
+}
+
+/// 数字或数字字符串, 如 `timeout: "30"`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum NumberOrString {
+    Number(f64),
+    String(String),
+}
+
+impl fmt::Display for NumberOrString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberOrString::Number(number) => write!(f, "{}", number),
+            NumberOrString::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl NumberOrString {
+    /// 转换为整数, `1.5`、`NaN` 及超出范围的数字返回 `None`
+    fn to_i32(&self) -> Option<i32> {
+        match self {
+            NumberOrString::Number(number) if number.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(number) => Some(*number as i32),
+            NumberOrString::Number(_) => None,
+            NumberOrString::String(value) => value.trim().parse::<i32>().ok(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum BoolOrString {
+    Bool(bool),
+    String(String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct JsHttpOptions {
+    url: Field<String>,
+    method: Field<String>,
+    headers: Field<Map<String, Value>>,
+    timeout: Field<NumberOrString>,
+    #[serde(rename = "type")]
+    request_type: Field<NumberOrString>,
+    response_type: Field<NumberOrString>,
+    response_encoding: Field<String>,
+    schema: Field<Value>,
+    schema_mode: Field<Keyword<SchemaMode>>,
+    compress_request: Field<Keyword<Compression>>,
+    compress_threshold: Field<NumberOrString>,
+    circuit_key: Field<String>,
+    transform_request_keys: Field<Keyword<Case>>,
+    transform_response_keys: Field<Keyword<Case>>,
+    transform_skip: Field<Vec<String>>,
+    idempotency_key: Field<BoolOrString>,
+    verify_digest: Field<BoolOrString>,
+}
+
+impl JsHttpOptions {
+    /// 转换成 `HttpRequestOptions`, 错误写入 `errors`
+    pub fn into_options(self, errors: &mut Vec<String>) -> HttpRequestOptions {
+        let non_empty = |value: Option<String>| value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        let mut options = HttpRequestOptions {
+            url: self.url.take("url", errors).unwrap_or_default(),
+            method: self.method.take("method", errors),
+            response_encoding: non_empty(self.response_encoding.take("responseEncoding", errors)),
+            schema_mode: self.schema_mode.take_keyword("schemaMode", errors),
+            compress_request: self.compress_request.take_keyword("compressRequest", errors),
+            circuit_key: non_empty(self.circuit_key.take("circuitKey", errors)),
+            transform_request_keys: self.transform_request_keys.take_keyword("transformRequestKeys", errors),
+            transform_response_keys: self.transform_response_keys.take_keyword("transformResponseKeys", errors),
+            transform_skip: self.transform_skip.take("transformSkip", errors),
+            ..Default::default()
+        };
+
+        // headers, 值为 `null` 时删除实例中的同名 `header`
+        if let Some(headers) = self.headers.take("headers", errors) {
+            for (name, value) in &headers {
+                if !value.is_string() && !value.is_null() {
+                    errors.push(format!("`headers.{}`: invalid type: {}, expected a string", name, value));
+                }
+            }
+
+            options.headers = Some(Value::Object(headers));
+        }
+
+        if let Some(timeout) = self.timeout.take("timeout", errors) {
+            match timeout.to_i32() {
+                Some(timeout) => options.timeout = Some(timeout),
+                None => errors.push(format!("`timeout`: invalid number: {}", timeout)),
+            }
+        }
+
+        if let Some(threshold) = self.compress_threshold.take("compressThreshold", errors) {
+            match threshold.to_i32().and_then(|value| u32::try_from(value).ok()) {
+                Some(value) => options.compress_threshold = Some(value),
+                None => errors.push(format!("`compressThreshold`: invalid number: {}", threshold)),
+            }
+        }
+
+        for (name, field, request_type) in [("type", self.request_type, &mut options.request_type), ("responseType", self.response_type, &mut options.response_type)] {
+            if let Some(value) = field.take(name, errors) {
+                match value.to_i32().filter(|value| (0..=5).contains(value)) {
+                    Some(value) => *request_type = Some(HttpRequestType::get_type(value)),
+                    None => errors.push(format!("`{}`: invalid value: {}, expected 0-5", name, value)),
+                }
+            }
+        }
+
+        if let Some(schema) = self.schema.take("schema", errors) {
+            if schema.is_object() {
+                options.schema = Some(Schema::Json(schema));
+            } else {
+                errors.push(format!("`schema`: invalid type: {}, expected a object", schema));
+            }
+        }
+
+        // idempotencyKey, `true` 时自动生成
+        options.idempotency_key = match self.idempotency_key.take("idempotencyKey", errors) {
+            Some(BoolOrString::Bool(true)) => Some(IdempotencyKey::Auto),
+            Some(BoolOrString::String(key)) => non_empty(Some(key)).map(IdempotencyKey::Key),
+            _ => None,
+        };
+
+        // verifyDigest, `true` 时使用响应头中的摘要
+        options.verify_digest = match self.verify_digest.take("verifyDigest", errors) {
+            Some(BoolOrString::Bool(true)) => Some(VerifyDigest::Header),
+            Some(BoolOrString::String(digest)) => match digest::parse_expected(&digest) {
+                Some(hashes) => Some(VerifyDigest::Expected(hashes)),
+                None => {
+                    errors.push(format!("`verifyDigest`: invalid digest: {:?}, expected sha-256 or sha-512", digest));
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        options
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct JsHttpRequest {
+    cache: Field<Keyword<Cache>>,
+    credentials: Field<Keyword<Credentials>>,
+    integrity: Field<String>,
+    mode: Field<Keyword<Mode>>,
+    redirect: Field<Keyword<Redirect>>,
+    referrer: Field<String>,
+    referrer_policy: Field<Keyword<ReferrerPolicy>>,
+    keepalive: Field<bool>,
+    priority: Field<Keyword<Priority>>,
+}
+
+impl JsHttpRequest {
+    /// 转换成 `HttpRequest`, 错误写入 `errors`
+    pub fn into_request(self, errors: &mut Vec<String>) -> HttpRequest {
+        let non_empty = |value: Option<String>| value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        HttpRequest {
+            cache: self.cache.take_keyword("cache", errors),
+            credentials: self.credentials.take_keyword("credentials", errors),
+            integrity: non_empty(self.integrity.take("integrity", errors)),
+            mode: self.mode.take_keyword("mode", errors),
+            redirect: self.redirect.take_keyword("redirect", errors),
+            referrer: non_empty(self.referrer.take("referrer", errors)),
+            referrer_policy: self.referrer_policy.take_keyword("referrerPolicy", errors),
+            keepalive: self.keepalive.take("keepalive", errors),
+            priority: self.priority.take_keyword("priority", errors),
+            ..Default::default()
+        }
+    }
+}
+
+/// 解析 `opts`, 不校验未知字段
+pub(crate) fn parse_options(obj: &Object, errors: &mut Vec<String>) -> HttpRequestOptions {
+    let mut options = deserialize::<JsHttpOptions>(obj, errors).into_options(errors);
+    options.data = crate::send::HttpClient::get_value(obj, "data");
+    options
+}
+
+/// 解析 `request`, 不校验未知字段
+pub(crate) fn parse_request(obj: &Object, errors: &mut Vec<String>) -> HttpRequest {
+    let mut request = deserialize::<JsHttpRequest>(obj, errors).into_request(errors);
+    if let Some(signal) = crate::send::HttpClient::get_value(obj, "signal") {
+        match signal.dyn_into::<AbortSignal>() {
+            Ok(signal) => request.signal = Some(signal),
+            Err(_) => errors.push("`signal`: invalid type, expected a AbortSignal".to_string()),
+        }
+    }
+
+    request
+}
+
+fn deserialize<T: for<'de> Deserialize<'de> + Default>(obj: &Object, errors: &mut Vec<String>) -> T {
+    serde_wasm_bindgen::from_value(obj.into()).unwrap_or_else(|err| {
+        errors.push(err.to_string());
+        T::default()
+    })
+}
+
+/// 校验未知字段, `fields` 为允许的字段
+pub(crate) fn check_fields(obj: &Object, fields: &[&[&str]], errors: &mut Vec<String>) {
+    for key in Object::keys(obj).iter().filter_map(|key| key.as_string()) {
+        if !fields.iter().any(|fields| fields.contains(&key.as_str())) {
+            errors.push(format!("`{}`: unknown field", key));
+        }
+    }
+}
+
+/// 有错误时返回 `Error::InvalidOptions`
+pub(crate) fn finish<T>(value: T, errors: Vec<String>) -> Result<T, JsValue> {
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(JsValue::from_str(&Error::InvalidOptions(errors).to_string()))
+    }
+}
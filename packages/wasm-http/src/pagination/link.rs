@@ -0,0 +1,105 @@
+//! `Link` 响应头解析, 如 `<https://api.github.com/user/repos?page=3>; rel="next", <...>; rel="last"`
+
+use crate::response::headers::split_header_values;
+use std::collections::BTreeMap;
+
+/// 一个链接
+#[doc = "https://www.rfc-editor.org/rfc/rfc8288#section-3"]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Link {
+    pub uri: String,
+    pub rel: Vec<String>,                 // 关系类型, 小写
+    pub params: BTreeMap<String, String>, // 其他参数, 名称小写
+}
+
+/// 解析 `Link` 响应头, 忽略格式错误的链接
+pub fn parse(value: &str) -> Vec<Link> {
+    split_header_values("link", value).iter().filter_map(|link| parse_link(link)).collect()
+}
+
+/// 查找 `rel` 为 `relation` 的第一个链接
+pub fn find<'a>(links: &'a [Link], relation: &str) -> Option<&'a Link> {
+    let relation = relation.to_lowercase();
+    links.iter().find(|link| link.rel.contains(&relation))
+}
+
+fn parse_link(link: &str) -> Option<Link> {
+    let link = link.trim();
+    let rest = link.strip_prefix('<')?;
+    let end = rest.find('>')?;
+
+    let mut result = Link {
+        uri: rest[..end].trim().to_string(),
+        ..Default::default()
+    };
+
+    for param in split_params(&rest[end + 1..]) {
+        let (name, value) = match param.split_once('=') {
+            Some((name, value)) => (name.trim().to_lowercase(), unquote(value.trim())),
+            None => (param.trim().to_lowercase(), String::new()),
+        };
+
+        if name.is_empty() {
+            continue;
+        }
+
+        // 同名参数只取第一个
+        if name == "rel" {
+            if result.rel.is_empty() {
+                result.rel = value.split_whitespace().map(|rel| rel.to_lowercase()).collect();
+            }
+        } else {
+            result.params.entry(name).or_insert(value);
+        }
+    }
+
+    Some(result)
+}
+
+/// 按 `;` 拆分参数, 忽略引号中的 `;`
+fn split_params(params: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for c in params.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && in_quotes {
+            escaped = true;
+        } else if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == ';' && !in_quotes {
+            result.push(std::mem::take(&mut current));
+            continue;
+        }
+
+        current.push(c);
+    }
+
+    result.push(current);
+    result.into_iter().filter(|param| !param.trim().is_empty()).collect()
+}
+
+/// 去掉引号及转义
+fn unquote(value: &str) -> String {
+    let value = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+        Some(value) => value,
+        None => return value.to_string(),
+    };
+
+    let mut result = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+
+        escaped = false;
+        result.push(c);
+    }
+
+    result
+}
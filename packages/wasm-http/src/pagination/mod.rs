@@ -0,0 +1,367 @@
+/*!
+    分页, 把一个请求模板转换成按页返回响应的异步迭代器(`js` 的 `Symbol.asyncIterator`、`Rust` 的 `Stream`)。
+    下一页的地址有三种获取方式:
+    - `link`: 响应头 `Link` 中 `rel="next"` 的链接(`RFC 8288`)
+    - `cursor`: 响应 `body` 中指定路径的游标, 作为 `query` 参数发送
+    - `page`: `page`/`pageSize` 参数, 当前页的数据少于 `pageSize` 时结束
+*/
+
+pub mod link;
+
+use crate::client::iterator;
+use crate::error::Error;
+use crate::instance::url;
+use crate::request::HttpRequest;
+use crate::send::HttpClient;
+use crate::{HttpRequestOptions, HttpResponseOptions};
+use futures::channel::oneshot;
+use futures::lock::Mutex;
+use futures::stream::{self, LocalBoxStream};
+use futures::StreamExt;
+use js_sys::{Object, Promise, Reflect};
+use serde_json::Value;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{future_to_promise, spawn_local};
+
+/// 下一页的获取方式
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Pagination {
+    /// `Link: <...>; rel="next"`
+    #[default]
+    Link,
+
+    /// 游标, `path` 为游标在 `body` 中的路径, 支持 `a.b.0` 和 `JSON Pointer`(`/a/b/0`), `param` 为发送游标的参数名
+    Cursor { path: String, param: String },
+
+    /// 页码, `items_path` 为数据列表在 `body` 中的路径, 为空时 `body` 本身即为列表
+    Page { param: String, size_param: String, start: u64, size: u64, items_path: Option<String> },
+}
+
+impl Pagination {
+    /// 第一页的地址
+    pub fn first_url(&self, url: &str) -> String {
+        match self {
+            Pagination::Page { param, size_param, start, size, .. } => {
+                let url = if get_query_param(url, param).is_none() { set_query_param(url, param, &start.to_string()) } else { url.to_string() };
+                if get_query_param(&url, size_param).is_none() {
+                    set_query_param(&url, size_param, &size.to_string())
+                } else {
+                    url
+                }
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    /// 根据当前页的地址和响应获取下一页的地址, 没有下一页时返回 `None`
+    pub fn next_url(&self, url: &str, response: &HttpResponseOptions) -> Option<String> {
+        match self {
+            Pagination::Link => {
+                let links: Vec<link::Link> = response.headers.get_all("link").iter().filter_map(|value| value.to_str().ok()).flat_map(link::parse).collect();
+                let next = link::find(&links, "next")?;
+                let base = if response.url.is_empty() { url } else { &response.url };
+                Some(url::resolve(base, &next.uri))
+            }
+            Pagination::Cursor { path, param } => {
+                let cursor = match get_path(&response.body, path)? {
+                    Value::String(cursor) => cursor.clone(),
+                    Value::Number(cursor) => cursor.to_string(),
+                    _ => return None,
+                };
+
+                // 游标为空或没有变化时结束
+                if cursor.is_empty() || get_query_param(url, param).as_deref() == Some(encode(&cursor).as_str()) {
+                    return None;
+                }
+
+                Some(set_query_param(url, param, &cursor))
+            }
+            Pagination::Page { param, size, items_path, .. } => {
+                let items = match items_path {
+                    Some(items_path) => get_path(&response.body, items_path)?,
+                    None => &response.body,
+                };
+
+                let count = items.as_array()?.len() as u64;
+                if count == 0 || count < *size {
+                    return None;
+                }
+
+                let page = get_query_param(url, param).and_then(|page| page.parse::<u64>().ok())?;
+                Some(set_query_param(url, param, &(page + 1).to_string()))
+            }
+        }
+    }
+
+    /// 解析 `js` 配置, `type` 分为: `link`、`cursor`、`page`
+    fn from_js(obj: &Object) -> Pagination {
+        let string = |name: &str| HttpClient::get_value(obj, name).map(HttpClient::get_str).filter(|value| !value.trim().is_empty());
+        let number = |name: &str| HttpClient::get_value(obj, name).and_then(|value| value.as_f64()).map(|value| value.max(0.0) as u64);
+
+        match string("type").unwrap_or_default().trim().to_lowercase().as_str() {
+            "cursor" => Pagination::Cursor {
+                path: string("cursorPath").unwrap_or_else(|| String::from("cursor")),
+                param: string("cursorParam").unwrap_or_else(|| String::from("cursor")),
+            },
+            "page" => Pagination::Page {
+                param: string("pageParam").unwrap_or_else(|| String::from("page")),
+                size_param: string("pageSizeParam").unwrap_or_else(|| String::from("pageSize")),
+                start: number("startPage").unwrap_or(1),
+                size: number("pageSize").unwrap_or(20).max(1),
+                items_path: string("itemsPath"),
+            },
+            _ => Pagination::Link,
+        }
+    }
+}
+
+/// 获取 `body` 中指定路径的值, 支持 `a.b.0` 和 `JSON Pointer`
+pub fn get_path<'a>(body: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.is_empty() {
+        return Some(body);
+    }
+
+    if path.starts_with('/') {
+        return body.pointer(path);
+    }
+
+    path.split('.').try_fold(body, |value, segment| match value {
+        Value::Array(list) => list.get(segment.parse::<usize>().ok()?),
+        Value::Object(map) => map.get(segment),
+        _ => None,
+    })
+}
+
+/// 获取 `query` 参数的原始值
+pub fn get_query_param(url: &str, name: &str) -> Option<String> {
+    let (url, _) = split_fragment(url);
+    let (_, query) = url.split_once('?')?;
+    query.split('&').filter_map(|pair| pair.split_once('=').or(Some((pair, "")))).find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+}
+
+/// 设置 `query` 参数, 已存在时替换
+pub fn set_query_param(url: &str, name: &str, value: &str) -> String {
+    let (url, fragment) = split_fragment(url);
+    let pair = format!("{}={}", name, encode(value));
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => return format!("{}?{}{}", url, pair, fragment),
+    };
+
+    let mut replaced = false;
+    let mut pairs: Vec<String> = Vec::new();
+    for item in query.split('&').filter(|item| !item.is_empty()) {
+        let key = item.split_once('=').map(|(key, _)| key).unwrap_or(item);
+        if key == name {
+            if !replaced {
+                pairs.push(pair.clone());
+                replaced = true;
+            }
+        } else {
+            pairs.push(item.to_string());
+        }
+    }
+
+    if !replaced {
+        pairs.push(pair);
+    }
+
+    format!("{}?{}{}", path, pairs.join("&"), fragment)
+}
+
+fn split_fragment(url: &str) -> (&str, &str) {
+    match url.find('#') {
+        Some(position) => (&url[..position], &url[position..]),
+        None => (url, ""),
+    }
+}
+
+/// 百分号编码, 保留 `RFC 3986` 的非保留字符
+fn encode(value: &str) -> String {
+    let mut result = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => result.push(byte as char),
+            byte => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    result
+}
+
+/// 下一页
+enum Next {
+    /// 尚未发送
+    Pending(HttpRequestOptions),
+
+    /// 已经预取
+    Prefetched(HttpRequestOptions, oneshot::Receiver<Result<HttpResponseOptions, Error>>),
+}
+
+/// 按页返回的响应
+pub type Pages = LocalBoxStream<'static, Result<HttpResponseOptions, Error>>;
+
+struct State {
+    next: Option<Next>,
+    pages: u32,
+}
+
+/// 分页器
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    options: HttpRequestOptions,
+    request: HttpRequest,
+    pagination: Pagination,
+    max_pages: Option<u32>, // 最大页数
+    prefetch: bool,         // 返回当前页时是否同时请求下一页
+}
+
+impl Paginator {
+    pub fn new(options: HttpRequestOptions, pagination: Pagination) -> Self {
+        Self {
+            options,
+            request: HttpRequest::default(),
+            pagination,
+            max_pages: None,
+            prefetch: false,
+        }
+    }
+
+    pub fn max_pages(mut self, max_pages: Option<u32>) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// 按页返回响应, 出错后结束
+    pub fn stream(self) -> Pages {
+        let mut options = self.options.clone();
+        options.url = self.pagination.first_url(&options.url);
+
+        let state = State {
+            next: if self.max_pages == Some(0) { None } else { Some(Next::Pending(options)) },
+            pages: 0,
+        };
+
+        let paginator = Rc::new(self);
+        stream::unfold(state, move |mut state| {
+            let paginator = paginator.clone();
+            async move {
+                let (options, response) = match state.next.take()? {
+                    Next::Pending(options) => {
+                        let response = HttpClient::call(Self::page_options(&options), paginator.request.clone()).await;
+                        (options, response)
+                    }
+                    Next::Prefetched(options, receiver) => {
+                        let response = receiver.await.unwrap_or_else(|_| Err(Error::Error("pagination request was cancelled !".to_string())));
+                        (options, response)
+                    }
+                };
+
+                let response = match response {
+                    Ok(response) => response,
+                    Err(err) => return Some((Err(err), state)),
+                };
+
+                state.pages += 1;
+                let has_more = paginator.max_pages.map(|max_pages| state.pages < max_pages).unwrap_or(true);
+                if let Some(url) = paginator.pagination.next_url(&options.url, &response).filter(|_| has_more) {
+                    let mut options = options.clone();
+                    options.url = url;
+                    state.next = Some(if paginator.prefetch { paginator.spawn(options) } else { Next::Pending(options) });
+                }
+
+                Some((Ok(response), state))
+            }
+        })
+        .boxed_local()
+    }
+
+    /// 每页是一个单独的请求, 使用各自的幂等键, 不写回用于计算下一页的 `options`
+    fn page_options(options: &HttpRequestOptions) -> HttpRequestOptions {
+        let mut options = options.clone();
+        HttpClient::resolve_idempotency_key(&mut options);
+        options
+    }
+
+    /// 预取下一页
+    fn spawn(&self, options: HttpRequestOptions) -> Next {
+        let (sender, receiver) = oneshot::channel();
+        let request = self.request.clone();
+        let spawn_options = Self::page_options(&options);
+        spawn_local(async move {
+            let _ = sender.send(HttpClient::call(spawn_options, request).await);
+        });
+
+        Next::Prefetched(options, receiver)
+    }
+
+    /// 解析 `opts.pagination`, 包括 `type`、`maxPages`、`prefetch` 等
+    pub(crate) fn from_js(options: HttpRequestOptions, request: HttpRequest, opts: &JsValue) -> Paginator {
+        let mut paginator = Paginator::new(options, Pagination::default());
+        paginator.request = request;
+
+        let pagination = opts.dyn_ref::<Object>().and_then(|obj| HttpClient::get_value(obj, "pagination"));
+        if let Some(obj) = pagination.as_ref().and_then(|pagination| pagination.dyn_ref::<Object>()) {
+            paginator.pagination = Pagination::from_js(obj);
+            paginator.max_pages = HttpClient::get_value(obj, "maxPages").and_then(|value| value.as_f64()).map(|value| value.max(0.0) as u32);
+            paginator.prefetch = HttpClient::get_value(obj, "prefetch").map(|value| value.is_truthy()).unwrap_or(false);
+        }
+
+        paginator
+    }
+
+    /// `js` 的异步迭代器, 每次返回一页的响应, 与 `send` 的返回值相同
+    pub(crate) fn into_js(self) -> Result<Object, JsValue> {
+        // 同时调用多次 `next()` 时按顺序等待, 每次返回下一页
+        let stream: Rc<Mutex<Option<Pages>>> = Rc::new(Mutex::new(Some(self.stream())));
+
+        let next_stream = stream.clone();
+        let next = Closure::wrap(Box::new(move || -> Promise {
+            let stream = next_stream.clone();
+            future_to_promise(async move {
+                let mut guard = stream.lock().await;
+                let current = match guard.as_mut() {
+                    Some(current) => current,
+                    None => return Self::iterator_result(JsValue::UNDEFINED, true),
+                };
+
+                match current.next().await {
+                    Some(Ok(response)) => Self::iterator_result(HttpClient::to_js_response(response)?, false),
+                    Some(Err(err)) => {
+                        guard.take();
+                        Err(JsValue::from_str(&err.to_string()))
+                    }
+                    None => {
+                        guard.take();
+                        Self::iterator_result(JsValue::UNDEFINED, true)
+                    }
+                }
+            })
+        }) as Box<dyn FnMut() -> Promise>);
+
+        // `for await` 提前结束时调用, 丢弃预取的请求
+        let finish = Closure::wrap(Box::new(move || -> Promise {
+            let stream = stream.clone();
+            future_to_promise(async move {
+                stream.lock().await.take();
+                Self::iterator_result(JsValue::UNDEFINED, true)
+            })
+        }) as Box<dyn FnMut() -> Promise>);
+
+        // 迭代器的生命周期由 `js` 决定, 回收迭代器时一起释放闭包
+        Ok(iterator::async_iterator(next, finish))
+    }
+
+    fn iterator_result(value: JsValue, done: bool) -> Result<JsValue, JsValue> {
+        let result = Object::new();
+        Reflect::set(&result, &JsValue::from_str("done"), &JsValue::from_bool(done))?;
+        Reflect::set(&result, &JsValue::from_str("value"), &value)?;
+        Ok(result.into())
+    }
+}
@@ -1,9 +1,11 @@
 //! cache 作为Request 接口只读属性包含着请求的缓存模式。它控制着请求以何种方式与浏览器的 HTTP 缓存进行交互。
 
+use serde::Deserialize;
 use web_sys::RequestCache;
 
 #[doc = "https://developer.mozilla.org/zh-CN/docs/Web/API/Request/cache"]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Cache {
     /**
       浏览器从 HTTP 缓存中寻找匹配的请求
@@ -54,21 +56,3 @@ impl From<Cache> for RequestCache {
         }
     }
 }
-
-impl Cache {
-    pub fn get_cache(cache: String) -> Cache {
-        let cache = cache.trim();
-        if cache.is_empty() {
-            return Cache::Default;
-        }
-
-        return match cache.to_lowercase().as_str() {
-            "no-store" => Cache::NoStore,
-            "reload" => Cache::Reload,
-            "no-cache" => Cache::NoCache,
-            "force-cache" => Cache::ForceCache,
-            "only-if-cached" => Cache::OnlyIfCached,
-            _ => Cache::Default,
-        };
-    }
-}
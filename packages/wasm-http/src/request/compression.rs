@@ -0,0 +1,154 @@
+//! 请求 `body` 压缩, 优先使用 `CompressionStream`, 不支持(如 `br`)时使用 `Rust` 实现压缩。
+//! `Rust` 实现只在开启 `rust-compression` feature 时编译, 未开启时浏览器不支持的格式不压缩, 按原样发送。
+
+use crate::error::Error;
+#[cfg(feature = "rust-compression")]
+use flate2::write::{GzEncoder, ZlibEncoder};
+use js_sys::{Array, Reflect, Uint8Array};
+use serde::Deserialize;
+#[cfg(feature = "rust-compression")]
+use std::io::Write;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, ReadableWritablePair, Response};
+
+/// 默认的压缩阈值, 小于该大小(字节)的 `body` 不压缩
+pub const THRESHOLD: u32 = 1024;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = CompressionStream)]
+    type JsCompressionStream;
+
+    #[wasm_bindgen(constructor, catch, js_class = "CompressionStream")]
+    fn new(format: &str) -> Result<JsCompressionStream, JsValue>;
+}
+
+/// 压缩格式, 同时也是 `Content-Encoding` 的值
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/CompressionStream/CompressionStream)"]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Deflate, // `zlib` 格式
+    Br,
+}
+
+impl Compression {
+    pub fn get_compression(compression: String) -> Option<Compression> {
+        match compression.trim().to_lowercase().as_str() {
+            "gzip" => Some(Compression::Gzip),
+            "deflate" => Some(Compression::Deflate),
+            "br" => Some(Compression::Br),
+            _ => None,
+        }
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Deflate => "deflate",
+            Compression::Br => "br",
+        }
+    }
+}
+
+/// `Rust` 实现的压缩
+#[cfg(feature = "rust-compression")]
+pub fn encode(bytes: &[u8], compression: Compression) -> Result<Vec<u8>, Error> {
+    let map_err = |err: std::io::Error| Error::Error(format!("compress request body error: {}", err));
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes).map_err(map_err)?;
+            encoder.finish().map_err(map_err)
+        }
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes).map_err(map_err)?;
+            encoder.finish().map_err(map_err)
+        }
+        Compression::Br => {
+            let mut result = Vec::new();
+            {
+                let mut encoder = brotli::CompressorWriter::new(&mut result, 4096, 5, 22);
+                encoder.write_all(bytes).map_err(map_err)?;
+            }
+
+            Ok(result)
+        }
+    }
+}
+
+/// 压缩 `body`, 只处理字符串和 `Blob`, 不需要压缩时返回 `None`
+pub(crate) async fn compress(body: &JsValue, compression: Compression, threshold: u32) -> Result<Option<JsValue>, Error> {
+    let (blob, bytes) = if let Some(text) = body.as_string() {
+        let blob = Blob::new_with_str_sequence(&Array::of1(body)).map_err(Error::js_error)?;
+        (blob, Some(text.into_bytes()))
+    } else if let Some(blob) = body.dyn_ref::<Blob>() {
+        (blob.clone(), None)
+    } else {
+        return Ok(None);
+    };
+
+    if blob.size() < threshold as f64 {
+        return Ok(None);
+    }
+
+    if let Some(stream) = compression_stream(compression) {
+        let stream = blob.stream().pipe_through(stream.unchecked_ref::<ReadableWritablePair>());
+        let response = Response::new_with_opt_readable_stream(Some(&stream)).map_err(Error::js_error)?;
+        let array_buffer = JsFuture::from(response.array_buffer().map_err(Error::js_error)?).await.map_err(Error::js_error)?;
+        return Ok(Some(Uint8Array::new(&array_buffer).into()));
+    }
+
+    #[cfg(feature = "rust-compression")]
+    {
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => Uint8Array::new(&JsFuture::from(blob.array_buffer()).await.map_err(Error::js_error)?).to_vec(),
+        };
+
+        let bytes = encode(&bytes, compression)?;
+        Ok(Some(Uint8Array::from(bytes.as_slice()).into()))
+    }
+
+    #[cfg(not(feature = "rust-compression"))]
+    {
+        let _ = bytes;
+        Ok(None)
+    }
+}
+
+/// 同步压缩 `body`, 用于 `beacon`, 只处理字符串, 不需要压缩时返回 `None`
+/// `Blob` 无法同步读取, 返回错误
+#[cfg(feature = "rust-compression")]
+pub(crate) fn compress_sync(body: &JsValue, compression: Compression, threshold: u32) -> Result<Option<JsValue>, Error> {
+    let text = match body.as_string() {
+        Some(text) => text,
+        None if body.is_instance_of::<Blob>() => return Err(Error::Error("`beacon` can not compress `Blob` body !".to_string())),
+        None => return Ok(None),
+    };
+
+    if text.len() < threshold as usize {
+        return Ok(None);
+    }
+
+    let bytes = encode(text.as_bytes(), compression)?;
+    Ok(Some(Uint8Array::from(bytes.as_slice()).into()))
+}
+
+/// 未开启 `rust-compression` feature 时无法同步压缩, 按原样发送
+#[cfg(not(feature = "rust-compression"))]
+pub(crate) fn compress_sync(_body: &JsValue, _compression: Compression, _threshold: u32) -> Result<Option<JsValue>, Error> {
+    Ok(None)
+}
+
+/// 不支持 `CompressionStream` 或该格式时返回 `None`
+fn compression_stream(compression: Compression) -> Option<JsCompressionStream> {
+    if !Reflect::has(&js_sys::global(), &JsValue::from_str("CompressionStream")).unwrap_or(false) {
+        return None;
+    }
+
+    JsCompressionStream::new(compression.encoding()).ok()
+}
@@ -6,10 +6,12 @@
      - include: 不论是不是跨域的请求，总是发送请求资源域在本地的 `cookies`、`HTTP Basic authentication` 等验证信息。
 */
 
+use serde::Deserialize;
 use web_sys::RequestCredentials;
 
 #[doc = "https://developer.mozilla.org/en-US/docs/Web/API/Request/credentials"]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Credentials {
     /// 从不发送 cookies
     Omit,
@@ -31,18 +33,3 @@ impl From<Credentials> for RequestCredentials {
         }
     }
 }
-
-impl Credentials {
-    pub fn get_credentials(credentials: String) -> Credentials {
-        let credentials = credentials.trim();
-        if credentials.is_empty() {
-            return Credentials::SameOrigin;
-        }
-
-        return match credentials.to_lowercase().as_str() {
-            "omit" => Credentials::Omit,
-            "include" => Credentials::Include,
-            _ => Credentials::SameOrigin,
-        };
-    }
-}
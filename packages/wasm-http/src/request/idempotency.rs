@@ -0,0 +1,65 @@
+/*!
+    幂等键, 为 `POST`、`PATCH` 请求添加 `Idempotency-Key` 请求头, 服务端据此识别重复提交的请求。
+    - `idempotencyKey` 为 `true` 时每个逻辑请求生成一个 `UUID v4`, 为字符串时使用调用方提供的键
+    - 键在每个逻辑请求开始时写入 `headers`, 之后的重试、`HAR` 记录及回放使用同一个键
+    - 包含多个请求的操作(如 `tus` 上传)为每个请求派生不同的键, 见 `IdempotencyKey::derive`
+    - `headers` 中已有 `Idempotency-Key` 时不覆盖
+*/
+
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+pub const HEADER: &str = "Idempotency-Key";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdempotencyKey {
+    Auto,        // 自动生成
+    Key(String), // 调用方提供
+}
+
+impl IdempotencyKey {
+    /// 生成 `UUID v4`
+    pub fn generate() -> String {
+        Uuid::new_v4().to_string()
+    }
+
+    /// 使用的键, `Auto` 时每次调用都生成新的键
+    pub fn key(&self) -> String {
+        match self {
+            IdempotencyKey::Auto => Self::generate(),
+            IdempotencyKey::Key(key) => key.clone(),
+        }
+    }
+
+    /// 操作中的某个请求使用的键, 调用方提供的键加上 `suffix`, 避免多个请求共用一个键
+    pub fn derive(&self, suffix: &str) -> String {
+        match self {
+            IdempotencyKey::Auto => Self::generate(),
+            IdempotencyKey::Key(key) => format!("{}-{}", key, suffix),
+        }
+    }
+}
+
+/// 是否为需要幂等键的非幂等方法
+pub fn is_unsafe_method(method: &str) -> bool {
+    method.eq_ignore_ascii_case("post") || method.eq_ignore_ascii_case("patch")
+}
+
+/// 把幂等键写入 `headers`, 返回实际使用的键(`headers` 中已有时为原来的值)
+pub fn with_header(headers: Option<Value>, key: &str) -> (Value, String) {
+    let mut headers = match headers {
+        Some(Value::Object(headers)) => headers,
+        _ => Map::new(),
+    };
+
+    let existing = headers.iter().find(|(name, _)| name.eq_ignore_ascii_case(HEADER)).map(|(_, value)| value.as_str().unwrap_or_default().to_string());
+    let key = match existing {
+        Some(existing) => existing,
+        None => {
+            headers.insert(HEADER.to_string(), Value::String(key.to_string()));
+            key.to_string()
+        }
+    };
+
+    (Value::Object(headers), key)
+}
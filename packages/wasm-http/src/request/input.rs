@@ -0,0 +1,46 @@
+//! 原生的 `Request` 或 `URL` 作为请求的输入, `Request` 的 `body` 不读取到内存, 发送时沿用其 `ReadableStream`。
+//! `Rust` 中创建的 `options` 通过 `send_request` 发送。
+
+use crate::error::Error;
+use crate::HttpRequestOptions;
+use serde_json::{Map, Value};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use web_sys::{Request, Url};
+
+impl HttpRequestOptions {
+    /// 使用 `Request` 的 `url`、`method` 及 `headers`, `mode`、`credentials` 等属性在发送时沿用
+    pub fn from_request(request: &Request) -> Result<Self, Error> {
+        let mut headers = Map::new();
+        if let Some(iterator) = js_sys::try_iter(request.headers().as_ref()).map_err(Error::js_error)? {
+            for header in iterator {
+                let pair: js_sys::Array = header.map_err(Error::js_error)?.into();
+                if let (Some(name), Some(value)) = (pair.get(0).as_string(), pair.get(1).as_string()) {
+                    headers.insert(name, Value::String(value));
+                }
+            }
+        }
+
+        Ok(Self {
+            url: request.url(),
+            method: Some(request.method()),
+            headers: Some(Value::Object(headers)),
+            source: Some(Clone::clone(request)), // 不使用 `Request.clone()`, 避免复制 `body`
+            ..Default::default()
+        })
+    }
+
+    /// 使用 `URL` 的地址, 其余字段为默认值
+    pub fn from_url(url: &Url) -> Self {
+        Self { url: url.href(), ..Default::default() }
+    }
+}
+
+/// `opts` 为 `Request` 或 `URL` 时返回对应的 `options`, 否则返回 `None`
+pub(crate) fn from_input(opts: &JsValue) -> Result<Option<HttpRequestOptions>, Error> {
+    if let Some(request) = opts.dyn_ref::<Request>() {
+        return HttpRequestOptions::from_request(request).map(Some);
+    }
+
+    Ok(opts.dyn_ref::<Url>().map(HttpRequestOptions::from_url))
+}
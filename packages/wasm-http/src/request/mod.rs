@@ -1,14 +1,21 @@
-pub(crate) mod cache;
-pub(crate) mod credentials;
-pub(crate) mod mode;
-pub(crate) mod redirect;
-pub(crate) mod referrer_policy;
+pub mod cache;
+pub mod compression;
+pub mod credentials;
+pub mod idempotency;
+pub(crate) mod input;
+pub mod mode;
+pub mod priority;
+pub mod redirect;
+pub mod referrer_policy;
+pub mod stream;
 
 use crate::request::cache::Cache;
 use crate::request::credentials::Credentials;
 use crate::request::mode::Mode;
+use crate::request::priority::Priority;
 use crate::request::redirect::Redirect;
 use crate::request::referrer_policy::ReferrerPolicy;
+use wasm_bindgen::JsValue;
 use web_sys::{AbortSignal, RequestInit};
 
 #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Request/Request)"]
@@ -21,6 +28,8 @@ pub struct HttpRequest {
     pub redirect: Option<Redirect>,
     pub referrer: Option<String>,
     pub referrer_policy: Option<ReferrerPolicy>,
+    pub keepalive: Option<bool>,    // 页面卸载后继续发送, `body` 不能超过 `64KB`
+    pub priority: Option<Priority>, // 请求优先级
     pub(crate) signal: Option<AbortSignal>,
 }
 
@@ -71,6 +80,18 @@ impl HttpRequest {
         self
     }
 
+    #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Request/keepalive)"]
+    pub fn keepalive(mut self, keepalive: bool) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    #[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Request/priority)"]
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
     #[doc = "[MDN Documentation](https://developer.mozilla.org/zh-CN/docs/Web/API/AbortController)"]
     pub fn signal(mut self, signal: Option<&AbortSignal>) -> Self {
         if let Some(signal) = signal {
@@ -81,6 +102,23 @@ impl HttpRequest {
 
         self
     }
+
+    /// 合并 `request`, 当前配置优先, 未设置的字段使用 `defaults` 中的值
+    pub(crate) fn merge(self, defaults: &HttpRequest) -> Self {
+        let defaults = defaults.clone();
+        Self {
+            cache: self.cache.or(defaults.cache),
+            credentials: self.credentials.or(defaults.credentials),
+            integrity: self.integrity.or(defaults.integrity),
+            mode: self.mode.or(defaults.mode),
+            redirect: self.redirect.or(defaults.redirect),
+            referrer: self.referrer.or(defaults.referrer),
+            referrer_policy: self.referrer_policy.or(defaults.referrer_policy),
+            keepalive: self.keepalive.or(defaults.keepalive),
+            priority: self.priority.or(defaults.priority),
+            signal: self.signal.or(defaults.signal),
+        }
+    }
 }
 
 impl From<HttpRequest> for RequestInit {
@@ -115,6 +153,15 @@ impl From<HttpRequest> for RequestInit {
             init.set_referrer_policy(referrer_policy.into());
         }
 
+        // 当前 `web_sys` 版本的 `RequestInit` 没有 `keepalive` 及 `priority`, 直接设置属性
+        if let Some(keepalive) = value.keepalive {
+            let _ = js_sys::Reflect::set(&init, &JsValue::from_str("keepalive"), &JsValue::from_bool(keepalive));
+        }
+
+        if let Some(priority) = value.priority {
+            let _ = js_sys::Reflect::set(&init, &JsValue::from_str("priority"), &JsValue::from_str(priority.as_str()));
+        }
+
         if let Some(signal) = value.signal {
             init.set_signal(Some(&signal));
         }
@@ -3,10 +3,12 @@
     这用于确定跨域请求是否能得到有效的响应，以及响应的哪些属性是可读的。
 */
 
+use serde::Deserialize;
 use web_sys::RequestMode;
 
 #[doc = "https://developer.mozilla.org/en-US/docs/Web/API/Request/mode"]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Mode {
     /// 如果使用此模式向另外一个源发送请求，显而易见，结果会是一个错误。你可以设置该模式以确保请求总是向当前的源发起的
     SameOrigin,
@@ -43,19 +45,3 @@ impl From<Mode> for RequestMode {
         }
     }
 }
-
-impl Mode {
-    pub fn get_mode(mode: String) -> Mode {
-        let mode = mode.trim();
-        if mode.is_empty() {
-            return Mode::NoCors;
-        }
-
-        return match mode.to_lowercase().as_str() {
-            "same-origin" => Mode::SameOrigin,
-            "cors" => Mode::Cors,
-            "navigate" => Mode::Navigate,
-            _ => Mode::NoCors,
-        };
-    }
-}
@@ -0,0 +1,28 @@
+/*!
+    `Request` 的 `priority` 属性, 表示请求相对于同类型其他请求的优先级, 浏览器据此调整请求的发送顺序。
+    - high: 高于同类型的其他请求
+    - low: 低于同类型的其他请求, 如页面卸载时的统计上报
+    - auto: 由浏览器决定(默认)
+*/
+
+use serde::Deserialize;
+
+#[doc = "https://developer.mozilla.org/en-US/docs/Web/API/Request/priority"]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Low,
+    #[default]
+    Auto,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Low => "low",
+            Priority::Auto => "auto",
+        }
+    }
+}
@@ -1,8 +1,10 @@
 /// `Request` 接口的 `redirect` 只读属性包含了处理重定向的模式。
+use serde::Deserialize;
 use web_sys::RequestRedirect;
 
 #[doc = "https://developer.mozilla.org/en-US/docs/Web/API/Request/redirect"]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Redirect {
     /// 跟随所有重定向，获取资源
     #[default]
@@ -27,18 +29,3 @@ impl From<Redirect> for RequestRedirect {
         }
     }
 }
-
-impl Redirect {
-    pub fn get_redirect(redirect: String) -> Redirect {
-        let redirect = redirect.trim();
-        if redirect.is_empty() {
-            return Redirect::Follow;
-        }
-
-        return match redirect.to_lowercase().as_str() {
-            "error" => Redirect::Error,
-            "manual" => Redirect::Manual,
-            _ => Redirect::Follow,
-        };
-    }
-}
@@ -2,12 +2,15 @@
     `Request` 接口的一个只读属性 `referrerPolicy`，该属性返回引用策略（referrer policy）。
     引用策略规定了在请求中应该包含哪些引用信息（以 `Referer` 标头发送的信息）。
 */
+use serde::Deserialize;
 use web_sys::ReferrerPolicy as RequestReferrerPolicy;
 
 #[doc = "https://developer.mozilla.org/en-US/docs/Web/API/Request/referrerPolicy"]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum ReferrerPolicy {
     /// 对应于没有引用策略，会回退到其他地方定义的引用策略，或者在没有这样的更高级策略可用的情况下，回退到默认的引用策略。
+    #[serde(rename = "", alias = "none")]
     None,
 
     /// 指定不发送任何引用信息随请求发送到任何来源。
@@ -57,20 +60,3 @@ impl From<ReferrerPolicy> for RequestReferrerPolicy {
         }
     }
 }
-
-impl ReferrerPolicy {
-    pub fn get_referrer_policy(referrer_policy: String) -> ReferrerPolicy {
-        let referrer_policy = referrer_policy.trim();
-        return match referrer_policy.to_lowercase().as_str() {
-            "" => ReferrerPolicy::None,
-            "no-referrer" => ReferrerPolicy::NoReferrer,
-            "no-referrer-when-downgrade" => ReferrerPolicy::NoReferrerWhenDowngrade,
-            "origin" => ReferrerPolicy::Origin,
-            "origin-when-cross-origin" => ReferrerPolicy::OriginWhenCrossOrigin,
-            "unsafe-url" => ReferrerPolicy::UnsafeUrl,
-            "same-origin" => ReferrerPolicy::SameOrigin,
-            "strict-origin" => ReferrerPolicy::StrictOrigin,
-            _ => ReferrerPolicy::StrictOriginWhenCrossOrigin,
-        };
-    }
-}
@@ -0,0 +1,134 @@
+/*!
+    流式请求 `body`, 支持 `JS` 的 `ReadableStream` 及 `Rust` 的 `Stream<Item = Bytes>`, 用于上传生成的大文件(如导出的 `CSV`), 不需要先把 `body` 读取到内存。
+    - 浏览器支持请求流时使用 `duplex: "half"` 发送
+    - 不支持时先读取为 `Blob` 再发送
+    - 流只能读取一次, 不能用于重试、`tus` 等需要多次发送 `body` 的请求
+*/
+
+use crate::error::Error;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use js_sys::{Array, Object, Promise, Reflect, Uint8Array};
+use std::cell::{Cell, RefCell};
+use std::pin::Pin;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, ReadableStream, ReadableStreamDefaultController, ReadableStreamDefaultReader, RequestInit};
+
+thread_local! {
+    static SUPPORTED: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+type BoxStream = Pin<Box<dyn Stream<Item = Bytes>>>;
+
+/// `body` 是否为 `ReadableStream`
+pub fn is_stream(data: &JsValue) -> bool {
+    data.is_instance_of::<ReadableStream>()
+}
+
+/// `Rust` 的 `Stream` 转换为 `ReadableStream`, 作为 `HttpRequestOptions.data` 发送, 浏览器读取时才拉取下一块
+pub fn from_stream<S>(stream: S) -> Result<ReadableStream, Error>
+where
+    S: Stream<Item = Bytes> + 'static,
+{
+    let stream: Rc<RefCell<Option<BoxStream>>> = Rc::new(RefCell::new(Some(Box::pin(stream))));
+
+    let pull = {
+        let stream = stream.clone();
+        Closure::<dyn FnMut(ReadableStreamDefaultController) -> Promise>::new(move |controller: ReadableStreamDefaultController| {
+            let stream = stream.clone();
+            wasm_bindgen_futures::future_to_promise(async move {
+                // 读取时取出, 避免跨 `await` 持有 `RefCell` 的借用, 结束或取消后不再放回
+                let mut inner = match stream.borrow_mut().take() {
+                    Some(inner) => inner,
+                    None => return Ok(JsValue::UNDEFINED),
+                };
+
+                // 跳过空的块, 部分浏览器上传时不接受空的 `Uint8Array`
+                while let Some(chunk) = inner.next().await {
+                    if !chunk.is_empty() {
+                        *stream.borrow_mut() = Some(inner);
+                        controller.enqueue_with_chunk(&Uint8Array::from(chunk.as_ref()))?;
+                        return Ok(JsValue::UNDEFINED);
+                    }
+                }
+
+                controller.close()?;
+                Ok(JsValue::UNDEFINED)
+            })
+        })
+    };
+
+    let cancel = Closure::<dyn FnMut(JsValue)>::new(move |_reason: JsValue| {
+        stream.borrow_mut().take();
+    });
+
+    let source = Object::new();
+    Reflect::set(&source, &JsValue::from_str("pull"), &pull.into_js_value()).map_err(Error::js_error)?;
+    Reflect::set(&source, &JsValue::from_str("cancel"), &cancel.into_js_value()).map_err(Error::js_error)?;
+    ReadableStream::new_with_underlying_source(&source).map_err(Error::js_error)
+}
+
+/// 浏览器是否支持 `ReadableStream` 作为请求 `body`, 结果会被缓存
+pub fn supports_request_streams() -> bool {
+    SUPPORTED.with(|supported| {
+        if let Some(result) = supported.get() {
+            return result;
+        }
+
+        let result = detect();
+        supported.set(Some(result));
+        result
+    })
+}
+
+/// 支持时 `Request` 会读取 `duplex`, 且不会把流转换为字符串(设置 `text/plain` 的 `Content-Type`)
+fn detect() -> bool {
+    let body = match ReadableStream::new() {
+        Ok(body) => body,
+        Err(_) => return false,
+    };
+
+    let accessed = Rc::new(Cell::new(false));
+    let getter = {
+        let accessed = accessed.clone();
+        Closure::<dyn FnMut() -> JsValue>::new(move || {
+            accessed.set(true);
+            JsValue::from_str("half")
+        })
+    };
+
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_body(&body);
+
+    let descriptor = Object::new();
+    if Reflect::set(&descriptor, &JsValue::from_str("get"), getter.as_ref()).is_err() {
+        return false;
+    }
+    Object::define_property(&init, &JsValue::from_str("duplex"), &descriptor);
+
+    match web_sys::Request::new_with_str_and_init("data:,", &init) {
+        Ok(request) => accessed.get() && !request.headers().has("Content-Type").unwrap_or(true),
+        Err(_) => false,
+    }
+}
+
+/// 读取流的全部内容为 `Blob`, 用于不支持请求流的浏览器
+pub(crate) async fn buffer(stream: &ReadableStream) -> Result<Blob, Error> {
+    let reader: ReadableStreamDefaultReader = stream.get_reader().unchecked_into();
+    let chunks = Array::new();
+
+    loop {
+        let chunk = JsFuture::from(reader.read()).await.map_err(Error::js_error)?;
+        let done = Reflect::get(&chunk, &JsValue::from_str("done")).map(|done| done.is_truthy()).unwrap_or(true);
+        if done {
+            break;
+        }
+
+        chunks.push(&Reflect::get(&chunk, &JsValue::from_str("value")).map_err(Error::js_error)?);
+    }
+
+    Blob::new_with_u8_array_sequence(&chunks).map_err(Error::js_error)
+}
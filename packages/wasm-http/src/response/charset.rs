@@ -0,0 +1,68 @@
+/*!
+    根据 `Content-Type` 中的 `charset` 解码文本响应。
+    编码的优先级为: `BOM` > `responseEncoding` > `charset` > `UTF-8`。
+    浏览器中使用 `TextDecoder` 解码, `TextDecoder` 不可用或不支持该编码时使用 `encoding_rs`。
+*/
+
+use crate::error::Error;
+use encoding_rs::{Encoding, UTF_8};
+use js_sys::Uint8Array;
+use web_sys::TextDecoder;
+
+/// 获取 `Content-Type` 中的 `charset`
+#[doc = "https://www.rfc-editor.org/rfc/rfc9110#section-8.3.1"]
+pub fn get_charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+
+        let value = value.trim().trim_matches('"').trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        Some(value.to_string())
+    })
+}
+
+/// 获取编码, 不支持 `responseEncoding` 时报错, 不支持 `charset` 时使用 `UTF-8`
+#[doc = "https://encoding.spec.whatwg.org/#names-and-labels"]
+pub fn get_encoding(bytes: &[u8], content_type: Option<&str>, response_encoding: Option<&str>) -> Result<&'static Encoding, Error> {
+    // BOM
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return Ok(encoding);
+    }
+
+    // responseEncoding
+    if let Some(response_encoding) = response_encoding.map(str::trim).filter(|label| !label.is_empty()) {
+        return Encoding::for_label(response_encoding.as_bytes()).ok_or_else(|| Error::UnsupportedEncoding(response_encoding.to_string()));
+    }
+
+    // charset
+    let encoding = content_type.and_then(get_charset).and_then(|charset| Encoding::for_label(charset.as_bytes()));
+    Ok(encoding.unwrap_or(UTF_8))
+}
+
+/// 使用 `encoding_rs` 解码, 与编码匹配的 `BOM` 会被去掉, 非法字节替换为 `U+FFFD`
+pub fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+    let (text, _) = encoding.decode_with_bom_removal(bytes);
+    text.into_owned()
+}
+
+/// 解码文本
+pub fn decode_text(bytes: &[u8], content_type: Option<&str>, response_encoding: Option<&str>) -> Result<String, Error> {
+    let encoding = get_encoding(bytes, content_type, response_encoding)?;
+    Ok(decode(bytes, encoding))
+}
+
+/// 使用 `TextDecoder` 解码, 不支持时使用 `encoding_rs`
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/TextDecoder)"]
+pub(crate) fn decode_js(bytes: &Uint8Array, encoding: &'static Encoding) -> Result<String, Error> {
+    if let Ok(decoder) = TextDecoder::new_with_label(encoding.name()) {
+        return decoder.decode_with_js_u8_array(bytes).map_err(Error::js_error);
+    }
+
+    Ok(decode(&bytes.to_vec(), encoding))
+}
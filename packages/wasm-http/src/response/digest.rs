@@ -0,0 +1,180 @@
+/*!
+    响应 `body` 的摘要校验, 用于签名的固件、配置等下载, 支持 `sha-256`、`sha-512`。
+    - `verifyDigest: true` 时按顺序使用 `Content-Digest`(RFC 9530)、`Repr-Digest`、`Digest`(RFC 3230) 响应头中的摘要, 没有时请求失败
+    - `verifyDigest` 为字符串时使用调用方传入的摘要, 支持 `sha-256=:<base64>:`、`sha-256=<base64>`、`sha256-<base64>`(SRI) 及十六进制
+    - 有多个摘要时都需要一致, 不一致时返回 `Error::IntegrityMismatch`, 不返回数据
+    - 响应头中的摘要按 `Content-Encoding` 编码后的内容计算(RFC 9530), 而浏览器只提供解码后的 `body`, 响应有 `gzip`、`br` 等编码时无法校验, 直接返回 `Error::IntegrityMismatch`
+    - 调用方传入的摘要按解码后的 `body` 计算, 不受 `Content-Encoding` 影响
+    - 跨域时摘要响应头及 `Content-Encoding` 需要在 `Access-Control-Expose-Headers` 中声明
+*/
+
+use crate::error::Error;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use js_sys::Uint8Array;
+use sha2::{Digest, Sha256, Sha512};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, Response, ResponseInit};
+
+/// 响应头, 按优先级排序
+pub const HEADERS: [&str; 3] = ["content-digest", "repr-digest", "digest"];
+
+/// 摘要算法
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    /// 忽略大小写, 支持 `sha-256` 及 `sha256`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "sha-256" | "sha256" => Some(Algorithm::Sha256),
+            "sha-512" | "sha512" => Some(Algorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha-256",
+            Algorithm::Sha512 => "sha-512",
+        }
+    }
+
+    pub fn hash(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Sha256 => Sha256::digest(bytes).to_vec(),
+            Algorithm::Sha512 => Sha512::digest(bytes).to_vec(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha512 => 64,
+        }
+    }
+}
+
+/// 算法及摘要
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hash {
+    pub algorithm: Algorithm,
+    pub value: Vec<u8>,
+}
+
+/// 校验方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyDigest {
+    Header,              // 使用响应头中的摘要
+    Expected(Vec<Hash>), // 使用调用方传入的摘要
+}
+
+/// 解析单个摘要, 不支持的算法或格式返回 `None`
+fn parse_item(item: &str) -> Option<Hash> {
+    let item = item.trim();
+    let decode = |algorithm: Algorithm, value: &str| {
+        // 去掉 `Content-Digest` 中的参数及字节序列两边的 `:`
+        let value = value.split(';').next().unwrap_or_default().trim().trim_matches(':');
+        let value = STANDARD.decode(value).ok().filter(|value| value.len() == algorithm.size())?;
+        Some(Hash { algorithm, value })
+    };
+
+    // `sha-256=:<base64>:`、`sha-256=<base64>`
+    if let Some((name, value)) = item.split_once('=') {
+        if let Some(algorithm) = Algorithm::from_name(name) {
+            return decode(algorithm, value);
+        }
+    }
+
+    // `sha256-<base64>`
+    if let Some((name, value)) = item.split_once('-') {
+        if let Some(algorithm) = Algorithm::from_name(name) {
+            return decode(algorithm, value);
+        }
+    }
+
+    // 十六进制, 按长度区分算法
+    let algorithm = match item.len() {
+        64 => Algorithm::Sha256,
+        128 => Algorithm::Sha512,
+        _ => return None,
+    };
+    hex::decode(item).ok().map(|value| Hash { algorithm, value })
+}
+
+/// 解析响应头, 忽略不支持的算法
+pub fn parse_header(value: &str) -> Vec<Hash> {
+    value.split(',').filter_map(parse_item).collect()
+}
+
+/// 解析调用方传入的摘要, 多个时用 `,` 分隔, 有不支持的算法或格式时返回 `None`
+pub fn parse_expected(value: &str) -> Option<Vec<Hash>> {
+    let hashes = value.split(',').map(parse_item).collect::<Option<Vec<Hash>>>()?;
+    Some(hashes).filter(|hashes| !hashes.is_empty())
+}
+
+/// 按顺序查找响应头中的摘要
+pub fn from_headers(names: &[&str], header: impl Fn(&str) -> Option<String>) -> Vec<Hash> {
+    names.iter().filter_map(|name| header(name)).map(|value| parse_header(&value)).find(|hashes| !hashes.is_empty()).unwrap_or_default()
+}
+
+/// 响应头中的摘要按编码后的内容计算, 有 `identity` 以外的 `Content-Encoding` 时无法校验
+pub fn check_encoding(content_encoding: Option<&str>) -> Result<(), Error> {
+    match content_encoding.map(str::trim).filter(|encoding| !encoding.is_empty() && !encoding.eq_ignore_ascii_case("identity")) {
+        Some(encoding) => Err(Error::IntegrityMismatch(format!("cannot verify encoded content against digest headers, `Content-Encoding` is `{}`", encoding))),
+        None => Ok(()),
+    }
+}
+
+/// 校验 `bytes`, 所有摘要都需要一致
+pub fn verify(bytes: &[u8], hashes: &[Hash]) -> Result<(), Error> {
+    if hashes.is_empty() {
+        return Err(Error::IntegrityMismatch("no sha-256 or sha-512 digest to verify".to_string()));
+    }
+
+    for hash in hashes {
+        let actual = hash.algorithm.hash(bytes);
+        if actual != hash.value {
+            return Err(Error::IntegrityMismatch(format!("{} expected `{}`, got `{}`", hash.algorithm.name(), STANDARD.encode(&hash.value), STANDARD.encode(actual))));
+        }
+    }
+
+    Ok(())
+}
+
+/// 读取并校验响应 `body`, 返回使用相同 `body` 重新创建的 `Response`, 后续按 `responseType` 正常解析
+pub async fn verify_response(response: Response, verify_digest: &VerifyDigest) -> Result<Response, Error> {
+    let hashes = match verify_digest {
+        VerifyDigest::Expected(hashes) => hashes.clone(),
+        VerifyDigest::Header => {
+            check_encoding(response.headers().get("content-encoding").ok().flatten().as_deref())?;
+            let hashes = from_headers(&HEADERS, |name| response.headers().get(name).ok().flatten());
+            if hashes.is_empty() {
+                return Err(Error::IntegrityMismatch("missing `Content-Digest`, `Repr-Digest` or `Digest` response header".to_string()));
+            }
+            hashes
+        }
+    };
+
+    let array_buffer = JsFuture::from(response.array_buffer().map_err(Error::js_error)?).await.map_err(Error::js_error)?;
+    verify(&Uint8Array::new(&array_buffer).to_vec(), &hashes)?;
+
+    let init = ResponseInit::new();
+    init.set_status(response.status());
+    init.set_status_text(&response.status_text());
+    init.set_headers(&response.headers());
+
+    // `204`、`304` 等状态码不能有 `body`
+    let body = Some(&array_buffer).filter(|_| !matches!(response.status(), 101 | 103 | 204 | 205 | 304));
+    Response::new_with_opt_buffer_source_and_init(body.map(|body| body.unchecked_ref()), &init).map_err(Error::js_error)
+}
+
+/// 校验 `Blob`, 用于分段下载合并后的文件
+pub(crate) async fn verify_blob(blob: &Blob, hashes: &[Hash]) -> Result<(), Error> {
+    let array_buffer = JsFuture::from(blob.array_buffer()).await.map_err(Error::js_error)?;
+    verify(&Uint8Array::new(&array_buffer).to_vec(), hashes)
+}
@@ -0,0 +1,258 @@
+/*!
+    响应头, 支持同名多值及忽略大小写读取。
+    浏览器的 `Headers` 会把同名的 `header` 以 `, ` 合并, 这里按照 `RFC 9110` 的列表语法重新拆分成多个值。
+    跨域响应只会暴露 `CORS` 安全的 `header` 以及 `Access-Control-Expose-Headers` 中声明的 `header`。
+*/
+
+use crate::response::response_type::ResponseType;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use js_sys::{Array, Object, Reflect};
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// `CORS` 安全的响应头, 跨域时总是可以读取
+#[doc = "https://fetch.spec.whatwg.org/#cors-safelisted-response-header-name"]
+pub const SAFELISTED_HEADERS: [&str; 7] = ["cache-control", "content-language", "content-length", "content-type", "expires", "last-modified", "pragma"];
+
+/// 值中可能包含 `,` 且不是列表语法的 `header`, 不做拆分
+const SINGLETON_HEADERS: [&str; 10] = [
+    "date",
+    "expires",
+    "last-modified",
+    "if-modified-since",
+    "if-unmodified-since",
+    "retry-after",
+    "set-cookie",
+    "www-authenticate",
+    "proxy-authenticate",
+    "content-disposition",
+];
+
+/// 把合并后的 `header` 值拆分成多个值, 忽略引号和 `<>` 中的 `,`
+#[doc = "https://www.rfc-editor.org/rfc/rfc9110#section-5.6.1"]
+pub fn split_header_values(name: &str, value: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    if SINGLETON_HEADERS.contains(&name.as_str()) {
+        return vec![value.trim().to_string()];
+    }
+
+    let mut values: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut in_brackets = false;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        if escaped {
+            escaped = false;
+            current.push(c);
+            continue;
+        }
+
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '<' if !in_quotes => in_brackets = true,
+            '>' if !in_quotes => in_brackets = false,
+            ',' if !in_quotes && !in_brackets => {
+                let item = current.trim();
+                if !item.is_empty() {
+                    values.push(item.to_string());
+                }
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(c);
+    }
+
+    let item = current.trim();
+    if !item.is_empty() {
+        values.push(item.to_string());
+    }
+
+    values
+}
+
+/// 添加 `header`, 同名多值会被拆分, 非法的名称或值会被忽略
+pub(crate) fn append_header(headers: &mut HeaderMap, name: &str, value: &str) {
+    let header_name = match HeaderName::from_bytes(name.as_bytes()) {
+        Ok(header_name) => header_name,
+        Err(_) => return,
+    };
+
+    for value in split_header_values(name, value) {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.append(header_name.clone(), value);
+        }
+    }
+}
+
+/// 获取 `header` 的所有值
+fn get_values(headers: &HeaderMap, name: &str) -> Vec<String> {
+    headers.get_all(name).iter().filter_map(|value| value.to_str().ok()).map(|value| value.to_string()).collect()
+}
+
+/// `HeaderMap` 的序列化, 同名多值以 `, ` 连接, 与 `Headers.get` 保持一致
+pub(crate) fn serialize<S>(headers: &HeaderMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for name in headers.keys() {
+        map.insert(name.to_string(), get_values(headers, name.as_str()).join(", "));
+    }
+
+    map.serialize(serializer)
+}
+
+/// `HeaderMap` 的反序列化, 支持 `string` 和 `string[]` 两种值
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<HeaderMap, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Values {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let map: BTreeMap<String, Values> = BTreeMap::deserialize(deserializer)?;
+    let mut headers = HeaderMap::new();
+    for (name, values) in map {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(DeError::custom)?;
+        let values = match values {
+            Values::One(value) => vec![value],
+            Values::Many(values) => values,
+        };
+
+        for value in values {
+            headers.append(header_name.clone(), HeaderValue::from_str(&value).map_err(DeError::custom)?);
+        }
+    }
+
+    Ok(headers)
+}
+
+/// 暴露给 `js` 的响应头
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ResponseHeaders {
+    headers: HeaderMap,
+    kind: ResponseType, // 响应类型
+}
+
+#[wasm_bindgen]
+impl ResponseHeaders {
+    /**
+      获取 `header`, 忽略大小写, 同名多值以 `, ` 连接
+    */
+    pub fn get(&self, name: &str) -> Option<String> {
+        let values = get_values(&self.headers, name);
+        if values.is_empty() {
+            return None;
+        }
+
+        Some(values.join(", "))
+    }
+
+    /**
+      获取 `header` 的所有值
+    */
+    #[wasm_bindgen(js_name = getAll)]
+    pub fn get_all(&self, name: &str) -> Vec<String> {
+        get_values(&self.headers, name)
+    }
+
+    /**
+      是否存在 `header`
+    */
+    pub fn has(&self, name: &str) -> bool {
+        self.headers.contains_key(name)
+    }
+
+    /**
+      所有的 `header` 名称(小写)
+    */
+    pub fn keys(&self) -> Vec<String> {
+        self.headers.keys().map(|name| name.to_string()).collect()
+    }
+
+    /**
+      所有的 `[name, value]`, 同名多值各占一项
+    */
+    pub fn entries(&self) -> Array {
+        let entries = Array::new();
+        for (name, value) in self.headers.iter() {
+            if let Ok(value) = value.to_str() {
+                entries.push(&Array::of2(&JsValue::from_str(name.as_str()), &JsValue::from_str(value)));
+            }
+        }
+
+        entries
+    }
+
+    /**
+      响应头是否被 `CORS` 过滤(跨域或 `opaque` 响应)
+    */
+    #[wasm_bindgen(getter)]
+    pub fn filtered(&self) -> bool {
+        self.kind.is_filtered()
+    }
+
+    /**
+      跨域响应中通过 `Access-Control-Expose-Headers` 暴露的 `header`, 即除 `CORS` 安全 `header` 以外可读取的 `header`
+    */
+    pub fn exposed(&self) -> Vec<String> {
+        if !self.filtered() {
+            return Vec::new();
+        }
+
+        self.keys().into_iter().filter(|name| !SAFELISTED_HEADERS.contains(&name.as_str())).collect()
+    }
+
+    /**
+      `header` 是否因为 `CORS` 被隐藏, 即响应被过滤、且该 `header` 既不可读取也不是 `CORS` 安全的 `header`, `opaque` 响应的所有 `header` 都被隐藏
+    */
+    #[wasm_bindgen(js_name = isHidden)]
+    pub fn is_hidden(&self, name: &str) -> bool {
+        if self.kind.is_opaque() {
+            return true;
+        }
+
+        if !self.filtered() || self.has(name) {
+            return false;
+        }
+
+        !SAFELISTED_HEADERS.contains(&name.to_lowercase().as_str())
+    }
+
+    /**
+      `JSON.stringify` 时输出普通对象
+    */
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        let obj = Object::new();
+        for name in self.headers.keys() {
+            let value = get_values(&self.headers, name.as_str()).join(", ");
+            Reflect::set(&obj, &JsValue::from_str(name.as_str()), &JsValue::from_str(&value))?;
+        }
+
+        Ok(obj.into())
+    }
+}
+
+impl ResponseHeaders {
+    pub fn new(headers: HeaderMap, kind: ResponseType) -> Self {
+        Self { headers, kind }
+    }
+
+    pub fn header_map(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
@@ -0,0 +1,106 @@
+/*!
+    `JS` 调用时使用 `JSON.parse` 解析 `JSON` 响应, 避免 `serde_json` 解析、遍历及 `serde_wasm_bindgen` 转换的开销。
+    大数字的处理与 `serde` 路径相同: 超出 `Number.MAX_SAFE_INTEGER` 的数字及 `0` 转换为字符串。
+    只有文本中存在需要转换的数字时才使用 `reviver`, `reviver` 通过 `JSON.parse` 源文本访问(`context.source`)得到原始的数字文本,
+    不支持源文本访问的浏览器(如 `Safari`)中大数字已经丢失精度, 此时使用 `serde_json` 解析。
+    `reviver` 对每个值都会调用一次, 使用 `JS` 实现, 避免每次调用都进出 `wasm`(见 `tests/json_bench.rs`)。
+*/
+
+use crate::error::Error;
+use crate::send::HttpClient;
+use js_sys::{Number, JSON};
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(inline_js = r#"
+function reviver(key, value, context) {
+    if (typeof value !== "number") {
+        return value;
+    }
+
+    if (value === 0) {
+        return "0";
+    }
+
+    // 原始的数字文本
+    if (!Number.isFinite(value) || Math.abs(value) > Number.MAX_SAFE_INTEGER) {
+        return context.source;
+    }
+
+    return value;
+}
+
+let source;
+
+export function supports_source() {
+    if (source === undefined) {
+        source = false;
+        JSON.parse("1", (key, value, context) => {
+            source = typeof context?.source === "string";
+            return value;
+        });
+    }
+
+    return source;
+}
+
+export function parse_with_reviver(text) {
+    return JSON.parse(text, reviver);
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(catch)]
+    fn parse_with_reviver(text: &str) -> Result<JsValue, JsValue>;
+
+    fn supports_source() -> bool;
+}
+
+/// 是否需要转换数字, 即是否存在 `0` 或绝对值超出 `Number.MAX_SAFE_INTEGER` 的数字
+pub fn needs_reviver(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            // 跳过字符串
+            b'"' => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'"' {
+                    index += if bytes[index] == b'\\' { 2 } else { 1 };
+                }
+                index += 1;
+            }
+            b'-' | b'0'..=b'9' => {
+                let start = index;
+                while index < bytes.len() && matches!(bytes[index], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') {
+                    index += 1;
+                }
+
+                if let Ok(number) = text[start..index].parse::<f64>() {
+                    if number == 0.0 || number.is_infinite() || number.abs() > Number::MAX_SAFE_INTEGER {
+                        return true;
+                    }
+                }
+            }
+            _ => index += 1,
+        }
+    }
+
+    false
+}
+
+/// 使用 `JSON.parse` 解析, 格式错误时返回 `Error::MissingResponseBody`
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/JSON/parse)"]
+pub fn parse(text: &str) -> Result<JsValue, Error> {
+    if !needs_reviver(text) {
+        return JSON::parse(text).map_err(|_| Error::MissingResponseBody);
+    }
+
+    if supports_source() {
+        return parse_with_reviver(text).map_err(|_| Error::MissingResponseBody);
+    }
+
+    // 不支持源文本访问, 与 `serde` 路径相同
+    let value: Value = serde_json::from_str(text).map_err(|_| Error::MissingResponseBody)?;
+    HttpClient::convert_numbers(value).serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|err| Error::Error(err.to_string()))
+}
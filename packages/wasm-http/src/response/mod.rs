@@ -0,0 +1,8 @@
+//! 响应相关
+
+pub mod charset;
+pub mod digest;
+pub mod headers;
+pub mod json;
+pub mod response_type;
+pub mod timing;
@@ -0,0 +1,51 @@
+/*!
+    `Response` 接口的 `type` 只读属性包含响应的类型, 决定了响应的哪些部分可以被读取。
+    默认的 `Mode::NoCors` 请求跨域资源时得到 `opaque` 响应, `status` 为 `0`, `headers` 和 `body` 都无法读取。
+*/
+
+use serde::{Deserialize, Serialize};
+
+#[doc = "https://developer.mozilla.org/en-US/docs/Web/API/Response/type"]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseType {
+    /// 同源响应, 除 `Set-Cookie` 外的 `header` 都可以读取
+    #[default]
+    Basic,
+
+    /// 跨域响应, 只能读取 `CORS` 安全的 `header` 及 `Access-Control-Expose-Headers` 中声明的 `header`
+    Cors,
+
+    /// 网络错误
+    Error,
+
+    /// `no-cors` 跨域请求的响应, `status` 为 `0`, `headers` 和 `body` 都无法读取
+    Opaque,
+
+    /// `Redirect::Manual` 请求遇到重定向时的响应, 与 `opaque` 一样无法读取
+    OpaqueRedirect,
+}
+
+impl From<web_sys::ResponseType> for ResponseType {
+    fn from(value: web_sys::ResponseType) -> Self {
+        match value {
+            web_sys::ResponseType::Cors => ResponseType::Cors,
+            web_sys::ResponseType::Error => ResponseType::Error,
+            web_sys::ResponseType::Opaque => ResponseType::Opaque,
+            web_sys::ResponseType::Opaqueredirect => ResponseType::OpaqueRedirect,
+            _ => ResponseType::Basic,
+        }
+    }
+}
+
+impl ResponseType {
+    /// 响应头是否被 `CORS` 过滤
+    pub fn is_filtered(&self) -> bool {
+        matches!(self, ResponseType::Cors | ResponseType::Opaque | ResponseType::OpaqueRedirect)
+    }
+
+    /// `headers` 和 `body` 是否无法读取
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, ResponseType::Opaque | ResponseType::OpaqueRedirect)
+    }
+}
@@ -0,0 +1,130 @@
+/*!
+    请求耗时, 时间基于 `performance.now()`, 不支持时使用 `Date.now()`。
+    请求结束后根据 `url` 查找对应的 `PerformanceResourceTiming`, 得到 `DNS`、建立连接、首字节及下载等阶段的耗时。
+    跨域请求的响应没有 `Timing-Allow-Origin` 时浏览器不提供这些阶段, 此时只有 `startTime`、`endTime`、`duration`。
+*/
+
+use js_sys::{Array, Date, Function, Reflect};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Timing {
+    #[serde(rename = "startTime")]
+    pub start_time: f64, // 开始发送请求的时间
+    #[serde(rename = "endTime")]
+    pub end_time: f64, // 读取完 `body` 的时间
+    pub duration: f64, // 耗时(毫秒)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<f64>, // `DNS` 查询
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect: Option<f64>, // 建立连接, 包括 `TLS`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<f64>, // `TLS` 握手
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb: Option<f64>, // 从开始请求到收到第一个字节
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download: Option<f64>, // 下载 `body`
+    #[serde(default, rename = "transferSize", skip_serializing_if = "Option::is_none")]
+    pub transfer_size: Option<f64>, // 传输的大小, 包括 `headers`, 命中缓存时为 `0`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>, // 协议, 如 `h2`、`http/1.1`
+}
+
+/// `PerformanceResourceTiming` 中的时间点
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/PerformanceResourceTiming)"]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceEntry {
+    pub start_time: f64,
+    pub domain_lookup_start: f64,
+    pub domain_lookup_end: f64,
+    pub connect_start: f64,
+    pub connect_end: f64,
+    pub secure_connection_start: f64,
+    pub response_start: f64,
+    pub response_end: f64,
+    pub transfer_size: f64,
+    pub next_hop_protocol: String,
+}
+
+impl Timing {
+    /// 开始计时
+    pub fn start() -> Self {
+        Self { start_time: now(), ..Default::default() }
+    }
+
+    /// 结束计时
+    pub fn end(mut self) -> Self {
+        self.end_time = now();
+        self.duration = self.end_time - self.start_time;
+        self
+    }
+
+    /// 根据 `PerformanceResourceTiming` 计算各阶段的耗时, 时间点为 `0` 表示浏览器未提供
+    pub fn with_resource(mut self, entry: &ResourceEntry) -> Self {
+        let span = |start: f64, end: f64| if start > 0.0 && end >= start { Some(end - start) } else { None };
+        if entry.response_start > 0.0 {
+            self.dns = span(entry.domain_lookup_start, entry.domain_lookup_end);
+            self.connect = span(entry.connect_start, entry.connect_end);
+            self.tls = span(entry.secure_connection_start, entry.connect_end);
+            self.ttfb = span(entry.start_time, entry.response_start);
+            self.download = span(entry.response_start, entry.response_end);
+            self.transfer_size = Some(entry.transfer_size);
+        }
+
+        if !entry.next_hop_protocol.is_empty() {
+            self.protocol = Some(entry.next_hop_protocol.clone());
+        }
+
+        self
+    }
+
+    /// 查找 `url` 在本次请求开始之后的最后一个 `PerformanceResourceTiming`, 找不到时不处理
+    pub(crate) fn resource(self, url: &str) -> Self {
+        match find_resource(url, self.start_time) {
+            Some(entry) => self.with_resource(&entry),
+            None => self,
+        }
+    }
+}
+
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Performance/getEntriesByName)"]
+fn find_resource(url: &str, start_time: f64) -> Option<ResourceEntry> {
+    let performance = Reflect::get(&js_sys::global(), &JsValue::from_str("performance")).ok().filter(|performance| performance.is_object())?;
+    let get_entries_by_name = Reflect::get(&performance, &JsValue::from_str("getEntriesByName")).ok()?.dyn_into::<Function>().ok()?;
+    let entries = get_entries_by_name.call2(&performance, &JsValue::from_str(url), &JsValue::from_str("resource")).ok()?;
+
+    let number = |entry: &JsValue, name: &str| Reflect::get(entry, &JsValue::from_str(name)).ok().and_then(|value| value.as_f64()).unwrap_or(0.0);
+    // 允许 `1ms` 的误差
+    let entry = Array::from(&entries).iter().rev().find(|entry| number(entry, "startTime") >= start_time - 1.0)?;
+
+    Some(ResourceEntry {
+        start_time: number(&entry, "startTime"),
+        domain_lookup_start: number(&entry, "domainLookupStart"),
+        domain_lookup_end: number(&entry, "domainLookupEnd"),
+        connect_start: number(&entry, "connectStart"),
+        connect_end: number(&entry, "connectEnd"),
+        secure_connection_start: number(&entry, "secureConnectionStart"),
+        response_start: number(&entry, "responseStart"),
+        response_end: number(&entry, "responseEnd"),
+        transfer_size: number(&entry, "transferSize"),
+        next_hop_protocol: Reflect::get(&entry, &JsValue::from_str("nextHopProtocol")).ok().and_then(|value| value.as_string()).unwrap_or_default(),
+    })
+}
+
+/// 当前时间, 毫秒
+#[doc = "[MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/Performance/now)"]
+pub(crate) fn now() -> f64 {
+    let performance = Reflect::get(&js_sys::global(), &JsValue::from_str("performance")).unwrap_or(JsValue::UNDEFINED);
+    if !performance.is_undefined() && !performance.is_null() {
+        if let Ok(now) = Reflect::get(&performance, &JsValue::from_str("now")) {
+            if let Some(now) = now.dyn_ref::<Function>() {
+                if let Some(now) = now.call0(&performance).ok().and_then(|value| value.as_f64()) {
+                    return now;
+                }
+            }
+        }
+    }
+
+    Date::now()
+}
@@ -0,0 +1,129 @@
+/*!
+    响应的 `schema` 校验, 用于尽早发现后端接口的变化。
+    `schema` 可以是 `JSON Schema` 对象, 也可以是实现了 `serde::Deserialize` 的 `Rust` 类型。
+    校验失败时返回 `Error::Validation`, 其中列出了每个错误的 `JSON Pointer` 路径和关键字; `SchemaMode::Warn` 时只打印警告。
+*/
+
+mod validator;
+
+use crate::error::Error;
+use crate::schema::validator::Validator;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+
+/// 校验失败的地方
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub path: String,    // `JSON Pointer` 路径, 如 `/data/0/name`
+    pub keyword: String, // 违反的关键字, 如 `type`、`required`
+    pub message: String, // 说明
+}
+
+impl Violation {
+    pub fn new(path: &str, keyword: &str, message: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            keyword: keyword.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// `JSON Pointer` 转义
+    #[doc = "https://www.rfc-editor.org/rfc/rfc6901#section-3"]
+    pub(crate) fn escape(segment: &str) -> String {
+        segment.replace('~', "~0").replace('/', "~1")
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        write!(f, "{}: {} ({})", path, self.message, self.keyword)
+    }
+}
+
+/// 校验失败时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaMode {
+    /// 返回 `Error::Validation`
+    #[default]
+    Error,
+
+    /// 只打印警告, 并写入响应的 `error` 中, 用于逐步接入
+    Warn,
+}
+
+impl SchemaMode {
+    pub fn get_schema_mode(mode: String) -> SchemaMode {
+        match mode.trim().to_lowercase().as_str() {
+            "warn" => SchemaMode::Warn,
+            _ => SchemaMode::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Schema {
+    /// `JSON Schema` 对象
+    Json(Value),
+
+    /// `Rust` 类型, 通过 `Schema::of::<T>()` 创建
+    Type(fn(&Value) -> Vec<Violation>),
+}
+
+impl Schema {
+    /// 使用 `serde` 类型作为 `schema`
+    pub fn of<T: DeserializeOwned>() -> Self {
+        Schema::Type(Self::check_type::<T>)
+    }
+
+    /// 校验 `body`
+    pub fn validate(&self, body: &Value) -> Result<(), Error> {
+        let violations = match self {
+            Schema::Json(schema) => Validator::new(schema).validate(body),
+            Schema::Type(check) => check(body),
+        };
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::Validation(violations))
+    }
+
+    fn check_type<T: DeserializeOwned>(body: &Value) -> Vec<Violation> {
+        let err = match serde_path_to_error::deserialize::<_, T>(body) {
+            Ok(_) => return Vec::new(),
+            Err(err) => err,
+        };
+
+        let mut path = String::new();
+        for segment in err.path().iter() {
+            match segment {
+                serde_path_to_error::Segment::Seq { index } => path.push_str(&format!("/{}", index)),
+                serde_path_to_error::Segment::Map { key } => path.push_str(&format!("/{}", Violation::escape(key))),
+                serde_path_to_error::Segment::Enum { variant } => path.push_str(&format!("/{}", Violation::escape(variant))),
+                serde_path_to_error::Segment::Unknown => path.push_str("/?"),
+            }
+        }
+
+        // 根据 `serde` 的错误信息推断关键字
+        let message = err.inner().to_string();
+        let keyword = if message.starts_with("missing field") {
+            "required"
+        } else if message.starts_with("unknown field") {
+            "additionalProperties"
+        } else if message.starts_with("unknown variant") {
+            "enum"
+        } else if message.starts_with("invalid length") {
+            "minItems"
+        } else {
+            "type"
+        };
+
+        vec![Violation::new(&path, keyword, &message)]
+    }
+}
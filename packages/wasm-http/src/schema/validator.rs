@@ -0,0 +1,310 @@
+/*!
+    `JSON Schema` 校验, 支持常用的关键字:
+    - 通用: `type`、`enum`、`const`、`$ref`(仅支持文档内的引用, 如 `#/$defs/user`)
+    - 组合: `allOf`、`anyOf`、`oneOf`、`not`
+    - 对象: `properties`、`required`、`additionalProperties`、`minProperties`、`maxProperties`
+    - 数组: `items`、`prefixItems`、`minItems`、`maxItems`、`uniqueItems`
+    - 字符串: `minLength`、`maxLength`、`pattern`
+    - 数字: `minimum`、`maximum`、`exclusiveMinimum`、`exclusiveMaximum`、`multipleOf`
+
+    其他关键字(如 `format`)会被忽略。
+*/
+
+use crate::schema::Violation;
+use regex_lite::Regex;
+use serde_json::{Map, Value};
+
+/// `$ref` 的最大嵌套层数, 避免循环引用
+const MAX_DEPTH: usize = 64;
+
+#[doc = "https://json-schema.org/draft/2020-12/json-schema-validation"]
+pub(crate) struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl<'a> Validator<'a> {
+    pub(crate) fn new(root: &'a Value) -> Self {
+        Self { root }
+    }
+
+    /// 校验 `instance`, 返回所有不符合的地方
+    pub(crate) fn validate(&self, instance: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.validate_schema(self.root, instance, "", 0, &mut violations);
+        violations
+    }
+
+    fn is_valid(&self, schema: &Value, instance: &Value, path: &str, depth: usize) -> bool {
+        let mut violations = Vec::new();
+        self.validate_schema(schema, instance, path, depth, &mut violations);
+        violations.is_empty()
+    }
+
+    fn validate_schema(&self, schema: &Value, instance: &Value, path: &str, depth: usize, violations: &mut Vec<Violation>) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                violations.push(Violation::new(path, "false", "no value is allowed"));
+                return;
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        // $ref
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            match self.resolve(reference) {
+                Some(target) if depth < MAX_DEPTH => self.validate_schema(target, instance, path, depth + 1, violations),
+                Some(_) => violations.push(Violation::new(path, "$ref", &format!("too many nested references: {}", reference))),
+                None => violations.push(Violation::new(path, "$ref", &format!("can not resolve reference: {}", reference))),
+            }
+        }
+
+        self.validate_common(schema, instance, path, violations);
+        self.validate_combination(schema, instance, path, depth, violations);
+
+        match instance {
+            Value::Object(object) => self.validate_object(schema, object, path, depth, violations),
+            Value::Array(array) => self.validate_array(schema, array, path, depth, violations),
+            Value::String(string) => Self::validate_string(schema, string, path, violations),
+            Value::Number(_) => Self::validate_number(schema, instance, path, violations),
+            _ => {}
+        }
+    }
+
+    /// 只支持文档内的引用
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        let pointer = reference.strip_prefix('#')?;
+        if pointer.is_empty() {
+            return Some(self.root);
+        }
+
+        self.root.pointer(pointer)
+    }
+
+    /// type、enum、const
+    fn validate_common(&self, schema: &Map<String, Value>, instance: &Value, path: &str, violations: &mut Vec<Violation>) {
+        if let Some(types) = schema.get("type") {
+            let matched = match types {
+                Value::String(name) => Self::is_type(name, instance),
+                Value::Array(names) => names.iter().filter_map(Value::as_str).any(|name| Self::is_type(name, instance)),
+                _ => true,
+            };
+
+            if !matched {
+                violations.push(Violation::new(path, "type", &format!("expected type {}, found {}", types, Self::type_of(instance))));
+            }
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.iter().any(|value| Self::equals(value, instance)) {
+                violations.push(Violation::new(path, "enum", &format!("{} is not one of {}", instance, Value::Array(values.clone()))));
+            }
+        }
+
+        if let Some(value) = schema.get("const") {
+            if !Self::equals(value, instance) {
+                violations.push(Violation::new(path, "const", &format!("expected {}, found {}", value, instance)));
+            }
+        }
+    }
+
+    /// allOf、anyOf、oneOf、not
+    fn validate_combination(&self, schema: &Map<String, Value>, instance: &Value, path: &str, depth: usize, violations: &mut Vec<Violation>) {
+        if let Some(schemas) = schema.get("allOf").and_then(Value::as_array) {
+            for item in schemas {
+                self.validate_schema(item, instance, path, depth, violations);
+            }
+        }
+
+        if let Some(schemas) = schema.get("anyOf").and_then(Value::as_array) {
+            if !schemas.iter().any(|item| self.is_valid(item, instance, path, depth)) {
+                violations.push(Violation::new(path, "anyOf", "does not match any of the schemas"));
+            }
+        }
+
+        if let Some(schemas) = schema.get("oneOf").and_then(Value::as_array) {
+            let count = schemas.iter().filter(|item| self.is_valid(item, instance, path, depth)).count();
+            if count != 1 {
+                violations.push(Violation::new(path, "oneOf", &format!("matches {} of the schemas, expected exactly one", count)));
+            }
+        }
+
+        if let Some(item) = schema.get("not") {
+            if self.is_valid(item, instance, path, depth) {
+                violations.push(Violation::new(path, "not", "must not match the schema"));
+            }
+        }
+    }
+
+    fn validate_object(&self, schema: &Map<String, Value>, object: &Map<String, Value>, path: &str, depth: usize, violations: &mut Vec<Violation>) {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    violations.push(Violation::new(path, "required", &format!("\"{}\" is a required property", name)));
+                }
+            }
+        }
+
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) < min {
+                violations.push(Violation::new(path, "minProperties", &format!("has {} properties, expected at least {}", object.len(), min)));
+            }
+        }
+
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) > max {
+                violations.push(Violation::new(path, "maxProperties", &format!("has {} properties, expected at most {}", object.len(), max)));
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+        for (name, value) in object {
+            let property_path = format!("{}/{}", path, Violation::escape(name));
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property) => self.validate_schema(property, value, &property_path, depth, violations),
+                None => match additional {
+                    Some(Value::Bool(false)) => violations.push(Violation::new(&property_path, "additionalProperties", &format!("additional property \"{}\" is not allowed", name))),
+                    Some(additional) => self.validate_schema(additional, value, &property_path, depth, violations),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn validate_array(&self, schema: &Map<String, Value>, array: &[Value], path: &str, depth: usize, violations: &mut Vec<Violation>) {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (array.len() as u64) < min {
+                violations.push(Violation::new(path, "minItems", &format!("has {} items, expected at least {}", array.len(), min)));
+            }
+        }
+
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (array.len() as u64) > max {
+                violations.push(Violation::new(path, "maxItems", &format!("has {} items, expected at most {}", array.len(), max)));
+            }
+        }
+
+        if let Some(true) = schema.get("uniqueItems").and_then(Value::as_bool) {
+            let duplicated = array.iter().enumerate().any(|(index, item)| array[..index].iter().any(|other| Self::equals(other, item)));
+            if duplicated {
+                violations.push(Violation::new(path, "uniqueItems", "has duplicate items"));
+            }
+        }
+
+        // prefixItems(2020-12), 以及旧版本中数组形式的 items
+        let (prefix, items) = match (schema.get("prefixItems").and_then(Value::as_array), schema.get("items")) {
+            (Some(prefix), items) => (prefix.as_slice(), items),
+            (None, Some(Value::Array(prefix))) => (prefix.as_slice(), schema.get("additionalItems")),
+            (None, items) => (&[][..], items),
+        };
+
+        for (index, value) in array.iter().enumerate() {
+            let item_path = format!("{}/{}", path, index);
+            if let Some(item) = prefix.get(index).or(items) {
+                self.validate_schema(item, value, &item_path, depth, violations);
+            }
+        }
+    }
+
+    fn validate_string(schema: &Map<String, Value>, string: &str, path: &str, violations: &mut Vec<Violation>) {
+        let length = string.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                violations.push(Violation::new(path, "minLength", &format!("length is {}, expected at least {}", length, min)));
+            }
+        }
+
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                violations.push(Violation::new(path, "maxLength", &format!("length is {}, expected at most {}", length, max)));
+            }
+        }
+
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            match Regex::new(pattern) {
+                Ok(regex) => {
+                    if !regex.is_match(string) {
+                        violations.push(Violation::new(path, "pattern", &format!("\"{}\" does not match \"{}\"", string, pattern)));
+                    }
+                }
+                Err(err) => violations.push(Violation::new(path, "pattern", &format!("invalid pattern \"{}\": {}", pattern, err))),
+            }
+        }
+    }
+
+    fn validate_number(schema: &Map<String, Value>, instance: &Value, path: &str, violations: &mut Vec<Violation>) {
+        let number = match instance.as_f64() {
+            Some(number) => number,
+            None => return,
+        };
+
+        let limit = |name: &str| schema.get(name).and_then(Value::as_f64);
+        if let Some(min) = limit("minimum") {
+            if number < min {
+                violations.push(Violation::new(path, "minimum", &format!("{} is less than {}", instance, min)));
+            }
+        }
+
+        if let Some(max) = limit("maximum") {
+            if number > max {
+                violations.push(Violation::new(path, "maximum", &format!("{} is greater than {}", instance, max)));
+            }
+        }
+
+        if let Some(min) = limit("exclusiveMinimum") {
+            if number <= min {
+                violations.push(Violation::new(path, "exclusiveMinimum", &format!("{} is less than or equal to {}", instance, min)));
+            }
+        }
+
+        if let Some(max) = limit("exclusiveMaximum") {
+            if number >= max {
+                violations.push(Violation::new(path, "exclusiveMaximum", &format!("{} is greater than or equal to {}", instance, max)));
+            }
+        }
+
+        if let Some(multiple) = limit("multipleOf").filter(|multiple| *multiple > 0.0) {
+            let quotient = number / multiple;
+            if (quotient - quotient.round()).abs() > f64::EPSILON * quotient.abs().max(1.0) {
+                violations.push(Violation::new(path, "multipleOf", &format!("{} is not a multiple of {}", instance, multiple)));
+            }
+        }
+    }
+
+    fn is_type(name: &str, instance: &Value) -> bool {
+        match name {
+            "null" => instance.is_null(),
+            "boolean" => instance.is_boolean(),
+            "object" => instance.is_object(),
+            "array" => instance.is_array(),
+            "string" => instance.is_string(),
+            "number" => instance.is_number(),
+            "integer" => instance.is_i64() || instance.is_u64() || instance.as_f64().map(|number| number.fract() == 0.0).unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    fn type_of(instance: &Value) -> &'static str {
+        match instance {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Object(_) => "object",
+            Value::Array(_) => "array",
+            Value::String(_) => "string",
+            Value::Number(number) if number.is_f64() => "number",
+            Value::Number(_) => "integer",
+        }
+    }
+
+    /// 数字按数值比较, 如 `1` 和 `1.0` 相等
+    fn equals(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+            (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Self::equals(a, b)),
+            (Value::Object(a), Value::Object(b)) => a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).map(|b| Self::equals(a, b)).unwrap_or(false)),
+            _ => a == b,
+        }
+    }
+}
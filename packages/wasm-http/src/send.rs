@@ -1,16 +1,28 @@
+use crate::circuit::layer::CircuitLayer;
+use crate::client::beacon;
+use crate::client::call::Call;
 use crate::client::Client;
 use crate::error::Error;
-use crate::request::cache::Cache;
-use crate::request::credentials::Credentials;
-use crate::request::mode::Mode;
-use crate::request::redirect::Redirect;
-use crate::request::referrer_policy::ReferrerPolicy;
+#[cfg(feature = "fault-injection")]
+use crate::fault::layer::FaultLayer;
+use crate::har::layer::HarLayer;
+use crate::har::{self, Wire};
+use crate::instance::HttpInstance;
+use crate::metrics::layer::MetricsLayer;
+use crate::multipart;
+use crate::options::{self, OPTIONS_FIELDS, REQUEST_FIELDS};
+use crate::pagination::Paginator;
+use crate::request::idempotency::{self, IdempotencyKey};
+use crate::request::input;
 use crate::request::HttpRequest;
-use crate::{HttpRequestOptions};
+use crate::response::headers::ResponseHeaders;
+use crate::response::json;
+use crate::{HttpRequestOptions, HttpResponseOptions};
 use http::Request;
 use js_sys::{JsString, Number, Object};
 use serde_json::Value;
-use serde_wasm_bindgen::from_value;
+#[cfg(not(feature = "fault-injection"))]
+use tower::layer::util::Identity;
 use tower::{ServiceBuilder, ServiceExt};
 use wasm_bindgen::prelude::*;
 
@@ -29,177 +41,68 @@ const RATE_LIMIT: u64 = 5;
 pub struct HttpClient;
 
 impl HttpClient {
-    fn get_str(field_value: JsValue) -> String {
+    pub(crate) fn get_str(field_value: JsValue) -> String {
         let value = JsString::from(field_value);
         let value = String::from(value);
         value
     }
 
-    /// 获取 `http` `options`
-    fn get_http_options(opts: JsValue) -> Result<HttpRequestOptions, JsValue> {
-        if !opts.is_object() {
-            return Err(JsValue::from_str(&Error::Error("`opts` is not a object !".to_string()).to_string()));
-        }
-
-        if let Some(obj) = opts.dyn_ref::<Object>() {
-            if obj.is_null() {
-                return Err(JsValue::from_str(&Error::Error("`opts` is null !".to_string()).to_string()));
-            }
-
-            let mut options = HttpRequestOptions::default();
-
-            // url
-            let url = js_sys::Reflect::get(&obj, &JsValue::from_str("url")).ok();
-            if let Some(url) = url {
-                options.url = Self::get_str(url);
-            }
-
-            if options.url.is_empty() {
-                return Err(JsValue::from_str(&Error::Error("`url` is empty !".to_string()).to_string()));
-            }
-
-            // method
-            let method = js_sys::Reflect::get(&obj, &JsValue::from_str("method")).ok();
-            if let Some(method) = method {
-                if !method.is_null() {
-                    options.method = Some(Self::get_str(method));
-                }
-            }
-
-            // data
-            let data = js_sys::Reflect::get(&obj, &JsValue::from_str("data")).ok();
-            options.data = data;
-
-            // headers
-            let headers = js_sys::Reflect::get(&obj, &JsValue::from_str("headers")).ok();
-            if let Some(headers) = headers {
-                if !headers.is_object() {
-                    return Err(JsValue::from_str(&Error::Error("`headers` is not a object !".to_string()).to_string()));
-                }
-
-                options.headers = Some(from_value(headers).ok().unwrap())
-            }
-
-            // timeout
-            let timeout = js_sys::Reflect::get(&obj, &JsValue::from_str("timeout")).ok();
-            if let Some(timeout) = timeout {
-                if timeout.is_null() {
-                    options.timeout = None;
-                } else {
-                    if timeout.is_string() {
-                        let timeout = Self::get_str(timeout).trim().to_string();
-                        let timeout = timeout.parse::<i32>().unwrap_or(0);
-                        options.timeout = Some(timeout);
-                    } else {
-                        if let Some(timeout) = timeout.dyn_ref::<Number>() {
-                            let timeout = timeout.as_f64().map(|f| f as i32);
-                            options.timeout = timeout;
-                        }
-                    }
-                }
-            }
-
-            // type
-            let _type = js_sys::Reflect::get(&obj, &JsValue::from_str("type")).ok();
-            if let Some(_type) = _type {
-                let request_type: String = Self::get_str(_type);
-                let request_type = request_type.parse::<i32>().unwrap_or(0);
-                options.request_type = Some(crate::HttpRequestType::get_type(request_type));
-            }
-
-            // responseType
-            let response_type = js_sys::Reflect::get(&obj, &JsValue::from_str("responseType")).ok();
-            if let Some(response_type) = response_type {
-                let response_type: String = Self::get_str(response_type);
-                let response_type = response_type.parse::<i32>().unwrap_or(0);
-                options.response_type = Some(crate::HttpRequestType::get_type(response_type));
-            }
-
-            return Ok(options);
+    /// 获取对象中的字段, `undefined` 和 `null` 视为未设置
+    pub(crate) fn get_value(obj: &Object, name: &str) -> Option<JsValue> {
+        let value = js_sys::Reflect::get(obj, &JsValue::from_str(name)).ok()?;
+        if value.is_undefined() || value.is_null() {
+            return None;
         }
 
-        Err(JsValue::from_str(&Error::Error("`opts` is not a object !".to_string()).to_string()))
+        Some(value)
     }
 
-    /// 获取 `request` `options`
-    fn get_request_options(request: JsValue) -> Result<HttpRequest, JsValue> {
-        let mut http_request = HttpRequest::default();
-        if request.is_null() {
-            return Ok(http_request);
-        }
-
-        if !request.is_object() {
-            return Err(JsValue::from_str(&Error::Error("`request` is not a object !".to_string()).to_string()));
-        }
-
-        if let Some(obj) = request.dyn_ref::<Object>() {
-            if obj.is_null() {
-                return Ok(http_request);
-            }
-
-            // cache
-            let cache = js_sys::Reflect::get(&obj, &JsValue::from_str("cache")).ok();
-            if let Some(cache) = cache {
-                let cache = Self::get_str(cache);
-                http_request.cache = Some(Cache::get_cache(cache));
-            }
-
-            // credentials
-            let credentials = js_sys::Reflect::get(&obj, &JsValue::from_str("credentials")).ok();
-            if let Some(credentials) = credentials {
-                let credentials = Self::get_str(credentials);
-                http_request.credentials = Some(Credentials::get_credentials(credentials));
-            }
-
-            // integrity
-            let integrity = js_sys::Reflect::get(&obj, &JsValue::from_str("integrity")).ok();
-            if let Some(integrity) = integrity {
-                let integrity = Self::get_str(integrity);
-                let integrity = integrity.trim();
-                if !integrity.is_empty() {
-                    http_request.integrity = Some(integrity.to_string());
-                }
-            }
-
-            // mode
-            let mode = js_sys::Reflect::get(&obj, &JsValue::from_str("mode")).ok();
-            if let Some(mode) = mode {
-                let mode = Self::get_str(mode);
-                if !mode.is_empty() {
-                    http_request.mode = Some(Mode::get_mode(mode));
+    /// 解析 `send` 的参数, 收集 `opts` 和 `request` 中所有字段的错误
+    /// `opts` 为 `Request` 或 `URL` 时, `request` 中的 `opts` 字段合并在其上
+    fn get_options(opts: &JsValue, request: &JsValue) -> Result<(HttpRequestOptions, HttpRequest), JsValue> {
+        let request_obj = if request.is_null() || request.is_undefined() {
+            None
+        } else {
+            Some(
+                request
+                    .dyn_ref::<Object>()
+                    .filter(|_| request.is_object())
+                    .ok_or_else(|| JsValue::from_str(&Error::Error("`request` is not a object !".to_string()).to_string()))?,
+            )
+        };
+
+        let mut errors = Vec::new();
+        let http_request = request_obj.map(|obj| options::parse_request(obj, &mut errors)).unwrap_or_default();
+        let options = match input::from_input(opts).map_err(|err| JsValue::from_str(&err.to_string()))? {
+            Some(options) => match request_obj {
+                Some(obj) => {
+                    options::check_fields(obj, &[OPTIONS_FIELDS, REQUEST_FIELDS], &mut errors);
+                    let overrides = options::parse_options(obj, &mut errors);
+                    let url = if overrides.url.is_empty() { options.url.clone() } else { overrides.url.clone() };
+                    HttpRequestOptions {
+                        url,
+                        ..HttpInstance::merge_options(&options, overrides)
+                    }
                 }
-            }
-
-            // redirect
-            let redirect = js_sys::Reflect::get(&obj, &JsValue::from_str("redirect")).ok();
-            if let Some(redirect) = redirect {
-                let redirect = Self::get_str(redirect);
-                if !redirect.is_empty() {
-                    http_request.redirect = Some(Redirect::get_redirect(redirect));
+                None => options,
+            },
+            None => {
+                let obj = opts.dyn_ref::<Object>().ok_or_else(|| JsValue::from_str(&Error::Error("`opts` is not a object !".to_string()).to_string()))?;
+                options::check_fields(obj, &[OPTIONS_FIELDS], &mut errors);
+                if let Some(obj) = request_obj {
+                    options::check_fields(obj, &[REQUEST_FIELDS], &mut errors);
                 }
-            }
 
-            // referrer
-            let referrer = js_sys::Reflect::get(&obj, &JsValue::from_str("referrer")).ok();
-            if let Some(referrer) = referrer {
-                let referrer = Self::get_str(referrer);
-                let referrer = referrer.trim();
-                if !referrer.is_empty() {
-                    http_request.referrer = Some(referrer.to_string());
+                let options = options::parse_options(obj, &mut errors);
+                if options.url.trim().is_empty() && !errors.iter().any(|err| err.starts_with("`url`")) {
+                    errors.push("`url`: is empty".to_string());
                 }
-            }
 
-            // referrer policy
-            let referrer_policy = js_sys::Reflect::get(&obj, &JsValue::from_str("referrerPolicy")).ok();
-            if let Some(referrer_policy) = referrer_policy {
-                let referrer_policy = Self::get_str(referrer_policy);
-                if !referrer_policy.is_empty() {
-                    http_request.referrer_policy = Some(ReferrerPolicy::get_referrer_policy(referrer_policy));
-                }
+                options
             }
-        }
+        };
 
-        Ok(http_request)
+        options::finish((options, http_request), errors)
     }
 
     pub async fn send(opts: JsValue, request: JsValue) -> Result<JsValue, JsValue> {
@@ -211,31 +114,106 @@ impl HttpClient {
             return Err(JsValue::from_str(&Error::Error("`opts` is not a object !".to_string()).to_string()));
         }
 
-        let options = Self::get_http_options(opts)?;
-        let request = Self::get_request_options(request)?;
+        let (mut options, request) = Self::get_options(&opts, &request)?;
+        Self::resolve_idempotency_key(&mut options);
+        Self::execute(options, request).await
+    }
+
+    /// `beacon` 模式发送请求, 不等待响应, 返回请求是否会在页面卸载后继续发送
+    pub fn beacon(opts: JsValue, request: JsValue) -> Result<bool, JsValue> {
+        let (mut options, request) = Self::get_options(&opts, &request)?;
+        Self::resolve_idempotency_key(&mut options);
+        beacon::send(&options, request).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// 分页请求, 返回异步迭代器
+    pub fn paginate(opts: JsValue, request: JsValue) -> Result<Object, JsValue> {
+        let (options, request) = Self::get_options(&opts, &request)?;
+        Paginator::from_js(options, request, &opts).into_js()
+    }
+
+    /// 发送请求
+    pub(crate) async fn call(mut options: HttpRequestOptions, request: HttpRequest) -> Result<HttpResponseOptions, Error> {
+        // 录制 `HAR` 时由 `Client` 保存收到的原始响应
+        if har::is_recording() {
+            options.har_wire = Some(Wire::default());
+        }
 
         // client
-        let client = Client::new_with_request(options, request);
+        let client = Client::new_with_request(options.clone(), request);
+
+        // 故障注入, 未开启 `fault-injection` feature 时不做任何处理
+        #[cfg(feature = "fault-injection")]
+        let fault_layer = FaultLayer::new(&options);
+        #[cfg(not(feature = "fault-injection"))]
+        let fault_layer = Identity::new();
 
         // service
         let service = ServiceBuilder::new()
             // .buffer(SERVICE_BUFFER)
             // .concurrency_limit(CONCURRENCY_LIMIT)
             // .rate_limit(RATE_LIMIT, Duration::from_secs(1))
+            .layer(MetricsLayer::new(&options))
+            .layer(CircuitLayer::new(&options))
+            .layer(HarLayer::new(&options))
+            .layer(fault_layer)
             .service(client);
 
-        let request = Request::builder().body(Value::Null).map_err(|err| JsValue::from_str(&err.to_string()))?;
-        let response = service.oneshot(request).await.map_err(|err| JsValue::from_str(&err.to_string()))?;
-        let (_, mut http_response) = response.into_parts();
+        let request = Request::builder().body(Value::Null)?;
+        let response = service.oneshot(request).await?;
+        let (_, http_response) = response.into_parts();
+        Ok(http_response)
+    }
+
+    /// 确定幂等键并写入 `headers`, 在每个逻辑请求开始时调用一次, 之后的重试使用同一个键
+    pub(crate) fn resolve_idempotency_key(options: &mut HttpRequestOptions) {
+        let key = match &options.idempotency_key {
+            Some(key) => key.key(),
+            None => return,
+        };
+
+        let (method, _) = Call::prepare_method(options);
+        if !idempotency::is_unsafe_method(method) {
+            return;
+        }
+
+        let (headers, key) = idempotency::with_header(options.headers.take(), &key);
+        options.headers = Some(headers);
+        options.idempotency_key = Some(IdempotencyKey::Key(key));
+    }
+
+    /// 发送请求, 并把结果转换成 `JsValue`
+    pub(crate) async fn execute(mut options: HttpRequestOptions, request: HttpRequest) -> Result<JsValue, JsValue> {
+        options.raw_json = true;
+        let http_response = Self::call(options, request).await.map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Self::to_js_response(http_response)
+    }
+
+    /// 把响应转换成 `JsValue`
+    pub(crate) fn to_js_response(mut http_response: HttpResponseOptions) -> Result<JsValue, JsValue> {
         let body = http_response.body.clone();
         // 查看 body 中有没有大数字
 
         http_response.body = Self::convert_numbers(body);
         let result = serde_wasm_bindgen::to_value(&http_response).map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+        // 原始的 `JSON` 文本使用 `JSON.parse` 解析, 大数字的处理与 `convert_numbers` 相同
+        if let Some(raw) = &http_response.raw {
+            let body = json::parse(raw).map_err(|err| JsValue::from_str(&err.to_string()))?;
+            js_sys::Reflect::set(&result, &JsValue::from_str("body"), &body)?;
+        }
+
+        // `multipart` 响应中的文件
+        let body = js_sys::Reflect::get(&result, &JsValue::from_str("body"))?;
+        multipart::set_blobs(&body, &http_response.blobs)?;
+
+        // headers 使用 `ResponseHeaders`, 支持忽略大小写及同名多值
+        let headers = ResponseHeaders::new(http_response.headers, http_response.kind);
+        js_sys::Reflect::set(&result, &JsValue::from_str("headers"), &JsValue::from(headers))?;
         Ok(result)
     }
 
-    fn convert_numbers(value: Value) -> Value {
+    pub(crate) fn convert_numbers(value: Value) -> Value {
         match value {
             Value::Array(vec) => {
                 let vec = vec.iter().map(|v| Self::convert_numbers(v.clone())).collect();
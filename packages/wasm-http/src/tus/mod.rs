@@ -0,0 +1,530 @@
+/*!
+    可续传的大文件分块上传, 实现 `tus 1.0` 协议的 `creation`、`concatenation`、`termination` 扩展。
+    - `POST` 创建上传, `HEAD` 获取已上传的位置, `PATCH` 按块上传, `DELETE` 终止上传
+    - 使用 `Blob.slice` 分块, 不会把整个文件读入内存
+    - 上传地址保存在 `localStorage` 中, 页面刷新后可以继续上传
+    - 服务端支持 `concatenation` 时可以把文件拆分成多个部分并行上传, 最后合并
+*/
+
+pub mod storage;
+
+use crate::client::timer;
+use crate::error::Error;
+use crate::instance::{url, HttpInstance};
+use crate::request::idempotency;
+use crate::request::HttpRequest;
+use crate::send::HttpClient;
+use crate::tus::storage::StoredUpload;
+use crate::{HttpRequestOptions, HttpRequestType, HttpResponseOptions};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures::future::join_all;
+use http::HeaderMap;
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::Blob;
+
+/// 协议版本
+#[doc = "https://tus.io/protocols/resumable-upload#tus-resumable"]
+pub const TUS_VERSION: &str = "1.0.0";
+
+/// 默认的分块大小, 5M
+const CHUNK_SIZE: u64 = 5 * 1024 * 1024;
+
+/// `options` 中 `opts` 和 `request` 以外的字段
+const TUS_FIELDS: &[&str] = &["endpoint", "chunkSize", "parallelUploads", "metadata", "fingerprint", "storeUrl", "removeFingerprintOnSuccess", "retryDelays", "onProgress"];
+
+/// `Upload-Metadata`, 如 `filename d29ybGRfZG9taW5hdGlvbi5wZGY=,is_confidential`
+#[doc = "https://tus.io/protocols/resumable-upload#upload-metadata"]
+pub fn encode_metadata(metadata: &BTreeMap<String, String>) -> String {
+    metadata
+        .iter()
+        .map(|(key, value)| if value.is_empty() { key.clone() } else { format!("{} {}", key, STANDARD.encode(value.as_bytes())) })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// 把文件拆分成 `parts` 个部分, 返回每个部分的 `[start, end)`
+pub fn split_ranges(size: u64, parts: u32) -> Vec<(u64, u64)> {
+    let parts = (parts.max(1) as u64).min(size.max(1));
+    let part_size = size / parts;
+    (0..parts).map(|index| (index * part_size, if index == parts - 1 { size } else { (index + 1) * part_size })).collect()
+}
+
+/// 合并上传的 `Upload-Concat`
+#[doc = "https://tus.io/protocols/resumable-upload#concatenation"]
+pub fn concat_header(urls: &[String]) -> String {
+    format!("final;{}", urls.join(" "))
+}
+
+/// 响应头中的 `Upload-Offset`
+pub fn get_offset(headers: &HeaderMap) -> Option<u64> {
+    headers.get("upload-offset")?.to_str().ok()?.trim().parse::<u64>().ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct TusOptions {
+    pub endpoint: String,                    // 创建上传的地址
+    pub chunk_size: u64,                     // 分块大小
+    pub parallel: u32,                       // 并行上传的部分数, 需要服务端支持 `concatenation`
+    pub metadata: BTreeMap<String, String>,  // `Upload-Metadata`
+    pub fingerprint: Option<String>,         // 保存上传地址的 `key`, 默认根据文件名、大小、修改时间及 `endpoint` 生成
+    pub store_url: bool,                     // 是否把上传地址保存到 `localStorage`
+    pub remove_fingerprint_on_success: bool, // 上传成功后是否删除保存的上传地址
+    pub retry_delays: Vec<u32>,              // 分块上传失败后的重试间隔(毫秒)
+}
+
+impl Default for TusOptions {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            chunk_size: CHUNK_SIZE,
+            parallel: 1,
+            metadata: BTreeMap::new(),
+            fingerprint: None,
+            store_url: true,
+            remove_fingerprint_on_success: true,
+            retry_delays: vec![0, 1000, 3000, 5000],
+        }
+    }
+}
+
+#[derive(Default)]
+struct UploadState {
+    url: Option<String>, // 上传地址, 并行上传时为合并后的地址
+    parts: Vec<String>,  // 并行上传时每个部分的地址
+    uploaded: Vec<u64>,  // 每个部分已上传的大小
+    aborted: bool,
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct TusUpload {
+    file: Blob,
+    options: TusOptions,
+    http_options: HttpRequestOptions,
+    request: HttpRequest,
+    on_progress: Option<Function>,
+    state: Rc<RefCell<UploadState>>,
+}
+
+#[wasm_bindgen]
+impl TusUpload {
+    /**
+      创建上传, `options` 中需要包含 `endpoint`, 可以设置 `chunkSize`、`parallelUploads`、`metadata`、`fingerprint`、`storeUrl`、`removeFingerprintOnSuccess`、`retryDelays`、`onProgress`, 以及 `opts` 和 `request` 的字段
+    */
+    #[wasm_bindgen(constructor)]
+    pub fn new(file: Blob, options: JsValue) -> Result<TusUpload, JsValue> {
+        let (_, http_options, request) = HttpInstance::get_config(&options, TUS_FIELDS)?;
+        let obj = options.dyn_ref::<Object>().ok_or_else(|| JsValue::from_str(&Error::Error("`options` is not a object !".to_string()).to_string()))?;
+
+        let mut tus_options = TusOptions {
+            endpoint: HttpClient::get_value(obj, "endpoint").map(HttpClient::get_str).unwrap_or_default(),
+            ..Default::default()
+        };
+
+        if tus_options.endpoint.trim().is_empty() {
+            return Err(JsValue::from_str(&Error::Error("`endpoint` is empty !".to_string()).to_string()));
+        }
+
+        let number = |name: &str| HttpClient::get_value(obj, name).and_then(|value| value.as_f64()).map(|value| value.max(0.0));
+        if let Some(chunk_size) = number("chunkSize").filter(|chunk_size| *chunk_size >= 1.0) {
+            tus_options.chunk_size = chunk_size as u64;
+        }
+
+        if let Some(parallel) = number("parallelUploads") {
+            tus_options.parallel = (parallel as u32).max(1);
+        }
+
+        if let Some(metadata) = HttpClient::get_value(obj, "metadata") {
+            tus_options.metadata = serde_wasm_bindgen::from_value(metadata).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        }
+
+        tus_options.fingerprint = HttpClient::get_value(obj, "fingerprint").map(HttpClient::get_str);
+        if let Some(store_url) = HttpClient::get_value(obj, "storeUrl") {
+            tus_options.store_url = store_url.is_truthy();
+        }
+
+        if let Some(remove) = HttpClient::get_value(obj, "removeFingerprintOnSuccess") {
+            tus_options.remove_fingerprint_on_success = remove.is_truthy();
+        }
+
+        if let Some(retry_delays) = HttpClient::get_value(obj, "retryDelays") {
+            tus_options.retry_delays = Array::from(&retry_delays).iter().filter_map(|delay| delay.as_f64()).map(|delay| delay.max(0.0) as u32).collect();
+        }
+
+        let mut upload = TusUpload::from_options(file, tus_options, http_options);
+        upload.request = request;
+        upload.on_progress = HttpClient::get_value(obj, "onProgress").and_then(|on_progress| on_progress.dyn_into::<Function>().ok());
+        Ok(upload)
+    }
+
+    /**
+      上传地址, 创建上传之前为 `undefined`
+    */
+    #[wasm_bindgen(getter)]
+    pub fn url(&self) -> Option<String> {
+        self.state.borrow().url.clone()
+    }
+
+    /**
+      开始或继续上传, 返回上传地址
+    */
+    #[wasm_bindgen(js_name = start)]
+    pub fn start_js(&self) -> Promise {
+        let upload = self.clone();
+        future_to_promise(async move { upload.start().await.map(|url| JsValue::from_str(&url)).map_err(|err| JsValue::from_str(&err.to_string())) })
+    }
+
+    /**
+      暂停上传, 当前分块完成后停止, 之后可以再次调用 `start` 继续
+    */
+    pub fn abort(&self) {
+        self.state.borrow_mut().aborted = true;
+    }
+
+    /**
+      终止上传, 删除服务端的上传及保存的上传地址
+    */
+    #[wasm_bindgen(js_name = terminate)]
+    pub fn terminate_js(&self) -> Promise {
+        let upload = self.clone();
+        future_to_promise(async move {
+            upload.terminate().await.map_err(|err| JsValue::from_str(&err.to_string()))?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+}
+
+impl TusUpload {
+    pub fn from_options(file: Blob, options: TusOptions, http_options: HttpRequestOptions) -> Self {
+        Self {
+            file,
+            options,
+            http_options,
+            request: HttpRequest::default(),
+            on_progress: None,
+            state: Rc::new(RefCell::new(UploadState::default())),
+        }
+    }
+
+    /// 开始或继续上传, 返回上传地址
+    pub async fn start(&self) -> Result<String, Error> {
+        self.state.borrow_mut().aborted = false;
+
+        let size = self.file.size() as u64;
+        let url = if self.options.parallel > 1 && size > 1 && self.supports_concatenation().await {
+            self.start_parallel(size).await?
+        } else {
+            self.start_single(size).await?
+        };
+
+        if self.options.remove_fingerprint_on_success {
+            storage::remove(&self.fingerprint());
+        }
+
+        Ok(url)
+    }
+
+    /// 终止上传
+    pub async fn terminate(&self) -> Result<(), Error> {
+        let urls: Vec<String> = {
+            let mut state = self.state.borrow_mut();
+            state.aborted = true;
+            state.url.iter().chain(state.parts.iter()).cloned().collect()
+        };
+
+        storage::remove(&self.fingerprint());
+        for url in urls {
+            let response = self.send("DELETE", &url, Vec::new(), None).await?;
+            // 已经不存在的上传视为终止成功
+            if !response.ok && response.status_code != 404 && response.status_code != 410 {
+                return Err(Error::Error(format!("tus: unexpected status {} when terminating upload !", response.status_code)));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn start_single(&self, size: u64) -> Result<String, Error> {
+        let fingerprint = self.fingerprint();
+        let stored = self.stored().and_then(|stored| stored.url);
+
+        // 继续之前的上传, 失败时重新创建
+        let resumed = match stored {
+            Some(url) => self.head(&url).await.ok().map(|offset| (url, offset)),
+            None => None,
+        };
+
+        let (url, offset) = match resumed {
+            Some(resumed) => resumed,
+            None => {
+                let url = self.create(Some(size), None, true, "create").await?;
+                if self.options.store_url {
+                    storage::set(
+                        &fingerprint,
+                        &StoredUpload {
+                            url: Some(url.clone()),
+                            parts: Vec::new(),
+                            size,
+                        },
+                    );
+                }
+                (url, 0)
+            }
+        };
+
+        self.state.borrow_mut().url = Some(url.clone());
+        self.upload_range(0, &url, 0, size, offset).await?;
+        Ok(url)
+    }
+
+    async fn start_parallel(&self, size: u64) -> Result<String, Error> {
+        let fingerprint = self.fingerprint();
+        let ranges = split_ranges(size, self.options.parallel);
+
+        // 继续之前的上传, 任何一个部分失效时全部重新创建
+        let mut parts: Vec<(String, u64)> = Vec::new();
+        if let Some(stored) = self.stored().filter(|stored| stored.parts.len() == ranges.len()) {
+            for url in stored.parts {
+                match self.head(&url).await {
+                    Ok(offset) => parts.push((url, offset)),
+                    Err(_) => {
+                        parts.clear();
+                        break;
+                    }
+                }
+            }
+        }
+
+        if parts.is_empty() {
+            for (index, (start, end)) in ranges.iter().enumerate() {
+                parts.push((self.create(Some(end - start), Some(String::from("partial")), false, &format!("create-{}", index)).await?, 0));
+            }
+
+            if self.options.store_url {
+                storage::set(
+                    &fingerprint,
+                    &StoredUpload {
+                        url: None,
+                        parts: parts.iter().map(|(url, _)| url.clone()).collect(),
+                        size,
+                    },
+                );
+            }
+        }
+
+        self.state.borrow_mut().parts = parts.iter().map(|(url, _)| url.clone()).collect();
+        let results = join_all(ranges.iter().zip(parts.iter()).enumerate().map(|(index, ((start, end), (url, offset)))| self.upload_range(index, url, *start, *end, *offset))).await;
+        results.into_iter().collect::<Result<Vec<()>, Error>>()?;
+
+        // 合并
+        let urls: Vec<String> = parts.into_iter().map(|(url, _)| url).collect();
+        let url = self.create(None, Some(concat_header(&urls)), true, "concat").await?;
+        self.state.borrow_mut().url = Some(url.clone());
+        Ok(url)
+    }
+
+    /// 从 `offset` 开始上传文件的 `[start, end)` 部分, 失败时按照 `retry_delays` 重试
+    async fn upload_range(&self, index: usize, url: &str, start: u64, end: u64, offset: u64) -> Result<(), Error> {
+        let length = end - start;
+        let mut offset = offset;
+        let mut attempt = 0;
+        let mut chunk_key: Option<(u64, String)> = None; // 当前块的幂等键, 重试同一块时沿用
+        self.progress(index, offset);
+
+        while offset < length {
+            if self.state.borrow().aborted {
+                return Err(Error::Error("tus: upload aborted !".to_string()));
+            }
+
+            let chunk_end = (offset + self.options.chunk_size).min(length);
+            let chunk = self.file.slice_with_f64_and_f64((start + offset) as f64, (start + chunk_end) as f64).map_err(Error::js_error)?;
+
+            if let Some(key) = &self.http_options.idempotency_key {
+                if chunk_key.as_ref().map(|(at, _)| *at != offset).unwrap_or(true) {
+                    chunk_key = Some((offset, key.derive(&format!("{}-{}", index, offset))));
+                }
+            }
+
+            match self.patch(url, offset, chunk, chunk_key.as_ref().map(|(_, key)| key.clone())).await {
+                Ok(next) => {
+                    offset = next;
+                    attempt = 0;
+                    self.progress(index, offset);
+                }
+                Err(err) => {
+                    let delay = match self.options.retry_delays.get(attempt) {
+                        Some(delay) => *delay,
+                        None => return Err(err),
+                    };
+
+                    attempt += 1;
+                    timer::sleep(delay).await;
+
+                    // 重试前重新获取已上传的位置
+                    if let Ok(next) = self.head(url).await {
+                        offset = next;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 创建上传, 返回上传地址
+    async fn create(&self, length: Option<u64>, concat: Option<String>, with_metadata: bool, key_suffix: &str) -> Result<String, Error> {
+        let mut headers: Vec<(&str, String)> = Vec::new();
+        if let Some(key) = &self.http_options.idempotency_key {
+            headers.push((idempotency::HEADER, key.derive(key_suffix)));
+        }
+
+        if let Some(length) = length {
+            headers.push(("Upload-Length", length.to_string()));
+        }
+
+        if let Some(concat) = concat {
+            headers.push(("Upload-Concat", concat));
+        }
+
+        if with_metadata && !self.options.metadata.is_empty() {
+            headers.push(("Upload-Metadata", encode_metadata(&self.options.metadata)));
+        }
+
+        let response = self.send("POST", &self.options.endpoint, headers, None).await?;
+        if response.status_code != 201 {
+            return Err(Error::Error(format!("tus: unexpected status {} when creating upload !", response.status_code)));
+        }
+
+        let location = response
+            .headers
+            .get("location")
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| Error::Error("tus: missing `Location` header when creating upload !".to_string()))?;
+        let base = if response.url.is_empty() { &self.options.endpoint } else { &response.url };
+        Ok(url::resolve(base, location))
+    }
+
+    /// 获取已上传的位置
+    async fn head(&self, url: &str) -> Result<u64, Error> {
+        let response = self.send("HEAD", url, vec![("Cache-Control", String::from("no-store"))], None).await?;
+        if !response.ok {
+            return Err(Error::Error(format!("tus: unexpected status {} when resuming upload !", response.status_code)));
+        }
+
+        get_offset(&response.headers).ok_or_else(|| Error::Error("tus: missing `Upload-Offset` header when resuming upload !".to_string()))
+    }
+
+    /// 上传一块, 返回新的位置
+    async fn patch(&self, url: &str, offset: u64, chunk: Blob, key: Option<String>) -> Result<u64, Error> {
+        let mut headers = vec![("Upload-Offset", offset.to_string()), ("Content-Type", String::from("application/offset+octet-stream"))];
+        if let Some(key) = key {
+            headers.push((idempotency::HEADER, key));
+        }
+
+        let response = self.send("PATCH", url, headers, Some(chunk)).await?;
+        if !response.ok {
+            return Err(Error::Error(format!("tus: unexpected status {} when uploading chunk !", response.status_code)));
+        }
+
+        get_offset(&response.headers).ok_or_else(|| Error::Error("tus: missing `Upload-Offset` header when uploading chunk !".to_string()))
+    }
+
+    /// 服务端是否支持 `concatenation`
+    async fn supports_concatenation(&self) -> bool {
+        match self.send("OPTIONS", &self.options.endpoint, Vec::new(), None).await {
+            Ok(response) => response
+                .headers
+                .get_all("tus-extension")
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .any(|value| value.split(',').any(|extension| extension.trim() == "concatenation")),
+            Err(_) => false,
+        }
+    }
+
+    async fn send(&self, method: &str, url: &str, headers: Vec<(&str, String)>, body: Option<Blob>) -> Result<HttpResponseOptions, Error> {
+        let mut options = self.http_options.clone();
+        options.url = url.to_string();
+        options.method = Some(method.to_string());
+        options.response_type = Some(HttpRequestType::Text);
+        options.schema = None;
+        // 分块已按 `Upload-Offset` 切分, 不能压缩; `tus` 的响应头中的摘要不对应文件内容
+        options.compress_request = None;
+        options.transform_request_keys = None;
+        options.verify_digest = None;
+        // 幂等键由 `create`、`upload_range` 按请求派生
+        options.idempotency_key = None;
+
+        let mut request_headers = match options.headers.take() {
+            Some(Value::Object(headers)) => headers,
+            _ => Map::new(),
+        };
+
+        for (name, value) in headers.into_iter().chain(std::iter::once(("Tus-Resumable", TUS_VERSION.to_string()))) {
+            request_headers.retain(|key, _| !key.eq_ignore_ascii_case(name));
+            request_headers.insert(name.to_string(), Value::String(value));
+        }
+
+        options.headers = Some(Value::Object(request_headers));
+        match body {
+            Some(body) => {
+                options.request_type = Some(HttpRequestType::Blob);
+                options.data = Some(body.into());
+            }
+            None => {
+                options.request_type = None;
+                options.data = None;
+            }
+        }
+
+        HttpClient::call(options, self.request.clone()).await
+    }
+
+    fn stored(&self) -> Option<StoredUpload> {
+        if !self.options.store_url {
+            return None;
+        }
+
+        storage::get(&self.fingerprint()).filter(|stored| stored.size == self.file.size() as u64)
+    }
+
+    /// 默认根据文件名、类型、大小、修改时间及 `endpoint` 生成
+    fn fingerprint(&self) -> String {
+        if let Some(fingerprint) = &self.options.fingerprint {
+            return fingerprint.clone();
+        }
+
+        let field = |name: &str| {
+            Reflect::get(&self.file, &JsValue::from_str(name))
+                .ok()
+                .filter(|value| !value.is_undefined())
+                .map(|value| value.as_string().unwrap_or_else(|| value.as_f64().map(|value| value.to_string()).unwrap_or_default()))
+                .unwrap_or_default()
+        };
+        format!("{}-{}-{}-{}-{}", field("name"), self.file.type_(), self.file.size(), field("lastModified"), self.options.endpoint)
+    }
+
+    /// 更新第 `index` 个部分已上传的大小, 并回调 `onProgress(uploaded, total)`
+    fn progress(&self, index: usize, uploaded: u64) {
+        let total_uploaded: u64 = {
+            let mut state = self.state.borrow_mut();
+            if state.uploaded.len() <= index {
+                state.uploaded.resize(index + 1, 0);
+            }
+
+            state.uploaded[index] = uploaded;
+            state.uploaded.iter().sum()
+        };
+
+        if let Some(on_progress) = &self.on_progress {
+            let _ = on_progress.call2(&JsValue::NULL, &JsValue::from_f64(total_uploaded as f64), &JsValue::from_f64(self.file.size()));
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! 上传地址的持久化, 保存在 `localStorage` 中, 页面刷新后可以继续上传。`Worker` 中没有 `localStorage`, 此时不保存。
+
+use serde::{Deserialize, Serialize};
+use web_sys::Storage;
+
+/// `localStorage` 中 `key` 的前缀
+const KEY_PREFIX: &str = "tus::";
+
+/// 保存的上传
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredUpload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>, // 单个上传的地址
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<String>, // 并行上传时每个部分的地址
+    pub size: u64,
+}
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+pub(crate) fn get(fingerprint: &str) -> Option<StoredUpload> {
+    let value = local_storage()?.get_item(&format!("{}{}", KEY_PREFIX, fingerprint)).ok()??;
+    serde_json::from_str(&value).ok()
+}
+
+pub(crate) fn set(fingerprint: &str, upload: &StoredUpload) {
+    if let (Some(storage), Ok(value)) = (local_storage(), serde_json::to_string(upload)) {
+        let _ = storage.set_item(&format!("{}{}", KEY_PREFIX, fingerprint), &value);
+    }
+}
+
+pub(crate) fn remove(fingerprint: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(&format!("{}{}", KEY_PREFIX, fingerprint));
+    }
+}
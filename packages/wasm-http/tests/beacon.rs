@@ -0,0 +1,60 @@
+//! `beacon` 模式压缩请求 `body`, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --features rust-compression --test beacon`
+
+#![cfg(all(target_arch = "wasm32", feature = "rust-compression"))]
+
+use flate2::read::GzDecoder;
+use js_sys::{Promise, Uint8Array, JSON};
+use std::io::Read;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.fetch = async (request, init) => {
+        globalThis.__sent = {
+            keepalive: init?.keepalive ?? request.keepalive,
+            encoding: request.headers.get("content-encoding"),
+            body: new Uint8Array(await request.arrayBuffer()),
+        };
+        return new Response(null, { status: 204 });
+    };
+}
+export function keepalive() { return globalThis.__sent.keepalive; }
+export function encoding() { return globalThis.__sent.encoding; }
+export function body() { return globalThis.__sent.body; }
+export function blob() { return new Blob(["a".repeat(100)]); }
+export function sleep(ms) { return new Promise((resolve) => setTimeout(resolve, ms)); }
+"#)]
+extern "C" {
+    fn install();
+    fn keepalive() -> bool;
+    fn encoding() -> Option<String>;
+    fn body() -> Uint8Array;
+    fn blob() -> JsValue;
+    fn sleep(ms: u32) -> Promise;
+}
+
+#[wasm_bindgen_test]
+async fn test_compress() {
+    install();
+    let data = "a".repeat(100);
+    let opts = JSON::parse(r#"{"url": "http://localhost/log", "method": "post", "type": 4, "compressRequest": "gzip", "compressThreshold": 10}"#).unwrap();
+    js_sys::Reflect::set(&opts, &JsValue::from_str("data"), &JsValue::from_str(&data)).unwrap();
+
+    // 压缩后使用 `keepalive` 的 `fetch`
+    assert!(wasm_http::beacon(opts, JsValue::UNDEFINED).unwrap());
+    JsFuture::from(sleep(10)).await.unwrap();
+    assert!(keepalive());
+    assert_eq!(encoding().as_deref(), Some("gzip"));
+
+    let mut result = String::new();
+    GzDecoder::new(body().to_vec().as_slice()).read_to_string(&mut result).unwrap();
+    assert_eq!(result, data);
+
+    // `Blob` 无法同步读取
+    let opts = JSON::parse(r#"{"url": "http://localhost/log", "method": "post", "type": 3, "compressRequest": "gzip", "compressThreshold": 10}"#).unwrap();
+    js_sys::Reflect::set(&opts, &JsValue::from_str("data"), &blob()).unwrap();
+    let err = wasm_http::beacon(opts, JsValue::UNDEFINED).unwrap_err().as_string().unwrap();
+    assert!(err.contains("can not compress `Blob` body"), "{}", err);
+}
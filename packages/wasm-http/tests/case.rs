@@ -0,0 +1,55 @@
+use serde_json::json;
+use wasm_http::case::{split_words, Case, KeyTransform};
+
+#[test]
+fn test_convert() {
+    assert_eq!(split_words("userID"), vec!["user", "id"]);
+    assert_eq!(split_words("HTTPServer_url"), vec!["http", "server", "url"]);
+    assert_eq!(Case::Snake.convert("userID"), "user_id");
+    assert_eq!(Case::Snake.convert("parseHTMLString"), "parse_html_string");
+    assert_eq!(Case::Camel.convert("user_id"), "userId");
+    assert_eq!(Case::Camel.convert("created-at"), "createdAt");
+    assert_eq!(Case::Pascal.convert("user_name"), "UserName");
+    assert_eq!(Case::Kebab.convert("pageSize2"), "page-size2");
+    assert_eq!(Case::Snake.convert("v2Items"), "v2_items");
+    assert_eq!(Case::Camel.convert("_"), "_");
+    assert_eq!(Case::Camel.convert("_id"), "_id");
+    assert_eq!(Case::Camel.convert("__typename"), "__typename");
+    assert_eq!(Case::Camel.convert("_user_id_"), "_userId_");
+    assert_eq!(Case::Snake.convert("-pageSize"), "-page_size");
+    assert_eq!(Case::get_case(String::from(" Snake ")), Some(Case::Snake));
+    assert_eq!(Case::get_case(String::from("upper")), None);
+}
+
+#[test]
+fn test_transform() {
+    let transform = KeyTransform::new(Case::Camel, vec![String::from("metadata"), String::from("data.raw_payload")]);
+    let value = json!({
+        "user_id": 1,
+        "data": {"raw_payload": {"keep_me": true}, "items": [{"item_name": "a"}]},
+        "metadata": {"x_y": 1},
+        "list": [[{"a_b": 1}]]
+    });
+
+    assert_eq!(
+        transform.transform(value),
+        json!({
+            "userId": 1,
+            "data": {"raw_payload": {"keep_me": true}, "items": [{"itemName": "a"}]},
+            "metadata": {"x_y": 1},
+            "list": [[{"aB": 1}]]
+        })
+    );
+
+    let transform = KeyTransform::new(Case::Snake, vec![String::from("sessionKey")]);
+    assert_eq!(transform.transform_query("https://example.org/users?pageSize=10&sortBy=createdAt&sessionKey=x&flag#userID"), "https://example.org/users?page_size=10&sort_by=createdAt&sessionKey=x&flag#userID");
+    assert_eq!(transform.transform_query("/users"), "/users");
+}
+
+#[test]
+fn test_collision() {
+    let transform = KeyTransform::new(Case::Camel, vec![]);
+    assert_eq!(transform.transform(json!({"_id": 1, "id": 2})), json!({"_id": 1, "id": 2}));
+    assert_eq!(transform.transform(json!({"user_id": 1, "userId": 2})), json!({"user_id": 1, "userId": 2}));
+    assert_eq!(transform.transform(json!({"user_id": 1, "user-id": 2})), json!({"user_id": 1, "userId": 2}));
+}
@@ -0,0 +1,40 @@
+use wasm_http::response::charset::{decode_text, get_charset, get_encoding};
+
+#[test]
+fn test_get_charset() {
+    assert_eq!(get_charset("text/html; charset=GBK"), Some("GBK".to_string()));
+    assert_eq!(get_charset("text/html;Charset=\"gb18030\""), Some("gb18030".to_string()));
+    assert_eq!(get_charset("application/json"), None);
+}
+
+#[test]
+fn test_get_encoding() {
+    assert_eq!(get_encoding(b"", Some("text/html; charset=gb2312"), None).unwrap().name(), "GBK");
+    assert_eq!(get_encoding(b"", Some("text/html; charset=x-unknown"), None).unwrap().name(), "UTF-8");
+    assert_eq!(get_encoding(b"", Some("text/html; charset=gbk"), Some("big5")).unwrap().name(), "Big5");
+    assert_eq!(get_encoding(&[0xFF, 0xFE, 0x41], Some("text/html; charset=gbk"), Some("big5")).unwrap().name(), "UTF-16LE");
+    assert!(get_encoding(b"", None, Some("x-unknown")).is_err());
+}
+
+#[test]
+fn test_decode_text() {
+    // `中文` 的 GBK 编码
+    let gbk = [0xD6, 0xD0, 0xCE, 0xC4];
+    assert_eq!(decode_text(&gbk, Some("text/html; charset=GBK"), None).unwrap(), "中文");
+    assert_eq!(decode_text(&gbk, Some("text/html; charset=GB18030"), None).unwrap(), "中文");
+
+    // `中文` 的 Big5 编码
+    let big5 = [0xA4, 0xA4, 0xA4, 0xE5];
+    assert_eq!(decode_text(&big5, None, Some("big5")).unwrap(), "中文");
+
+    // `テスト` 的 Shift_JIS 编码
+    let shift_jis = [0x83, 0x65, 0x83, 0x58, 0x83, 0x67];
+    assert_eq!(decode_text(&shift_jis, Some("text/plain; charset=Shift_JIS"), None).unwrap(), "テスト");
+
+    // 带 BOM 的 UTF-16
+    let utf16 = [0xFF, 0xFE, 0x2D, 0x4E, 0x87, 0x65];
+    assert_eq!(decode_text(&utf16, Some("text/plain; charset=utf-8"), None).unwrap(), "中文");
+
+    // 带 BOM 的 UTF-8
+    assert_eq!(decode_text("\u{FEFF}中文".as_bytes(), None, None).unwrap(), "中文");
+}
@@ -0,0 +1,82 @@
+use wasm_http::circuit::{CircuitBreaker, CircuitKey, CircuitOptions, CircuitState, Transition};
+use wasm_http::Error;
+
+fn options() -> CircuitOptions {
+    CircuitOptions {
+        failure_threshold: 3,
+        failure_rate: 0.5,
+        minimum_requests: 4,
+        window_size: 4,
+        cool_down: 1000.0,
+        half_open_requests: 1,
+        key: CircuitKey::Host,
+    }
+}
+
+#[test]
+fn test_key() {
+    assert_eq!(CircuitKey::Host.key("HTTPS://API.example.org:8443/users/1?x=1"), "https://api.example.org:8443");
+    assert_eq!(CircuitKey::Host.key("/api/users"), "same-origin");
+    assert_eq!(CircuitKey::Route(Vec::new()).key("https://api.example.org/users/1?x=1"), "https://api.example.org/users/:id");
+}
+
+#[test]
+fn test_consecutive_failures() {
+    let key = "https://api.example.org";
+    let mut breaker = CircuitBreaker::new(options());
+
+    for _ in 0..2 {
+        assert!(breaker.acquire(key, 0.0).0.is_ok());
+        assert_eq!(breaker.report(key, true, 0.0), None);
+    }
+
+    assert!(breaker.acquire(key, 0.0).0.is_ok());
+    let transition = breaker.report(key, true, 100.0);
+    assert_eq!(
+        transition,
+        Some(Transition {
+            key: key.to_string(),
+            from: CircuitState::Closed,
+            to: CircuitState::Open
+        })
+    );
+
+    // 冷却中
+    assert_eq!(breaker.acquire(key, 600.0).0, Err(500.0));
+    let err = Error::CircuitOpen { key: key.to_string(), retry_after: 500.0 };
+    assert_eq!(err.to_string(), "circuit open for `https://api.example.org`, retry after 500ms");
+
+    // 冷却结束, 只放行一个探测请求
+    let (result, transition) = breaker.acquire(key, 1100.0);
+    assert!(result.is_ok());
+    assert_eq!(transition.map(|transition| transition.to), Some(CircuitState::HalfOpen));
+    assert_eq!(breaker.acquire(key, 1100.0).0, Err(1000.0));
+
+    // 探测请求不计入统计时归还名额
+    breaker.release(key);
+    assert!(breaker.acquire(key, 1150.0).0.is_ok());
+    assert_eq!(breaker.state(key), CircuitState::HalfOpen);
+
+    // 探测失败, 重新熔断
+    assert_eq!(breaker.report(key, true, 1200.0).map(|transition| transition.to), Some(CircuitState::Open));
+    assert!(breaker.acquire(key, 2100.0).0.is_err());
+
+    // 探测成功, 恢复
+    assert!(breaker.acquire(key, 2200.0).0.is_ok());
+    assert_eq!(breaker.report(key, false, 2300.0).map(|transition| (transition.from, transition.to)), Some((CircuitState::HalfOpen, CircuitState::Closed)));
+    assert_eq!(breaker.state(key), CircuitState::Closed);
+}
+
+#[test]
+fn test_failure_rate() {
+    let key = "https://api.example.org";
+    let mut breaker = CircuitBreaker::new(options());
+    assert_eq!(breaker.report(key, true, 0.0), None);
+    assert_eq!(breaker.report(key, false, 0.0), None);
+    assert_eq!(breaker.report(key, true, 0.0), None);
+    assert_eq!(breaker.report(key, false, 0.0).map(|transition| transition.to), Some(CircuitState::Open));
+
+    // 其他分组不受影响
+    assert_eq!(breaker.state("https://cdn.example.org"), CircuitState::Closed);
+    assert_eq!(breaker.states().get(key), Some(&CircuitState::Open));
+}
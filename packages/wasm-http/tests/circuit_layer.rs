@@ -0,0 +1,98 @@
+//! 熔断器统计的请求结果, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test circuit_layer`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Promise, JSON};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::circuit::{get_circuit_states, set_circuit_breaker};
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.__responses = [];
+    globalThis.fetch = async (request, init) => {
+        const next = globalThis.__responses.shift();
+        if (next === "network") {
+            throw new TypeError("Failed to fetch");
+        }
+        // 不返回响应, 直到请求被取消
+        if (next === "hang") {
+            return new Promise((_, reject) => {
+                const abort = () => reject(init.signal.reason);
+                init.signal.aborted ? abort() : init.signal.addEventListener("abort", abort);
+            });
+        }
+        return new Response(next, { headers: { "Content-Type": "application/json" } });
+    };
+}
+export function respond(next) { globalThis.__responses.push(next); }
+export function aborted() {
+    const controller = new AbortController();
+    controller.abort();
+    return controller.signal;
+}
+export function sleep(ms) { return new Promise((resolve) => setTimeout(resolve, ms)); }
+"#)]
+extern "C" {
+    fn install();
+    fn respond(next: &str);
+    fn aborted() -> JsValue;
+    fn sleep(ms: u32) -> Promise;
+}
+
+async fn send() -> Result<JsValue, JsValue> {
+    let opts = JSON::parse(r#"{"url": "http://localhost/users", "method": "get", "schema": {"type": "object", "required": ["id"]}}"#).unwrap();
+    wasm_http::send(opts, JsValue::UNDEFINED).await
+}
+
+fn state() -> serde_json::Value {
+    serde_wasm_bindgen::from_value(get_circuit_states().unwrap()).unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn test_failures() {
+    install();
+    set_circuit_breaker(JSON::parse(r#"{"failureThreshold": 1, "coolDown": 20}"#).unwrap()).unwrap();
+
+    // 网络错误计为失败
+    respond("network");
+    assert!(send().await.is_err());
+    assert_eq!(state(), json!({"http://localhost": "open"}));
+    JsFuture::from(sleep(30)).await.unwrap();
+
+    // 探测请求的响应校验失败, 不计入统计, 归还探测名额
+    respond(r#"{"name": "a"}"#);
+    let err = send().await.unwrap_err().as_string().unwrap();
+    assert!(err.starts_with("response validation failed"), "{}", err);
+    assert_eq!(state(), json!({"http://localhost": "half-open"}));
+
+    respond(r#"{"id": 1}"#);
+    assert!(send().await.is_ok());
+    assert_eq!(state(), json!({"http://localhost": "closed"}));
+    set_circuit_breaker(JsValue::NULL).unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_timeout() {
+    install();
+    set_circuit_breaker(JSON::parse(r#"{"failureThreshold": 1, "coolDown": 20}"#).unwrap()).unwrap();
+
+    // 调用方取消请求不计入统计
+    respond("hang");
+    let request = JSON::parse("{}").unwrap();
+    js_sys::Reflect::set(&request, &JsValue::from_str("signal"), &aborted()).unwrap();
+    let opts = JSON::parse(r#"{"url": "http://localhost/users", "method": "get"}"#).unwrap();
+    let err = wasm_http::send(opts, request).await.unwrap_err().as_string().unwrap();
+    assert!(err.starts_with("js api error: AbortError"), "{}", err);
+    assert_eq!(state(), json!({"http://localhost": "closed"}));
+
+    // 超时计为失败
+    respond("hang");
+    let opts = JSON::parse(r#"{"url": "http://localhost/users", "method": "get", "timeout": 1}"#).unwrap();
+    let err = wasm_http::send(opts, JsValue::UNDEFINED).await.unwrap_err().as_string().unwrap();
+    assert_eq!(err, "request timeout");
+    assert_eq!(state(), json!({"http://localhost": "open"}));
+    set_circuit_breaker(JsValue::NULL).unwrap();
+}
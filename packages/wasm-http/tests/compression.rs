@@ -0,0 +1,36 @@
+#[cfg(feature = "rust-compression")]
+use flate2::read::{GzDecoder, ZlibDecoder};
+#[cfg(feature = "rust-compression")]
+use std::io::Read;
+#[cfg(feature = "rust-compression")]
+use wasm_http::compression::encode;
+use wasm_http::compression::Compression;
+
+#[test]
+fn test_get_compression() {
+    assert_eq!(Compression::get_compression(String::from(" GZIP ")), Some(Compression::Gzip));
+    assert_eq!(Compression::get_compression(String::from("deflate")), Some(Compression::Deflate));
+    assert_eq!(Compression::get_compression(String::from("br")), Some(Compression::Br));
+    assert_eq!(Compression::get_compression(String::from("zstd")), None);
+    assert_eq!(Compression::Br.encoding(), "br");
+}
+
+#[test]
+#[cfg(feature = "rust-compression")]
+fn test_encode() {
+    let body = r#"{"event":"page_view","path":"/home"}"#.repeat(100);
+
+    let mut result = String::new();
+    GzDecoder::new(encode(body.as_bytes(), Compression::Gzip).unwrap().as_slice()).read_to_string(&mut result).unwrap();
+    assert_eq!(result, body);
+
+    let mut result = String::new();
+    ZlibDecoder::new(encode(body.as_bytes(), Compression::Deflate).unwrap().as_slice()).read_to_string(&mut result).unwrap();
+    assert_eq!(result, body);
+
+    let compressed = encode(body.as_bytes(), Compression::Br).unwrap();
+    assert!(compressed.len() < body.len());
+    let mut result = String::new();
+    brotli::Decompressor::new(compressed.as_slice(), 4096).read_to_string(&mut result).unwrap();
+    assert_eq!(result, body);
+}
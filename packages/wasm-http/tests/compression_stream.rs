@@ -0,0 +1,49 @@
+//! 请求 `body` 压缩, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test compression_stream`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::JSON;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.fetch = async (request) => {
+        globalThis.__sent = { encoding: request.headers.get("content-encoding"), size: (await request.arrayBuffer()).byteLength };
+        return new Response("{}", { headers: { "Content-Type": "application/json" } });
+    };
+}
+export function encoding() { return globalThis.__sent.encoding; }
+export function size() { return globalThis.__sent.size; }
+"#)]
+extern "C" {
+    fn install();
+    fn encoding() -> Option<String>;
+    fn size() -> u32;
+}
+
+async fn send(compression: &str) {
+    let opts = JSON::parse(&format!(r#"{{"url": "http://localhost/log", "method": "post", "type": 4, "compressRequest": "{}"}}"#, compression)).unwrap();
+    js_sys::Reflect::set(&opts, &JsValue::from_str("data"), &JsValue::from_str(&"a".repeat(2048))).unwrap();
+    wasm_http::send(opts, JsValue::UNDEFINED).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_compression_stream() {
+    install();
+
+    // `CompressionStream` 支持 `gzip`
+    send("gzip").await;
+    assert_eq!(encoding().as_deref(), Some("gzip"));
+    assert!(size() < 2048);
+
+    // `node` 的 `CompressionStream` 不支持 `br`, 未开启 `rust-compression` feature 时按原样发送
+    send("br").await;
+    if cfg!(feature = "rust-compression") {
+        assert_eq!(encoding().as_deref(), Some("br"));
+        assert!(size() < 2048);
+    } else {
+        assert_eq!(encoding(), None);
+        assert_eq!(size(), 2048);
+    }
+}
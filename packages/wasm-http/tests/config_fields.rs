@@ -0,0 +1,52 @@
+//! `GraphQL`、`JSON-RPC`、`tus`、`Download` 的配置校验未知字段, 可以在 `node` 中运行: `wasm-pack test --node -- --test config_fields`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::JSON;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::download::Download;
+use wasm_http::graphql::GraphQLClient;
+use wasm_http::jsonrpc::JsonRpcClient;
+use wasm_http::tus::TusUpload;
+use web_sys::Blob;
+
+fn config(text: &str) -> JsValue {
+    JSON::parse(text).unwrap()
+}
+
+fn error(result: Result<impl Sized, JsValue>) -> String {
+    result.err().and_then(|err| err.as_string()).unwrap_or_default()
+}
+
+#[wasm_bindgen_test]
+fn test_known_fields() {
+    let url = String::from("http://localhost/api");
+    assert!(GraphQLClient::new(url.clone(), config(r#"{"headers": {"X-Id": "1"}, "mode": "cors", "persistedQueries": {}, "errorPolicy": "all"}"#)).is_ok());
+    assert!(JsonRpcClient::new(url.clone(), config(r#"{"timeout": 10, "batch": false, "batchWindow": 5, "maxBatchSize": 10}"#)).is_ok());
+    assert!(Download::new(url, config(r#"{"parallel": 2, "rangeSize": 5, "retryDelays": [], "credentials": "include"}"#)).is_ok());
+
+    let file = Blob::new().unwrap();
+    let options = config(r#"{"endpoint": "http://localhost/files/", "chunkSize": 5, "parallelUploads": 2, "metadata": {}, "retryDelays": [], "removeFingerprintOnSuccess": true}"#);
+    assert!(TusUpload::new(file, options).is_ok());
+}
+
+#[wasm_bindgen_test]
+async fn test_unknown_fields() {
+    let url = String::from("http://localhost/api");
+    assert_eq!(error(GraphQLClient::new(url.clone(), config(r#"{"errorPolcy": "all"}"#))), "invalid options: `errorPolcy`: unknown field");
+    assert_eq!(error(JsonRpcClient::new(url.clone(), config(r#"{"batchSize": 10}"#))), "invalid options: `batchSize`: unknown field");
+    assert_eq!(
+        error(Download::new(url.clone(), config(r#"{"rangesize": 5, "timeout": 1.5}"#))),
+        "invalid options: `rangesize`: unknown field; `timeout`: invalid number: 1.5"
+    );
+
+    let file = Blob::new().unwrap();
+    assert_eq!(error(TusUpload::new(file, config(r#"{"endpoint": "http://localhost/files/", "chunk": 5}"#))), "invalid options: `chunk`: unknown field");
+
+    // `query` 的 `opts`
+    let client = GraphQLClient::new(url, JsValue::UNDEFINED).unwrap();
+    let result = JsFuture::from(client.query(String::from("{ a }"), JsValue::UNDEFINED, config(r#"{"operation": "A"}"#))).await;
+    assert_eq!(error(result), "invalid options: `operation`: unknown field");
+}
@@ -0,0 +1,53 @@
+use wasm_http::response::digest::{self, Algorithm};
+use wasm_http::Error;
+
+const HELLO_SHA256: &str = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+
+#[test]
+fn test_parse() {
+    // `Content-Digest`, 忽略不支持的算法
+    let hashes = digest::parse_header(&format!("md5=:XUFAKrxLKna5cZ2REBfFkg==:, sha-256=:{}:", HELLO_SHA256));
+    assert_eq!(hashes.len(), 1);
+    assert_eq!(hashes[0].algorithm, Algorithm::Sha256);
+
+    // `Digest`
+    assert_eq!(digest::parse_header(&format!("SHA-256={}", HELLO_SHA256)), hashes);
+
+    // SRI 及十六进制
+    assert_eq!(digest::parse_expected(&format!("sha256-{}", HELLO_SHA256)), Some(hashes.clone()));
+    assert_eq!(digest::parse_expected("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"), Some(hashes));
+
+    assert_eq!(digest::parse_expected("md5-XUFAKrxLKna5cZ2REBfFkg=="), None);
+    assert_eq!(digest::parse_expected("sha-256=:dG9vIHNob3J0:"), None);
+    assert_eq!(digest::parse_expected(""), None);
+
+    let headers = |name: &str| (name == "digest").then(|| format!("sha-256={}", HELLO_SHA256));
+    assert_eq!(digest::from_headers(&digest::HEADERS, headers).len(), 1);
+}
+
+#[test]
+fn test_verify() {
+    let hashes = digest::parse_expected(&format!("sha-256=:{}:", HELLO_SHA256)).unwrap();
+    assert!(digest::verify(b"hello", &hashes).is_ok());
+
+    let err = digest::verify(b"hello!", &hashes).unwrap_err();
+    assert!(matches!(err, Error::IntegrityMismatch(_)));
+    assert!(err.to_string().starts_with(&format!("integrity mismatch: sha-256 expected `{}`, got `", HELLO_SHA256)));
+
+    // 有多个摘要时都需要一致
+    let sha512 = Algorithm::Sha512.hash(b"other");
+    let mut hashes = hashes;
+    hashes.push(digest::Hash { algorithm: Algorithm::Sha512, value: sha512 });
+    assert!(digest::verify(b"hello", &hashes).is_err());
+    assert!(digest::verify(b"hello", &[]).is_err());
+}
+
+#[test]
+fn test_check_encoding() {
+    assert!(digest::check_encoding(None).is_ok());
+    assert!(digest::check_encoding(Some("identity")).is_ok());
+
+    let err = digest::check_encoding(Some("gzip")).unwrap_err();
+    assert!(matches!(err, Error::IntegrityMismatch(_)));
+    assert_eq!(err.to_string(), "integrity mismatch: cannot verify encoded content against digest headers, `Content-Encoding` is `gzip`");
+}
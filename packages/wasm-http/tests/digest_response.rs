@@ -0,0 +1,43 @@
+//! 响应的摘要校验, 需要 `Response`, 可以在 `node` 中运行: `wasm-pack test --node -- --test digest_response`
+
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::response::digest::{self, VerifyDigest};
+use wasm_http::Error;
+use web_sys::{Headers, Response, ResponseInit};
+
+const HELLO_SHA256: &str = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+
+fn response(headers: &[(&str, &str)]) -> Response {
+    let init = ResponseInit::new();
+    let response_headers = Headers::new().unwrap();
+    for (name, value) in headers {
+        response_headers.set(name, value).unwrap();
+    }
+    init.set_headers(&response_headers);
+    Response::new_with_opt_str_and_init(Some("hello"), &init).unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn test_verify_response() {
+    let digest_header = format!("sha-256=:{}:", HELLO_SHA256);
+
+    // 校验后 `body` 仍然可以读取
+    let verified = digest::verify_response(response(&[("content-digest", &digest_header)]), &VerifyDigest::Header).await.unwrap();
+    let text = JsFuture::from(verified.text().unwrap()).await.unwrap();
+    assert_eq!(text, JsValue::from_str("hello"));
+
+    // 编码后的响应无法按响应头校验
+    let err = digest::verify_response(response(&[("content-digest", &digest_header), ("content-encoding", "gzip")]), &VerifyDigest::Header).await.unwrap_err();
+    assert!(matches!(err, Error::IntegrityMismatch(ref message) if message.starts_with("cannot verify encoded content")));
+
+    // 调用方传入的摘要按解码后的 `body` 计算
+    let expected = VerifyDigest::Expected(digest::parse_expected(&digest_header).unwrap());
+    assert!(digest::verify_response(response(&[("content-encoding", "gzip")]), &expected).await.is_ok());
+
+    let err = digest::verify_response(response(&[]), &VerifyDigest::Header).await.unwrap_err();
+    assert!(matches!(err, Error::IntegrityMismatch(_)));
+}
@@ -0,0 +1,27 @@
+use wasm_http::download::{if_range_validator, parse_content_range, plan_ranges, range_header};
+
+#[test]
+fn test_plan_ranges() {
+    assert_eq!(plan_ranges(10, 4), vec![(0, 4), (4, 8), (8, 10)]);
+    assert_eq!(plan_ranges(8, 4), vec![(0, 4), (4, 8)]);
+    assert_eq!(plan_ranges(3, 0), vec![(0, 1), (1, 2), (2, 3)]);
+    assert!(plan_ranges(0, 4).is_empty());
+}
+
+#[test]
+fn test_range_header() {
+    assert_eq!(range_header(0, 100), "bytes=0-99");
+    assert_eq!(parse_content_range("bytes 0-99/1000"), Some((0, 100, Some(1000))));
+    assert_eq!(parse_content_range(" bytes 100-199/*"), Some((100, 200, None)));
+    assert_eq!(parse_content_range("bytes */1000"), None);
+    assert_eq!(parse_content_range("items 0-1/2"), None);
+}
+
+#[test]
+fn test_if_range_validator() {
+    let last_modified = Some("Wed, 21 Oct 2015 07:28:00 GMT");
+    assert_eq!(if_range_validator(Some("\"abc\""), last_modified), Some(String::from("\"abc\"")));
+    assert_eq!(if_range_validator(Some("W/\"abc\""), last_modified), None);
+    assert_eq!(if_range_validator(None, last_modified), last_modified.map(String::from));
+    assert_eq!(if_range_validator(Some(""), None), None);
+}
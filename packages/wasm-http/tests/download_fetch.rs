@@ -0,0 +1,86 @@
+//! `Download` 的请求, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test download_fetch`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Promise, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::download::Download;
+use wasm_http::har;
+use web_sys::Blob;
+
+#[wasm_bindgen(inline_js = r#"
+export function install(content, etag) {
+    globalThis.__resource = { content, etag, delay: 0 };
+    globalThis.fetch = async (request) => {
+        // 按请求时的资源生成响应, 范围请求延迟后返回
+        const { content, etag, delay } = globalThis.__resource;
+        const headers = { "Accept-Ranges": "bytes", "Content-Length": String(content.length), ETag: etag };
+        let response;
+        if (request.method === "HEAD") {
+            response = () => new Response(null, { status: 200, headers });
+        } else {
+            const [start, end] = request.headers.get("Range").slice(6).split("-").map(Number);
+            response = request.headers.get("If-Range") === etag
+                ? () => new Response(content.slice(start, end + 1), { status: 206, headers: { ETag: etag, "Content-Range": `bytes ${start}-${end}/${content.length}` } })
+                : () => new Response(content, { status: 200, headers });
+        }
+
+        if (request.method !== "HEAD") {
+            await new Promise((resolve) => setTimeout(resolve, delay));
+        }
+        return response();
+    };
+}
+export function change(content, etag, delay) { globalThis.__resource = { content, etag, delay }; }
+export function sleep(ms) { return new Promise((resolve) => setTimeout(resolve, ms)); }
+export function text(blob) { return blob.text(); }
+export function entries(har) { return har.log.entries.map((entry) => `${entry.request.method} ${entry.response.status}`); }
+"#)]
+extern "C" {
+    fn install(content: &str, etag: &str);
+    fn change(content: &str, etag: &str, delay: u32);
+    fn sleep(ms: u32) -> Promise;
+    fn text(blob: &Blob) -> Promise;
+    fn entries(har: &JsValue) -> Vec<String>;
+}
+
+fn download() -> Download {
+    let options = JSON::parse(r#"{"rangeSize": 5, "parallel": 2, "retryDelays": []}"#).unwrap();
+    Download::new(String::from("http://localhost/file.txt"), options).unwrap()
+}
+
+async fn read(promise: Promise) -> Result<String, JsValue> {
+    let blob: Blob = JsFuture::from(promise).await?.into();
+    Ok(JsFuture::from(text(&blob)).await?.as_string().unwrap())
+}
+
+#[wasm_bindgen_test]
+async fn test_layers() {
+    install("0123456789", r#""a""#);
+    har::start_har_recording(JsValue::UNDEFINED);
+    assert_eq!(read(download().start_js()).await.unwrap(), "0123456789");
+
+    // 经过 `HarLayer` 等 `Layer`
+    let har = har::stop_har_recording().unwrap();
+    assert_eq!(entries(&har), vec!["HEAD 200", "GET 206", "GET 206"]);
+}
+
+#[wasm_bindgen_test]
+async fn test_late_ranges() {
+    install("0123456789", r#""a""#);
+    change("0123456789", r#""a""#, 30);
+    let download = download();
+    let first = download.start_js();
+
+    // 第一次的范围请求还未返回时资源发生变化, 再次开始下载
+    JsFuture::from(sleep(10)).await.unwrap();
+    change("abcdefghijklmnopqrst", r#""b""#, 0);
+    assert_eq!(read(download.start_js()).await.unwrap(), "abcdefghijklmnopqrst");
+
+    // 第一次的结果被忽略, 不会写入已下载的范围
+    assert!(read(first).await.is_err());
+    assert_eq!(download.loaded(), 20.0);
+    assert_eq!(read(download.start_js()).await.unwrap(), "abcdefghijklmnopqrst");
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "fault-injection")]
+
+use serde_json::json;
+use wasm_http::fault::{glob, truncate, FaultKind, FaultRule, Faults};
+use wasm_http::{Error, HttpResponseOptions};
+
+#[test]
+fn test_glob() {
+    assert!(glob("/api/*", "/api/users?page=1"));
+    assert!(glob("*://api.example.org/*/orders", "https://api.example.org/v1/orders"));
+    assert!(glob("/api/*/items/*", "/api/1/items/2"));
+    assert!(!glob("/api/*", "/static/app.js"));
+    assert!(!glob("/api/*/a*a", "/api/xa"));
+    assert!(glob("/health", "/health"));
+}
+
+#[test]
+fn test_pick() {
+    let faults = Faults::new(vec![
+        FaultRule {
+            url: Some(String::from("/api/payments*")),
+            method: Some(String::from("post")),
+            probability: 0.5,
+            kind: Some(FaultKind::Status(503, json!({"message": "unavailable"}))),
+            ..Default::default()
+        },
+        FaultRule {
+            url: Some(String::from("/api/*")),
+            latency: 300,
+            ..Default::default()
+        },
+    ]);
+
+    let rule = faults.pick("POST", "https://example.org/api/payments", || 0.2).unwrap();
+    assert_eq!(rule.kind, Some(FaultKind::Status(503, json!({"message": "unavailable"}))));
+
+    // 未命中概率时不再匹配后面的规则
+    assert!(faults.pick("POST", "https://example.org/api/payments", || 0.8).is_none());
+    assert_eq!(faults.pick("GET", "https://example.org/api/payments", || 0.8).unwrap().latency, 300);
+    assert!(faults.pick("GET", "https://example.org/static/app.js", || 0.0).is_none());
+
+    let disabled = Faults { enabled: false, ..faults };
+    assert!(disabled.pick("GET", "/api/users", || 0.0).is_none());
+}
+
+#[test]
+fn test_truncate() {
+    let mut response = HttpResponseOptions {
+        raw: Some(String::from(r#"{"id": 1}"#)),
+        ..Default::default()
+    };
+    truncate(&mut response, 0.5).unwrap();
+    assert_eq!(response.raw.as_deref(), Some(r#"{"id"#));
+
+    let mut response = HttpResponseOptions {
+        body: json!("你好世界"), ..Default::default()
+    };
+    truncate(&mut response, 0.6).unwrap();
+    assert_eq!(response.body, json!("你好"));
+
+    let mut response = HttpResponseOptions { body: json!({"id": 1}), ..Default::default() };
+    assert!(matches!(truncate(&mut response, 0.5), Err(Error::MissingResponseBody)));
+}
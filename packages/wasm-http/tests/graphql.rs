@@ -0,0 +1,121 @@
+use serde_json::json;
+use wasm_http::graphql::incremental::apply;
+use wasm_http::graphql::{hash_query, GraphQLRequest, GraphQLResponse};
+use wasm_http::multipart::{get_boundary, MultipartParser};
+use wasm_http::Error;
+
+#[test]
+fn test_persisted_query() {
+    let hash = hash_query("{ hello }");
+    assert_eq!(hash, "001c3174e099bd72b729d0c0a529ba9f5a740c446e2a6e1d71b283cb84ec3065");
+
+    let request = GraphQLRequest::new("{ hello }", Some(json!({"id": 1})));
+    assert_eq!(request.body(None, true), json!({"query": "{ hello }", "variables": {"id": 1}}));
+    assert_eq!(request.body(Some(&hash), false), json!({"variables": {"id": 1}, "extensions": {"persistedQuery": {"version": 1, "sha256Hash": hash}}}));
+    assert_eq!(
+        request.body(Some(&hash), true),
+        json!({"query": "{ hello }", "variables": {"id": 1}, "extensions": {"persistedQuery": {"version": 1, "sha256Hash": hash}}})
+    );
+}
+
+#[test]
+fn test_graphql_errors() {
+    let body = json!({
+        "data": {"user": null},
+        "errors": [{"message": "not found", "locations": [{"line": 1, "column": 3}], "path": ["user", 0, "name"], "extensions": {"code": "NOT_FOUND"}}]
+    });
+
+    let response = GraphQLResponse::from_body(200, true, body).unwrap();
+    assert_eq!(response.errors[0].code(), Some("NOT_FOUND"));
+
+    match response.into_result() {
+        Err(err @ Error::GraphQL(_)) => assert_eq!(err.to_string(), "graphql error: not found (at user.0.name)"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    assert!(GraphQLResponse::from_body(200, true, json!({"data": {"hello": "world"}})).unwrap().into_result().is_ok());
+    assert!(GraphQLResponse::from_body(502, false, json!({"message": "bad gateway"})).is_err());
+}
+
+#[test]
+fn test_multipart_parser() {
+    assert_eq!(get_boundary("multipart/mixed; boundary=\"graphql\"; deferSpec=20220824"), Some(String::from("graphql")));
+    assert_eq!(get_boundary("multipart/mixed"), None);
+
+    let body = "---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{\"data\":{\"a\":1},\"hasNext\":true}\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n{\"hasNext\":false}\r\n-----\r\n";
+
+    // 任意切分都得到同样的结果
+    for size in [1, 3, 7, body.len()] {
+        let mut parser = MultipartParser::new("-");
+        let mut parts: Vec<String> = Vec::new();
+        for chunk in body.as_bytes().chunks(size) {
+            parts.extend(parser.feed(chunk).into_iter().map(|part| String::from_utf8(part.body).unwrap()));
+        }
+
+        assert_eq!(parts, vec!["{\"data\":{\"a\":1},\"hasNext\":true}", "{\"hasNext\":false}"]);
+        assert!(parser.is_done());
+    }
+}
+
+#[test]
+fn test_is_mutation() {
+    let request = |query: &str, operation_name: Option<&str>| {
+        let mut request = GraphQLRequest::new(query, None);
+        request.operation_name = operation_name.map(String::from);
+        request
+    };
+
+    assert!(!request("{ hello }", None).is_mutation());
+    assert!(request("# 注释\n mutation Add($id: ID!) { add(id: $id) }", None).is_mutation());
+    assert!(!request("# mutation\nquery { hello(text: \"mutation\") }", None).is_mutation());
+
+    // 多个操作时按 `operationName` 选择
+    let query = "fragment F on User { mutation: id }\nquery Get { user { ...F } }\nmutation Add { add }";
+    assert_eq!(request(query, Some("Get")).operation_type(), Some("query"));
+    assert!(!request(query, Some("Get")).is_mutation());
+    assert!(request(query, Some("Add")).is_mutation());
+    assert!(request(query, None).is_mutation());
+    assert_eq!(request("query A { a } { b }", None).operation_type(), None);
+    assert_eq!(request(r#"subscription """mutation""" S { s }"#, None).operation_type(), Some("subscription"));
+}
+
+#[test]
+fn test_incremental_apply() {
+    let mut result = json!({});
+    apply(&mut result, json!({"data": {"user": {"id": 1, "friends": [{"id": 2}]}}, "hasNext": true}));
+    apply(
+        &mut result,
+        json!({
+            "incremental": [
+                {"data": {"name": "a"}, "path": ["user"], "label": "profile"},
+                {"items": [{"id": 3}, {"id": 4}], "path": ["user", "friends", 1]}
+            ],
+            "hasNext": true
+        }),
+    );
+    // 旧格式
+    apply(&mut result, json!({"data": {"age": 18}, "path": ["user"], "errors": [{"message": "partial"}], "hasNext": false}));
+
+    assert_eq!(
+        result,
+        json!({
+            "data": {"user": {"id": 1, "name": "a", "age": 18, "friends": [{"id": 2}, {"id": 3}, {"id": 4}]}},
+            "errors": [{"message": "partial"}],
+            "hasNext": false
+        })
+    );
+}
+
+#[test]
+fn test_incremental_index_out_of_range() {
+    let mut result = json!({"data": {"friends": [{"id": 1}]}, "hasNext": true});
+    // 超出列表长度的下标不会分配空间
+    apply(&mut result, json!({"incremental": [{"items": [{"id": 9}], "path": ["friends", 4294967295u64]}], "hasNext": true}));
+    apply(&mut result, json!({"incremental": [{"data": {"name": "x"}, "path": ["friends", 3]}], "hasNext": true}));
+    assert_eq!(result["data"], json!({"friends": [{"id": 1}]}));
+
+    // 等于列表长度时追加
+    apply(&mut result, json!({"incremental": [{"items": [{"id": 2}], "path": ["friends", 1]}], "hasNext": true}));
+    apply(&mut result, json!({"incremental": [{"data": {"id": 3}, "path": ["friends", 2]}], "hasNext": false}));
+    assert_eq!(result["data"], json!({"friends": [{"id": 1}, {"id": 2}, {"id": 3}]}));
+}
@@ -0,0 +1,73 @@
+//! `@defer`/`@stream` 的增量响应, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test graphql_incremental`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Function, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::graphql::GraphQLClient;
+use wasm_http::har;
+
+#[wasm_bindgen(inline_js = r#"
+export function install(parts) {
+    globalThis.fetch = async (request) => {
+        globalThis.__accept = request.headers.get("accept");
+        const encoder = new TextEncoder();
+        const body = new ReadableStream({
+            async start(controller) {
+                // 每个 `part` 分两次发送
+                for (const part of parts) {
+                    const text = `\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n${part}`;
+                    const middle = Math.floor(text.length / 2);
+                    controller.enqueue(encoder.encode(text.slice(0, middle)));
+                    await new Promise((resolve) => setTimeout(resolve, 1));
+                    controller.enqueue(encoder.encode(text.slice(middle)));
+                }
+                controller.enqueue(encoder.encode("\r\n-----\r\n"));
+                controller.close();
+            },
+        });
+        return new Response(body, { headers: { "Content-Type": 'multipart/mixed; boundary="-"; deferSpec=20220824' } });
+    };
+}
+export function accept() { return globalThis.__accept; }
+export function collector() {
+    globalThis.__next = [];
+    return (response) => globalThis.__next.push(JSON.stringify(response.data));
+}
+export function next() { return globalThis.__next; }
+export function entries(har) { return har.log.entries.map((entry) => `${entry.request.method} ${entry.response.status}`); }
+"#)]
+extern "C" {
+    fn install(parts: Vec<String>);
+    fn accept() -> String;
+    fn collector() -> Function;
+    fn next() -> Vec<String>;
+    fn entries(har: &JsValue) -> Vec<String>;
+}
+
+#[wasm_bindgen_test]
+async fn test_incremental() {
+    install(vec![
+        String::from(r#"{"data":{"user":{"id":1}},"hasNext":true}"#),
+        String::from(r#"{"incremental":[{"data":{"name":"a"},"path":["user"]}],"hasNext":false}"#),
+    ]);
+
+    let client = GraphQLClient::new(String::from("http://localhost/graphql"), JsValue::UNDEFINED).unwrap();
+    let opts = JSON::parse("{}").unwrap();
+    js_sys::Reflect::set(&opts, &JsValue::from_str("onNext"), &collector()).unwrap();
+
+    har::start_har_recording(JsValue::UNDEFINED);
+    let query = String::from("query { user { id ... @defer { name } } }");
+    let response = JsFuture::from(client.query(query, JsValue::UNDEFINED, opts)).await.unwrap();
+    let har = har::stop_har_recording().unwrap();
+
+    assert!(accept().starts_with("multipart/mixed"));
+    assert_eq!(next(), vec![r#"{"user":{"id":1}}"#, r#"{"user":{"id":1,"name":"a"}}"#]);
+    let data = js_sys::Reflect::get(&response, &JsValue::from_str("data")).unwrap();
+    assert_eq!(JSON::stringify(&data).unwrap(), r#"{"user":{"id":1,"name":"a"}}"#);
+
+    // 经过 `HarLayer` 等 `Layer`
+    assert_eq!(entries(&har), vec!["POST 200"]);
+}
@@ -0,0 +1,86 @@
+use http::header::{HeaderMap, HeaderValue};
+use serde_json::json;
+use wasm_http::har::{Entry, HarRequest, PostData, Recorder, Redaction, Replay, REDACTED};
+use wasm_http::{Error, HttpRequestType, HttpResponseOptions};
+
+fn http_response(body: serde_json::Value) -> HttpResponseOptions {
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", HeaderValue::from_static("application/json"));
+    headers.insert("set-cookie", HeaderValue::from_static("session=abc"));
+    HttpResponseOptions {
+        status_code: 200,
+        status_text: String::from("OK"),
+        ok: true,
+        headers,
+        body,
+        ..Default::default()
+    }
+}
+
+fn har_request(url: &str) -> HarRequest {
+    let headers = json!({"Authorization": "Bearer xxx", "X-Trace": "1"});
+    let body = PostData {
+        mime_type: String::from("application/json"),
+        text: json!({"user": "a", "password": "secret"}).to_string(),
+    };
+    HarRequest::new("post", url, Some(&headers), Some(body))
+}
+
+#[test]
+fn test_record() {
+    let redaction = Redaction {
+        body_keys: vec![String::from("password"), String::from("token")],
+        ..Default::default()
+    };
+
+    let mut recorder = Recorder::new(2, redaction);
+    let ok = http_response(json!({"data": [{"token": "t1", "id": 1}]}));
+    recorder.record(Entry::new("2024-01-01T00:00:00.000Z", har_request("https://example.org/a?x=1&y"), Ok(&ok), None));
+    recorder.record(Entry::new("2024-01-01T00:00:01.000Z", har_request("https://example.org/b"), Err(&Error::MissingResponseBody), None));
+    recorder.record(Entry::new("2024-01-01T00:00:02.000Z", har_request("https://example.org/c"), Ok(&ok), None));
+
+    let har = recorder.to_har();
+    assert_eq!(har.log.version, "1.2");
+    assert_eq!(har.log.entries.len(), 2);
+
+    let failed = &har.log.entries[0];
+    assert_eq!(failed.request.url, "https://example.org/b");
+    assert_eq!(failed.response.status, 0);
+    assert_eq!(failed.error.as_deref(), Some("missing response body in HTTP call"));
+
+    let entry = &har.log.entries[1];
+    assert_eq!(entry.request.method, "POST");
+    let authorization = entry.request.headers.iter().find(|header| header.name == "Authorization").unwrap();
+    assert_eq!(authorization.value, REDACTED);
+    assert_eq!(entry.response.headers.iter().find(|header| header.name == "set-cookie").unwrap().value, REDACTED);
+    assert_eq!(entry.request.post_data.as_ref().unwrap().text, json!({"user": "a", "password": REDACTED}).to_string());
+    assert_eq!(entry.response.content.text.as_deref(), Some(json!({"data": [{"token": REDACTED, "id": 1}]}).to_string().as_str()));
+
+    let query = HarRequest::new("get", "https://example.org/a?x=1&y#top", None, None).query_string;
+    assert_eq!(query.len(), 2);
+    assert_eq!((query[0].name.as_str(), query[0].value.as_str(), query[1].name.as_str()), ("x", "1", "y"));
+}
+
+#[test]
+fn test_replay() {
+    let mut recorder = Recorder::new(10, Redaction::default());
+    recorder.record(Entry::new("", HarRequest::new("get", "https://example.org/a", None, None), Ok(&http_response(json!({"page": 1}))), None));
+    recorder.record(Entry::new("", HarRequest::new("get", "https://example.org/a", None, None), Ok(&http_response(json!({"page": 2}))), None));
+    let blob = http_response(json!({"binary": [1, 2, 255]}));
+    recorder.record(Entry::new("", HarRequest::new("get", "https://example.org/blob", None, None), Ok(&blob), Some(&HttpRequestType::Blob)));
+
+    // 导出再导入
+    let har = serde_json::from_str(&serde_json::to_string(&recorder.to_har()).unwrap()).unwrap();
+    let mut replay = Replay::new(har);
+
+    // 按顺序返回, 用完后一直返回最后一条, 响应的处理见 `har_wire`
+    let text = |entry: Entry| entry.response.content.text.unwrap();
+    assert_eq!(text(replay.entry("GET", "https://example.org/a").unwrap()), r#"{"page":1}"#);
+    assert_eq!(text(replay.entry("get", "https://example.org/a").unwrap()), r#"{"page":2}"#);
+    assert_eq!(text(replay.entry("GET", "https://example.org/a").unwrap()), r#"{"page":2}"#);
+    assert!(replay.entry("POST", "https://example.org/a").is_err());
+
+    let entry = replay.find("GET", "https://example.org/blob").unwrap().clone();
+    assert_eq!(entry.response.content.encoding.as_deref(), Some("base64"));
+    assert_eq!(entry.response.content.text.as_deref(), Some("AQL/"));
+}
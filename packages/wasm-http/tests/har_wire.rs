@@ -0,0 +1,118 @@
+//! `HAR` 记录收到的原始响应, 回放时与实际的响应一样处理, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test har_wire`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::JSON;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use wasm_http::har::{self, replay, Entry, HarRequest, Recorder, Redaction};
+use wasm_http::{HttpRequestType, HttpResponseOptions};
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.fetch = async (request) => {
+        if (request.url.endsWith("/gbk")) {
+            // `GBK` 编码的 "中文"
+            return new Response(new Uint8Array([0xd6, 0xd0, 0xce, 0xc4]), { headers: { "Content-Type": "text/plain; charset=gbk" } });
+        }
+        return new Response('{"user_name":"a"}', { headers: { "Content-Type": "application/json" } });
+    };
+}
+export function body(response) {
+    const body = response.body;
+    return JSON.stringify(body instanceof Map ? Object.fromEntries(body) : body);
+}
+export function blob(response) { return Array.from(new Uint8Array(response.body.binary ?? response.body.get("binary"))); }
+export function offline() {
+    globalThis.fetch = async () => { throw new TypeError("Failed to fetch"); };
+}
+"#)]
+extern "C" {
+    fn install();
+    fn offline();
+    fn body(response: &JsValue) -> String;
+    fn blob(response: &JsValue) -> Vec<u8>;
+}
+
+async fn send(opts: Value) -> Result<Value, String> {
+    let opts = JSON::parse(&opts.to_string()).unwrap();
+    match wasm_http::send(opts, JsValue::UNDEFINED).await {
+        Ok(response) => Ok(serde_json::from_str(&body(&response)).unwrap()),
+        Err(err) => Err(err.as_string().unwrap()),
+    }
+}
+
+/// 同样的请求, 录制时及回放时的结果相同
+async fn requests() {
+    let validated = send(json!({"url": "http://localhost/users", "method": "get", "transformResponseKeys": "camel", "schema": {"type": "object", "required": ["id"]}})).await;
+    assert!(validated.unwrap_err().starts_with("response validation failed"));
+
+    let transformed = send(json!({"url": "http://localhost/users", "method": "get", "transformResponseKeys": "camel"})).await;
+    assert_eq!(transformed.unwrap(), json!({"userName": "a"}));
+
+    let decoded = send(json!({"url": "http://localhost/gbk", "method": "get", "responseType": 4})).await;
+    assert_eq!(decoded.unwrap(), json!("中文"));
+}
+
+#[wasm_bindgen_test]
+async fn test_record_and_replay() {
+    install();
+    har::start_har_recording(JsValue::UNDEFINED);
+    requests().await;
+    let recorded = har::stop_har_recording().unwrap();
+
+    // 记录收到的原始内容, 校验失败的请求同样记录响应
+    let har: Value = serde_wasm_bindgen::from_value(recorded.clone()).unwrap();
+    let contents: Vec<Value> = har["log"]["entries"].as_array().unwrap().iter().map(|entry| json!([entry["response"]["status"], entry["response"]["content"]])).collect();
+    assert_eq!(
+        contents,
+        vec![
+            json!([200, {"size": 17, "mimeType": "application/json", "text": r#"{"user_name":"a"}"#}]),
+            json!([200, {"size": 17, "mimeType": "application/json", "text": r#"{"user_name":"a"}"#}]),
+            json!([200, {"size": 4, "mimeType": "text/plain; charset=gbk", "text": "1tDOxA==", "encoding": "base64"}]),
+        ]
+    );
+
+    // 回放时经过转换、校验及解码
+    offline();
+    replay::replay_har(recorded).unwrap();
+    requests().await;
+    replay::stop_replay();
+}
+
+fn recorded(body: Value) -> HttpResponseOptions {
+    let mut response = HttpResponseOptions {
+        status_code: 200,
+        status_text: String::from("OK"),
+        ok: true,
+        body,
+        ..Default::default()
+    };
+    response.headers.insert("content-type", http::HeaderValue::from_static("application/json"));
+    response
+}
+
+#[wasm_bindgen_test]
+async fn test_replay_order() {
+    let mut recorder = Recorder::new(10, Redaction::default());
+    recorder.record(Entry::new("", HarRequest::new("get", "http://localhost/a", None, None), Ok(&recorded(json!({"page": 1}))), None));
+    recorder.record(Entry::new("", HarRequest::new("get", "http://localhost/a", None, None), Ok(&recorded(json!({"page": 2}))), None));
+    let binary = recorded(json!({"binary": [1, 2, 255]}));
+    recorder.record(Entry::new("", HarRequest::new("get", "http://localhost/blob", None, None), Ok(&binary), Some(&HttpRequestType::Blob)));
+
+    offline();
+    replay::replay_har(JsValue::from_str(&serde_json::to_string(&recorder.to_har()).unwrap())).unwrap();
+
+    // 按顺序返回, 用完后一直返回最后一条
+    assert_eq!(send(json!({"url": "http://localhost/a", "method": "get"})).await.unwrap(), json!({"page": 1}));
+    assert_eq!(send(json!({"url": "http://localhost/a", "method": "GET"})).await.unwrap(), json!({"page": 2}));
+    assert_eq!(send(json!({"url": "http://localhost/a", "method": "get"})).await.unwrap(), json!({"page": 2}));
+    assert!(send(json!({"url": "http://localhost/a", "method": "post"})).await.unwrap_err().contains("no recorded response in har"));
+
+    // `base64` 的内容按 `responseType` 读取
+    let opts = JSON::parse(r#"{"url": "http://localhost/blob", "method": "get", "responseType": 3}"#).unwrap();
+    let response = wasm_http::send(opts, JsValue::UNDEFINED).await.unwrap();
+    assert_eq!(blob(&response), vec![1, 2, 255]);
+    replay::stop_replay();
+}
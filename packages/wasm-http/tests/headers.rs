@@ -0,0 +1,41 @@
+use http::header::{HeaderMap, HeaderValue};
+use wasm_http::response::headers::{split_header_values, ResponseHeaders};
+use wasm_http::response::response_type::ResponseType;
+
+#[test]
+fn test_split_header_values() {
+    assert_eq!(split_header_values("Vary", "Accept-Encoding, Origin"), vec!["Accept-Encoding", "Origin"]);
+    assert_eq!(split_header_values("Cache-Control", "no-cache, max-age=0,  , private"), vec!["no-cache", "max-age=0", "private"]);
+    assert_eq!(split_header_values("X-Names", r#""a, b", c"#), vec![r#""a, b""#, "c"]);
+    assert_eq!(
+        split_header_values("Link", r#"<https://a.com/?page=2,3>; rel="next", <https://a.com/?page=1>; rel="first""#),
+        vec![r#"<https://a.com/?page=2,3>; rel="next""#, r#"<https://a.com/?page=1>; rel="first""#]
+    );
+    assert_eq!(split_header_values("Date", "Wed, 21 Oct 2015 07:28:00 GMT"), vec!["Wed, 21 Oct 2015 07:28:00 GMT"]);
+}
+
+#[test]
+fn test_response_headers() {
+    let mut map = HeaderMap::new();
+    map.append("x-request-id", HeaderValue::from_static("1"));
+    map.append("vary", HeaderValue::from_static("Accept"));
+    map.append("vary", HeaderValue::from_static("Origin"));
+    map.append("content-type", HeaderValue::from_static("application/json"));
+
+    let headers = ResponseHeaders::new(map, ResponseType::Cors);
+    assert_eq!(headers.get("Vary"), Some("Accept, Origin".to_string()));
+    assert_eq!(headers.get_all("VARY"), vec!["Accept", "Origin"]);
+    assert_eq!(headers.get("X-Missing"), None);
+    assert!(headers.has("Content-Type"));
+    assert_eq!(headers.exposed(), vec!["x-request-id", "vary"]);
+    assert!(headers.is_hidden("X-Total-Count"));
+    assert!(!headers.is_hidden("Content-Language"));
+    assert!(!headers.is_hidden("X-Request-Id"));
+}
+
+#[test]
+fn test_opaque_response_headers() {
+    let headers = ResponseHeaders::new(HeaderMap::new(), ResponseType::Opaque);
+    assert!(headers.filtered());
+    assert!(headers.is_hidden("Content-Type"));
+}
@@ -0,0 +1,44 @@
+use serde_json::json;
+use wasm_http::idempotency::{is_unsafe_method, with_header, IdempotencyKey, HEADER};
+
+#[test]
+fn test_key() {
+    let key = IdempotencyKey::Auto.key();
+    assert_eq!(key.len(), 36);
+    assert_eq!(key.chars().nth(14), Some('4'));
+    assert_ne!(key, IdempotencyKey::Auto.key());
+    assert_eq!(IdempotencyKey::Key(String::from("order-1")).key(), "order-1");
+    assert!(is_unsafe_method("POST"));
+    assert!(is_unsafe_method("patch"));
+    assert!(!is_unsafe_method("PUT"));
+    assert!(!is_unsafe_method("GET"));
+}
+
+#[test]
+fn test_with_header() {
+    let (headers, key) = with_header(Some(json!({"Accept": "application/json"})), "a");
+    assert_eq!(key, "a");
+    assert_eq!(headers, json!({"Accept": "application/json", HEADER: "a"}));
+
+    // 已有时不覆盖, 重试时得到同一个键
+    let (headers, key) = with_header(Some(headers), "b");
+    assert_eq!(key, "a");
+    assert_eq!(headers[HEADER], "a");
+
+    let (headers, key) = with_header(Some(json!({"idempotency-key": "c"})), "d");
+    assert_eq!(key, "c");
+    assert_eq!(headers, json!({"idempotency-key": "c"}));
+
+    let (headers, _) = with_header(None, "e");
+    assert_eq!(headers, json!({HEADER: "e"}));
+}
+
+#[test]
+fn test_derive() {
+    let key = IdempotencyKey::Key(String::from("upload-1"));
+    assert_eq!(key.derive("create"), "upload-1-create");
+    assert_eq!(key.derive("0-4"), "upload-1-0-4");
+
+    let auto = IdempotencyKey::Auto;
+    assert_ne!(auto.derive("0-0"), auto.derive("0-0"));
+}
@@ -0,0 +1,143 @@
+//! 原生的 `Request`、`URL` 作为请求的输入, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test input`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::JSON;
+use serde::Deserialize;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use wasm_http::schema::Schema;
+use wasm_http::{send_request, Error, HttpRequest, HttpRequestOptions};
+use web_sys::{Request, Url};
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.fetch = async (request, init) => {
+        // `init` 中的属性覆盖 `request` 中的
+        const value = (name) => init?.[name] ?? request[name];
+        globalThis.__sent = {
+            url: request.url,
+            method: request.method,
+            body: await request.text(),
+            header: request.headers.get("x-trace"),
+            mode: value("mode"),
+            credentials: value("credentials"),
+            cache: value("cache"),
+            redirect: value("redirect"),
+            referrerPolicy: value("referrerPolicy"),
+        };
+        return new Response("{}", { headers: { "Content-Type": "application/json" } });
+    };
+}
+export function sent() { return JSON.stringify(globalThis.__sent); }
+export function request() {
+    return new Request("http://localhost/a?page=1", {
+        method: "POST",
+        body: "hello",
+        headers: { "X-Trace": "1" },
+        credentials: "include",
+        cache: "no-store",
+        redirect: "manual",
+        referrerPolicy: "no-referrer",
+    });
+}
+"#)]
+extern "C" {
+    fn install();
+    fn sent() -> String;
+    fn request() -> Request;
+}
+
+#[wasm_bindgen_test]
+fn test_from_url() {
+    let url = Url::new("http://localhost/users?page=2").unwrap();
+    let options = HttpRequestOptions::from_url(&url);
+    assert_eq!(options.url, "http://localhost/users?page=2");
+    assert_eq!(options.method, None);
+    assert!(options.source.is_none());
+}
+
+#[wasm_bindgen_test]
+fn test_from_request() {
+    let options = HttpRequestOptions::from_request(&request()).unwrap();
+    assert_eq!(options.url, "http://localhost/a?page=1");
+    assert_eq!(options.method.as_deref(), Some("POST"));
+    assert_eq!(options.headers.unwrap()["x-trace"], json!("1"));
+    assert!(options.source.is_some());
+}
+
+#[wasm_bindgen_test]
+async fn test_url_override() {
+    install();
+
+    // 相同的 `url` 直接使用原生的 `Request`
+    wasm_http::send(request().into(), JsValue::UNDEFINED).await.unwrap();
+    let same: serde_json::Value = serde_json::from_str(&sent()).unwrap();
+    assert_eq!(same["url"], json!("http://localhost/a?page=1"));
+    assert_eq!(same["body"], json!("hello"));
+
+    // 修改 `url` 后沿用 `body` 及 `credentials` 等属性, `request` 中设置的值优先
+    let overrides = JSON::parse(r#"{"url": "http://localhost/b", "redirect": "error"}"#).unwrap();
+    wasm_http::send(request().into(), overrides).await.unwrap();
+    let post: serde_json::Value = serde_json::from_str(&sent()).unwrap();
+    assert_eq!(
+        post,
+        json!({
+            "url": "http://localhost/b",
+            "method": "POST",
+            "body": "hello",
+            "header": "1",
+            "mode": "cors",
+            "credentials": "include",
+            "cache": "no-store",
+            "redirect": "error",
+            "referrerPolicy": "no-referrer",
+        })
+    );
+
+    // `GET` 请求没有 `body`
+    let overrides = JSON::parse(r#"{"url": "http://localhost/c", "method": "get"}"#).unwrap();
+    wasm_http::send(request().into(), overrides).await.unwrap();
+    let get: serde_json::Value = serde_json::from_str(&sent()).unwrap();
+    assert_eq!(get["method"], json!("GET"));
+    assert_eq!(get["body"], json!(""));
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Sent {
+    url: String,
+    method: String,
+    body: String,
+    credentials: String,
+}
+
+#[wasm_bindgen_test]
+async fn test_send_request() {
+    install();
+
+    // `Rust` 中发送原生的 `Request`, 使用 `serde` 类型校验响应
+    let mut options = HttpRequestOptions::from_request(&request()).unwrap();
+    options.schema = Some(Schema::of::<Sent>());
+    let err = send_request(options, HttpRequest::new()).await.unwrap_err();
+    assert!(matches!(err, Error::Validation(_)), "{}", err);
+
+    let response = send_request(HttpRequestOptions::from_request(&request()).unwrap(), HttpRequest::new()).await.unwrap();
+    assert_eq!(response.json::<serde_json::Value>().unwrap(), json!({}));
+    assert!(response.json::<Sent>().is_err());
+    let received: Sent = serde_json::from_str(&sent()).unwrap();
+    assert_eq!(
+        received,
+        Sent {
+            url: String::from("http://localhost/a?page=1"),
+            method: String::from("POST"),
+            body: String::from("hello"),
+            credentials: String::from("include")
+        }
+    );
+
+    let url = Url::new("http://localhost/users?page=2").unwrap();
+    send_request(HttpRequestOptions::from_url(&url), HttpRequest::new()).await.unwrap();
+    let sent: serde_json::Value = serde_json::from_str(&sent()).unwrap();
+    assert_eq!(sent["url"], json!("http://localhost/users?page=2"));
+}
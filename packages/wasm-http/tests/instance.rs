@@ -0,0 +1,75 @@
+//! `HttpClient` 实例的默认配置, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test instance`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Promise, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::instance::HttpInstance;
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.fetch = async (request) => {
+        globalThis.__sent = `${request.method} ${await request.text()}`;
+        return new Response("{}", { headers: { "Content-Type": "application/json" } });
+    };
+}
+export function sent() { return globalThis.__sent; }
+export function respond(text, contentType) {
+    globalThis.fetch = async () => new Response(text, { headers: { "Content-Type": contentType } });
+}
+export function body(response) {
+    const body = response.body instanceof Map ? Object.fromEntries(response.body) : response.body;
+    return JSON.stringify(body ?? null);
+}
+"#)]
+extern "C" {
+    fn install();
+    fn sent() -> String;
+    fn respond(text: &str, content_type: &str);
+    fn body(response: &JsValue) -> String;
+}
+
+async fn send(promise: Promise) -> String {
+    JsFuture::from(promise).await.unwrap();
+    sent()
+}
+
+#[wasm_bindgen_test]
+async fn test_default_data() {
+    install();
+    let client = HttpInstance::new(JSON::parse(r#"{"baseURL": "http://localhost/", "type": 0, "data": {"a": 1}}"#).unwrap()).unwrap();
+
+    // `GET`、`HEAD`、`DELETE` 不继承默认的 `data`
+    assert_eq!(send(client.get(String::from("users"), JsValue::UNDEFINED)).await, "GET ");
+    assert_eq!(send(client.delete(String::from("users/1"), JsValue::UNDEFINED)).await, "DELETE ");
+    assert_eq!(send(client.request(JSON::parse(r#"{"url": "users", "method": "head"}"#).unwrap())).await, "HEAD ");
+
+    // 有 `body` 的请求未设置 `data` 时使用默认的 `data`
+    assert_eq!(send(client.post(String::from("users"), JsValue::UNDEFINED, JsValue::UNDEFINED)).await, r#"POST {"a":1}"#);
+    assert_eq!(send(client.post(String::from("users"), JSON::parse(r#"{"b": 2}"#).unwrap(), JsValue::UNDEFINED)).await, r#"POST {"b":2}"#);
+
+    // 子实例设置了 `DELETE` 时同样不继承
+    let child = client.extend(JSON::parse(r#"{"method": "delete"}"#).unwrap()).unwrap();
+    assert_eq!(send(child.request(JSON::parse(r#"{"url": "users/1"}"#).unwrap())).await, "DELETE ");
+}
+
+#[wasm_bindgen_test]
+async fn test_response_body() {
+    let client = HttpInstance::new(JSON::parse(r#"{"baseURL": "http://localhost/"}"#).unwrap()).unwrap();
+
+    // `DELETE`、`PUT` 等请求的非 `json` `body` 不解析, 仍为 `null`
+    respond("OK", "text/plain");
+    let response = JsFuture::from(client.delete(String::from("users/1"), JsValue::UNDEFINED)).await.unwrap();
+    assert_eq!(body(&response), "null");
+
+    respond("<p>ok</p>", "text/html");
+    let response = JsFuture::from(client.put(String::from("users/1"), JsValue::UNDEFINED, JsValue::UNDEFINED)).await.unwrap();
+    assert_eq!(body(&response), "null");
+
+    // 非空的 `json` `body` 正常解析
+    respond(r#"{"id":1}"#, "application/json; charset=utf-8");
+    let response = JsFuture::from(client.delete(String::from("users/1"), JsValue::UNDEFINED)).await.unwrap();
+    assert_eq!(body(&response), r#"{"id":1}"#);
+}
@@ -0,0 +1,13 @@
+use wasm_http::response::json::needs_reviver;
+
+#[test]
+fn test_needs_reviver() {
+    assert!(!needs_reviver(r#"{"id": 1, "price": -12.5, "list": [1e3, 2]}"#));
+    assert!(!needs_reviver(r#"{"id": "0", "big": "12345678901234567890"}"#));
+    assert!(!needs_reviver(r#"{"id": 9007199254740991, "escaped": "\" 0 \""}"#));
+    assert!(needs_reviver(r#"{"count": 0}"#));
+    assert!(needs_reviver(r#"{"ratio": -0.0}"#));
+    assert!(needs_reviver(r#"{"id": 9007199254740993}"#));
+    assert!(needs_reviver(r#"[1, 1e400]"#));
+    assert!(!needs_reviver(""));
+}
@@ -0,0 +1,70 @@
+//! `JSON` 响应解析的基准测试, 对比 `serde` 路径与 `JSON.parse`, 分别测试不需要及需要 `reviver` 的文本
+//! 运行: `wasm-pack test --headless --chrome --release -- --test json_bench`
+
+#![cfg(target_arch = "wasm32")]
+
+use serde_json::Value;
+use wasm_bindgen_test::*;
+use wasm_http::response::json;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// 约 `6MB` 的 `JSON` 文本, `numbers` 为 `true` 时包含需要转换为字符串的 `0` 及大数字
+fn payload(numbers: bool) -> String {
+    let items: Vec<String> = (0..40000)
+        .map(|index| {
+            let (stock, snowflake) = if numbers { (index % 3, 9007199254740993u64 + index as u64) } else { (index % 3 + 1, index as u64 + 1) };
+            format!(
+                r#"{{"id":{},"name":"item-{}","price":{}.5,"stock":{},"snowflake":{},"tags":["a","b","c"],"active":true,"owner":{{"id":{},"email":"user{}@example.org"}}}}"#,
+                index + 1,
+                index,
+                index,
+                stock,
+                snowflake,
+                index % 100 + 1,
+                index
+            )
+        })
+        .collect();
+    format!(r#"{{"total":{},"items":[{}]}}"#, items.len(), items.join(","))
+}
+
+/// 与 `serde` 路径相同, `0` 及超出 `Number.MAX_SAFE_INTEGER` 的数字转换为字符串
+fn convert_numbers(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(convert_numbers).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, convert_numbers(value))).collect()),
+        Value::Number(number) => match number.as_f64() {
+            Some(value) if value == 0.0 || value.abs() > js_sys::Number::MAX_SAFE_INTEGER => Value::String(number.to_string()),
+            _ => Value::Number(number),
+        },
+        value => value,
+    }
+}
+
+fn measure<F: FnMut()>(times: u32, mut f: F) -> f64 {
+    let start = js_sys::Date::now();
+    for _ in 0..times {
+        f();
+    }
+    (js_sys::Date::now() - start) / times as f64
+}
+
+#[wasm_bindgen_test]
+fn bench_json_parse() {
+    for numbers in [false, true] {
+        let text = payload(numbers);
+        assert_eq!(json::needs_reviver(&text), numbers);
+
+        let serde = measure(5, || {
+            let value: Value = serde_json::from_str(&text).unwrap();
+            serde_wasm_bindgen::to_value(&convert_numbers(value)).unwrap();
+        });
+
+        let native = measure(5, || {
+            json::parse(&text).unwrap();
+        });
+
+        console_log!("{} bytes, reviver: {}, serde: {:.2}ms, JSON.parse: {:.2}ms, {:.1}x", text.len(), numbers, serde, native, serde / native);
+    }
+}
@@ -0,0 +1,29 @@
+//! `JSON.parse` 及 `reviver` 的转换结果, 可以在 `node` 中运行: `wasm-pack test --node -- --test json_parse`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use wasm_http::response::json;
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    Reflect::get(value, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_parse() {
+    let value = json::parse(r#"{"id": 1, "count": 0, "ratio": -0.0, "big": 9007199254740993, "list": [0, 2], "name": "0"}"#).unwrap();
+    assert_eq!(get(&value, "id").as_f64(), Some(1.0));
+    assert_eq!(get(&value, "count").as_string().as_deref(), Some("0"));
+    assert_eq!(get(&value, "ratio").as_string().as_deref(), Some("0"));
+    assert_eq!(get(&value, "name").as_string().as_deref(), Some("0"));
+    assert_eq!(Reflect::get_u32(&get(&value, "list"), 0).unwrap().as_string().as_deref(), Some("0"));
+
+    // 支持源文本访问时由 `reviver` 转换, 否则使用 `serde_json`, 都保留原始的数字
+    assert_eq!(get(&value, "big").as_string().as_deref(), Some("9007199254740993"));
+    let value = json::parse(r#"{"id": 12345678901234567890}"#).unwrap();
+    assert_eq!(get(&value, "id").as_string().as_deref(), Some("12345678901234567890"));
+
+    assert!(json::parse("{").is_err());
+}
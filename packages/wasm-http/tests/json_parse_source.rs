@@ -0,0 +1,31 @@
+//! 不支持 `JSON.parse` 源文本访问时的大数字, 可以在 `node` 中运行: `wasm-pack test --node -- --test json_parse_source`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use wasm_http::response::json;
+
+#[wasm_bindgen(inline_js = r#"
+export function disable_source() {
+    // `reviver` 不再得到 `context`, 与 `Safari` 等浏览器相同
+    const parse = JSON.parse;
+    JSON.parse = (text, reviver) => (reviver ? parse(text, (key, value) => reviver(key, value)) : parse(text));
+}
+"#)]
+extern "C" {
+    fn disable_source();
+}
+
+#[wasm_bindgen_test]
+fn test_without_source() {
+    disable_source();
+    let value = json::parse(r#"{"id": 12345678901234567890, "count": 0, "list": [9007199254740993, 1]}"#).unwrap();
+    let get = |value: &JsValue, key: &str| Reflect::get(value, &JsValue::from_str(key)).unwrap();
+    assert_eq!(get(&value, "id").as_string().as_deref(), Some("12345678901234567890"));
+    assert_eq!(get(&value, "count").as_string().as_deref(), Some("0"));
+    let list = get(&value, "list");
+    assert_eq!(Reflect::get_u32(&list, 0).unwrap().as_string().as_deref(), Some("9007199254740993"));
+    assert_eq!(Reflect::get_u32(&list, 1).unwrap().as_f64(), Some(1.0));
+}
@@ -0,0 +1,46 @@
+use serde_json::json;
+use wasm_http::jsonrpc::{split_batch, JsonRpcErrorKind, JsonRpcRequest};
+use wasm_http::Error;
+
+#[test]
+fn test_request() {
+    let request = JsonRpcRequest::new("eth_blockNumber", None, Some(1));
+    assert_eq!(serde_json::to_value(&request).unwrap(), json!({"jsonrpc": "2.0", "method": "eth_blockNumber", "id": 1}));
+
+    // 通知没有 `id`
+    let notification = JsonRpcRequest::new("log", Some(json!(["hello"])), None);
+    assert_eq!(serde_json::to_value(&notification).unwrap(), json!({"jsonrpc": "2.0", "method": "log", "params": ["hello"]}));
+}
+
+#[test]
+fn test_split_batch() {
+    // 批量响应的顺序可以与请求不同
+    let body = json!([
+        {"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 2},
+        {"jsonrpc": "2.0", "result": "0x10", "id": 1}
+    ]);
+
+    let results = split_batch(&[1, 2, 3], body);
+    assert_eq!(results[0].as_ref().unwrap(), &json!("0x10"));
+    match &results[1] {
+        Err(Error::JsonRpc(err)) => {
+            assert_eq!(err.kind(), JsonRpcErrorKind::MethodNotFound);
+            assert_eq!(results[1].as_ref().unwrap_err().to_string(), "json-rpc error: Method not found (-32601)");
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(matches!(&results[2], Err(Error::Error(_))));
+
+    // 单个响应
+    let results = split_batch(&[7], json!({"jsonrpc": "2.0", "result": {"ok": true}, "id": 7}));
+    assert_eq!(results[0].as_ref().unwrap(), &json!({"ok": true}));
+
+    // `id` 为 `null` 的错误作用于所有调用
+    let results = split_batch(&[1, 2], json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}));
+    for result in results {
+        match result {
+            Err(Error::JsonRpc(err)) => assert_eq!(err.kind(), JsonRpcErrorKind::Parse),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! `JSON-RPC` 批量请求失败时的错误, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test jsonrpc_batch`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::JSON;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use wasm_http::circuit::set_circuit_breaker;
+use wasm_http::jsonrpc::JsonRpcClient;
+use wasm_http::{Error, HttpRequestOptions};
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.__requests = 0;
+    globalThis.fetch = async () => {
+        globalThis.__requests += 1;
+        throw new TypeError("Failed to fetch");
+    };
+}
+export function requests() { return globalThis.__requests; }
+"#)]
+extern "C" {
+    fn install();
+    fn requests() -> u32;
+}
+
+#[wasm_bindgen_test]
+async fn test_batch_error() {
+    install();
+    set_circuit_breaker(JSON::parse(r#"{"failureThreshold": 1, "coolDown": 1000}"#).unwrap()).unwrap();
+    let client = JsonRpcClient::from_options("http://localhost/rpc", HttpRequestOptions::default()).batch(true, 5);
+
+    // 同一批的调用都得到网络错误
+    let (a, b) = futures::future::join(client.call("a", None), client.call("b", None)).await;
+    assert_eq!(requests(), 1);
+    assert!(matches!(a, Err(Error::Network(_))), "{:?}", a);
+    assert!(matches!(b, Err(Error::Network(_))), "{:?}", b);
+
+    // 熔断后保留 `retry_after`
+    let (a, b) = futures::future::join(client.call("a", None), client.call("b", None)).await;
+    assert_eq!(requests(), 1);
+    for result in [a, b] {
+        match result {
+            Err(Error::CircuitOpen { retry_after, .. }) => assert!(retry_after > 0.0),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    set_circuit_breaker(JsValue::NULL).unwrap();
+}
@@ -0,0 +1,74 @@
+use wasm_http::metrics::{route_template, Metrics, Sample};
+use wasm_http::response::timing::{ResourceEntry, Timing};
+
+#[test]
+fn test_route_template() {
+    let routes = vec![String::from("/users/:name/repos"), String::from("/static/*")];
+    assert_eq!(route_template("https://api.example.org/users/octocat/repos?page=2", &routes), "/users/:name/repos");
+    assert_eq!(route_template("/static/js/app.js#top", &routes), "/static/*");
+    assert_eq!(route_template("https://api.example.org/orders/1024/items/5f0c8a1e-7b2d-4c3e-9a4f-1b2c3d4e5f60", &routes), "/orders/:id/items/:id");
+    assert_eq!(route_template("https://api.example.org", &[]), "/");
+    assert_eq!(route_template("/commits/4f2a654c0de1b2a3f4e5", &[]), "/commits/:id");
+    assert_eq!(route_template("/v2/search", &[]), "/v2/search");
+}
+
+#[test]
+fn test_record() {
+    let mut metrics = Metrics::new(Vec::new(), vec![500.0, 100.0]);
+    let sample = |duration: f64, ok: bool, error: Option<&str>| Sample {
+        route: metrics.route("https://api.example.org/users/1"),
+        method: String::from("GET"),
+        status: if ok { 200 } else { 500 },
+        ok,
+        duration,
+        error: error.map(String::from),
+        ..Default::default()
+    };
+
+    let samples = vec![sample(80.0, true, None), sample(100.0, true, None), sample(300.0, false, None), sample(2000.0, false, Some("timeout"))];
+    for sample in samples.iter() {
+        metrics.record(sample);
+    }
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.len(), 1);
+    let route = &snapshot[0];
+    assert_eq!((route.route.as_str(), route.method.as_str()), ("/users/:id", "GET"));
+    assert_eq!((route.count, route.errors, route.error_rate), (4, 2, 0.5));
+    assert_eq!((route.min, route.max, route.sum), (80.0, 2000.0, 2480.0));
+    assert_eq!(route.buckets, vec![100.0, 500.0]);
+    assert_eq!(route.counts, vec![2, 1, 1]);
+
+    metrics.reset();
+    assert!(metrics.snapshot().is_empty());
+}
+
+#[test]
+fn test_resource_timing() {
+    let entry = ResourceEntry {
+        start_time: 100.0,
+        domain_lookup_start: 101.0,
+        domain_lookup_end: 111.0,
+        connect_start: 111.0,
+        connect_end: 151.0,
+        secure_connection_start: 121.0,
+        response_start: 200.0,
+        response_end: 260.0,
+        transfer_size: 1024.0,
+        next_hop_protocol: String::from("h2"),
+    };
+
+    let timing = Timing::default().with_resource(&entry);
+    assert_eq!((timing.dns, timing.connect, timing.tls), (Some(10.0), Some(40.0), Some(30.0)));
+    assert_eq!((timing.ttfb, timing.download, timing.transfer_size), (Some(100.0), Some(60.0), Some(1024.0)));
+    assert_eq!(timing.protocol.as_deref(), Some("h2"));
+
+    // 跨域且没有 `Timing-Allow-Origin`
+    let restricted = ResourceEntry {
+        start_time: 100.0,
+        response_end: 260.0,
+        ..Default::default()
+    };
+    let timing = Timing::default().with_resource(&restricted);
+    assert_eq!((timing.dns, timing.ttfb, timing.protocol), (None, None, None));
+}
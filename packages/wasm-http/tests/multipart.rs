@@ -0,0 +1,42 @@
+use serde_json::json;
+use wasm_http::multipart::{get_boundary, is_form_data, parse};
+
+#[test]
+fn test_get_boundary() {
+    assert_eq!(get_boundary("multipart/mixed; boundary=\"batch_1\""), Some(String::from("batch_1")));
+    assert_eq!(get_boundary("Multipart/Form-Data;boundary=----abc"), Some(String::from("----abc")));
+    assert_eq!(get_boundary("multipart/mixed"), None);
+    assert_eq!(get_boundary("application/json; boundary=x"), None);
+    assert!(is_form_data("multipart/form-data; boundary=x"));
+    assert!(!is_form_data("multipart/mixed; boundary=x"));
+}
+
+#[test]
+fn test_parse() {
+    let body = [
+        b"preamble\r\n--b1\r\n".as_slice(),
+        b"Content-Type: application/json\r\n\r\n{\"id\":1}\r\n--b1\r\n",
+        b"Content-Disposition: form-data; name=\"note\"\r\nContent-Type: text/plain; charset=utf-8\r\n\r\nhello\r\nworld\r\n--b1\r\n",
+        b"Content-Disposition: attachment; name=\"file\"; filename=\"a.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n\x00\xff\r\n\r\n--b1\r\n",
+        b"\r\nno headers\r\n--b1--\r\nepilogue",
+    ]
+    .concat();
+
+    let parts = parse(&body, "b1");
+    assert_eq!(parts.len(), 4);
+
+    assert_eq!(parts[0].content_type(), Some("application/json"));
+    assert_eq!(parts[0].value(), Some(json!({"id": 1})));
+
+    assert_eq!(parts[1].name().as_deref(), Some("note"));
+    assert_eq!(parts[1].value(), Some(json!("hello\r\nworld")));
+
+    assert_eq!(parts[2].filename().as_deref(), Some("a.bin"));
+    assert_eq!(parts[2].body, b"\x00\xff\r\n".to_vec());
+    assert_eq!(parts[2].value(), None);
+
+    assert!(parts[3].headers.is_empty());
+    assert_eq!(parts[3].value(), Some(json!("no headers")));
+
+    assert!(parse(b"no delimiter", "b1").is_empty());
+}
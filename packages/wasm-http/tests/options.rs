@@ -0,0 +1,107 @@
+use serde_json::json;
+use wasm_http::options::{JsHttpOptions, JsHttpRequest};
+use wasm_http::request::mode::Mode;
+use wasm_http::request::redirect::Redirect;
+use wasm_http::request::referrer_policy::ReferrerPolicy;
+
+#[test]
+fn test_options() {
+    let options: JsHttpOptions = serde_json::from_value(json!({
+        "url": "https://example.org/api",
+        "method": "post",
+        "headers": {"Accept": "application/json", "X-Remove": null},
+        "timeout": "10",
+        "type": 0,
+        "responseType": "3",
+        "compressRequest": "gzip",
+        "transformResponseKeys": "camel",
+        "idempotencyKey": true,
+        "verifyDigest": "sha-256=:LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=:",
+        "data": {"ignored": true}
+    }))
+    .unwrap();
+
+    let mut errors = Vec::new();
+    let options = options.into_options(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(options.url, "https://example.org/api");
+    assert_eq!(options.timeout, Some(10));
+    assert!(options.idempotency_key.is_some());
+    assert!(options.verify_digest.is_some());
+    assert_eq!(options.headers, Some(json!({"Accept": "application/json", "X-Remove": null})));
+}
+
+#[test]
+fn test_errors() {
+    let options: JsHttpOptions = serde_json::from_value(json!({
+        "url": 1,
+        "headers": {"X-Id": 5},
+        "timeout": "soon",
+        "type": 9,
+        "compressRequest": "zip",
+        "transformSkip": "id",
+        "verifyDigest": "md5-abc"
+    }))
+    .unwrap();
+
+    // 收集所有字段的错误
+    let mut errors = Vec::new();
+    options.into_options(&mut errors);
+    assert_eq!(errors.len(), 7, "{:?}", errors);
+    assert!(errors[0].starts_with("`url`: invalid type"));
+    assert!(errors.iter().any(|err| err.starts_with("`headers.X-Id`")));
+    assert!(errors.iter().any(|err| err == "`timeout`: invalid number: \"soon\""));
+    assert!(errors.iter().any(|err| err == "`type`: invalid value: 9, expected 0-5"));
+    assert!(errors.iter().any(|err| err.starts_with("`compressRequest`: unknown variant `zip`")));
+    assert!(errors.iter().any(|err| err.starts_with("`transformSkip`: invalid type")));
+    assert!(errors.iter().any(|err| err == "`verifyDigest`: invalid digest: \"md5-abc\", expected sha-256 or sha-512"));
+
+    // 不是整数
+    let options: JsHttpOptions = serde_json::from_value(json!({"url": "https://example.org/api", "timeout": 1.5, "responseType": 2.5})).unwrap();
+    let mut errors = Vec::new();
+    options.into_options(&mut errors);
+    assert_eq!(errors, vec!["`timeout`: invalid number: 1.5", "`responseType`: invalid value: 2.5, expected 0-5"]);
+
+    let request: JsHttpRequest = serde_json::from_value(json!({"mode": "corz", "cache": "no-store", "referrerPolicy": "none", "credentials": 1})).unwrap();
+    let mut errors = Vec::new();
+    let request = request.into_request(&mut errors);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].starts_with("`credentials`: invalid type: integer `1`"));
+    assert!(errors[1].starts_with("`mode`: unknown variant `corz`, expected one of `same-origin`, `cors`, `no-cors`, `navigate`"));
+    assert!(request.cache.is_some() && request.referrer_policy.is_some());
+
+    // 枚举值忽略大小写及首尾空白, 空字符串视为未设置
+    let request: JsHttpRequest = serde_json::from_value(json!({"mode": " CORS ", "cache": "", "redirect": "Manual", "referrerPolicy": ""})).unwrap();
+    let mut errors = Vec::new();
+    let request = request.into_request(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(matches!(request.mode, Some(Mode::Cors)));
+    assert!(request.cache.is_none());
+    assert!(matches!(request.redirect, Some(Redirect::Manual)));
+    assert!(matches!(request.referrer_policy, Some(ReferrerPolicy::None)));
+
+    // `compressThreshold` 与 `timeout` 一样只接受整数
+    let options: JsHttpOptions = serde_json::from_value(json!({"url": "https://example.org/api", "compressRequest": "GZIP", "compressThreshold": "1024"})).unwrap();
+    let mut errors = Vec::new();
+    let options = options.into_options(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(options.compress_threshold, Some(1024));
+    for (threshold, err) in [(json!(10.5), "10.5"), (json!(-1), "-1"), (json!("big"), "\"big\"")] {
+        let options: JsHttpOptions = serde_json::from_value(json!({"url": "https://example.org/api", "compressThreshold": threshold})).unwrap();
+        let mut errors = Vec::new();
+        options.into_options(&mut errors);
+        assert_eq!(errors, vec![format!("`compressThreshold`: invalid number: {}", err)]);
+    }
+
+    let request: JsHttpRequest = serde_json::from_value(json!({"keepalive": true, "priority": "high"})).unwrap();
+    let mut errors = Vec::new();
+    let request = request.into_request(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(request.keepalive, Some(true));
+    assert_eq!(request.priority.map(|priority| priority.as_str()), Some("high"));
+
+    let request: JsHttpRequest = serde_json::from_value(json!({"priority": "urgent"})).unwrap();
+    let mut errors = Vec::new();
+    request.into_request(&mut errors);
+    assert!(errors[0].starts_with("`priority`: unknown variant `urgent`"));
+}
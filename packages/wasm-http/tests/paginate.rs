@@ -0,0 +1,45 @@
+//! `paginate` 返回的异步迭代器, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test paginate`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Object, Promise, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    const pages = { 1: [1, 2], 2: [3, 4], 3: [5] };
+    globalThis.fetch = async (request) => {
+        const page = new URL(request.url).searchParams.get("page");
+        await new Promise((resolve) => setTimeout(resolve, 1));
+        return new Response(JSON.stringify({ items: pages[page] ?? [] }), { headers: { "Content-Type": "application/json" } });
+    };
+}
+// 不等待上一次的结果, 同时调用多次 `next()`
+export async function concurrent(iterator, count) {
+    const results = await Promise.all(Array.from({ length: count }, () => iterator.next()));
+    return results.map((result) => {
+        if (result.done) {
+            return "done";
+        }
+        const body = result.value.body instanceof Map ? Object.fromEntries(result.value.body) : result.value.body;
+        return JSON.stringify(body.items);
+    });
+}
+"#)]
+extern "C" {
+    fn install();
+    fn concurrent(iterator: &Object, count: u32) -> Promise;
+}
+
+#[wasm_bindgen_test]
+async fn test_concurrent_next() {
+    install();
+    let opts = JSON::parse(r#"{"url": "http://localhost/items", "method": "get", "pagination": {"type": "page", "pageSize": 2, "itemsPath": "items"}}"#).unwrap();
+    let iterator = wasm_http::paginate(opts, JsValue::UNDEFINED).unwrap();
+
+    let results = JsFuture::from(concurrent(&iterator, 5)).await.unwrap();
+    let results: Vec<String> = serde_wasm_bindgen::from_value(results).unwrap();
+    assert_eq!(results, vec!["[1,2]", "[3,4]", "[5]", "done", "done"]);
+}
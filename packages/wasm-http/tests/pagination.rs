@@ -0,0 +1,73 @@
+use http::header::{HeaderMap, HeaderValue, LINK};
+use serde_json::json;
+use wasm_http::pagination::{get_path, get_query_param, link, set_query_param, Pagination};
+use wasm_http::HttpResponseOptions;
+
+#[test]
+fn test_parse_link() {
+    let links = link::parse(r#"<https://api.example.com/items?page=2>; rel="next", <https://api.example.com/items?page=9>; rel="last"; title="a;b", </items?page=1>; REL="prev first""#);
+    assert_eq!(links.len(), 3);
+    assert_eq!(link::find(&links, "next").unwrap().uri, "https://api.example.com/items?page=2");
+    assert_eq!(link::find(&links, "last").unwrap().params.get("title").unwrap(), "a;b");
+    assert_eq!(link::find(&links, "first").unwrap().uri, "/items?page=1");
+    assert!(link::find(&links, "self").is_none());
+}
+
+#[test]
+fn test_query_param() {
+    assert_eq!(set_query_param("https://a.com/items", "page", "2"), "https://a.com/items?page=2");
+    assert_eq!(set_query_param("https://a.com/items?page=1&q=x#top", "page", "2"), "https://a.com/items?page=2&q=x#top");
+    assert_eq!(set_query_param("https://a.com/items?q=x", "cursor", "a b/c"), "https://a.com/items?q=x&cursor=a%20b%2Fc");
+    assert_eq!(get_query_param("https://a.com/items?q=x&page=3", "page").as_deref(), Some("3"));
+    assert_eq!(get_query_param("https://a.com/items?q=x", "page"), None);
+
+    let body = json!({"meta": {"next": "abc"}, "data": [{"id": 1}]});
+    assert_eq!(get_path(&body, "meta.next"), Some(&json!("abc")));
+    assert_eq!(get_path(&body, "/data/0/id"), Some(&json!(1)));
+    assert_eq!(get_path(&body, "data.0.id"), Some(&json!(1)));
+    assert_eq!(get_path(&body, "meta.prev"), None);
+}
+
+fn response(url: &str, body: serde_json::Value, headers: HeaderMap) -> HttpResponseOptions {
+    HttpResponseOptions {
+        url: url.to_string(),
+        body,
+        headers,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_next_url() {
+    // link, 相对地址基于响应的地址解析
+    let mut headers = HeaderMap::new();
+    headers.insert(LINK, HeaderValue::from_static(r#"</items?page=3>; rel="next""#));
+    let page = response("https://a.com/items?page=2", json!([]), headers);
+    assert_eq!(Pagination::Link.next_url("https://a.com/items?page=2", &page).as_deref(), Some("https://a.com/items?page=3"));
+    assert_eq!(Pagination::Link.next_url("https://a.com/items", &response("", json!([]), HeaderMap::new())), None);
+
+    // cursor
+    let cursor = Pagination::Cursor {
+        path: String::from("meta.next_cursor"),
+        param: String::from("after"),
+    };
+    let page = response("", json!({"meta": {"next_cursor": "c2"}}), HeaderMap::new());
+    assert_eq!(cursor.next_url("https://a.com/items", &page).as_deref(), Some("https://a.com/items?after=c2"));
+    assert_eq!(cursor.next_url("https://a.com/items?after=c2", &page), None);
+    assert_eq!(cursor.next_url("https://a.com/items", &response("", json!({"meta": {"next_cursor": null}}), HeaderMap::new())), None);
+
+    // page
+    let pages = Pagination::Page {
+        param: String::from("page"),
+        size_param: String::from("size"),
+        start: 1,
+        size: 2,
+        items_path: Some(String::from("data")),
+    };
+    let url = pages.first_url("https://a.com/items");
+    assert_eq!(url, "https://a.com/items?page=1&size=2");
+    let full = response("", json!({"data": [1, 2]}), HeaderMap::new());
+    assert_eq!(pages.next_url(&url, &full).as_deref(), Some("https://a.com/items?page=2&size=2"));
+    let last = response("", json!({"data": [3]}), HeaderMap::new());
+    assert_eq!(pages.next_url(&url, &last), None);
+}
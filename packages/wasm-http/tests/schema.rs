@@ -0,0 +1,82 @@
+use serde::Deserialize;
+use serde_json::json;
+use wasm_http::schema::{Schema, Violation};
+
+fn violations(schema: Schema, body: serde_json::Value) -> Vec<(String, String)> {
+    match schema.validate(&body) {
+        Ok(_) => Vec::new(),
+        Err(wasm_http::Error::Validation(violations)) => violations.into_iter().map(|Violation { path, keyword, .. }| (path, keyword)).collect(),
+        Err(err) => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = Schema::Json(json!({
+        "type": "object",
+        "required": ["code", "data"],
+        "properties": {
+            "code": {"type": "integer", "enum": [0, 1]},
+            "data": {"type": "array", "items": {"$ref": "#/$defs/user"}}
+        },
+        "$defs": {
+            "user": {
+                "type": "object",
+                "required": ["id"],
+                "additionalProperties": false,
+                "properties": {
+                    "id": {"type": "integer", "minimum": 1},
+                    "name": {"type": "string", "minLength": 1, "pattern": "^[a-z]+$"},
+                    "a/b": {"type": ["string", "null"]}
+                }
+            }
+        }
+    }));
+
+    let body = json!({"code": 0, "data": [{"id": 1, "name": "abc", "a/b": null}]});
+    assert!(violations(schema.clone(), body).is_empty());
+
+    let body = json!({"code": 2, "data": [{"id": 0, "name": "ABC", "age": 1, "a/b": 1}, {}]});
+    let mut result = violations(schema, body);
+    result.sort();
+    let expected = [
+        ("/code", "enum"),
+        ("/data/0/age", "additionalProperties"),
+        ("/data/0/a~1b", "type"),
+        ("/data/0/id", "minimum"),
+        ("/data/0/name", "pattern"),
+        ("/data/1", "required"),
+    ];
+    assert_eq!(result, expected.iter().map(|(path, keyword)| (path.to_string(), keyword.to_string())).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_combination_schema() {
+    let schema = Schema::Json(json!({"oneOf": [{"type": "string"}, {"type": "number", "multipleOf": 0.5}], "not": {"const": 1.5}}));
+    assert!(violations(schema.clone(), json!("a")).is_empty());
+    assert!(violations(schema.clone(), json!(2.5)).is_empty());
+    assert_eq!(violations(schema.clone(), json!(1.5)), vec![("".to_string(), "not".to_string())]);
+    assert_eq!(violations(schema, json!(true)), vec![("".to_string(), "oneOf".to_string())]);
+}
+
+#[test]
+fn test_type_schema() {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        id: u64,
+        name: String,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Response {
+        code: i32,
+        data: Vec<User>,
+    }
+
+    let schema = Schema::of::<Response>();
+    assert!(violations(schema.clone(), json!({"code": 0, "data": [{"id": 1, "name": "a"}]})).is_empty());
+    assert_eq!(violations(schema.clone(), json!({"code": 0, "data": [{"id": "1", "name": "a"}]})), vec![("/data/0/id".to_string(), "type".to_string())]);
+    assert_eq!(violations(schema, json!({"code": 0, "data": [{"id": 1}]})), vec![("/data/0".to_string(), "required".to_string())]);
+}
@@ -0,0 +1,39 @@
+//! `Rust` 中发送请求, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test send_request`
+
+#![cfg(target_arch = "wasm32")]
+
+use bytes::Bytes;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use wasm_http::{send_request, stream, HttpRequest, HttpRequestOptions};
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.fetch = async (request) => {
+        const body = await request.text();
+        return new Response(JSON.stringify({ method: request.method, type: request.headers.get("content-type"), body }), { headers: { "Content-Type": "application/json" } });
+    };
+}
+"#)]
+extern "C" {
+    fn install();
+}
+
+#[wasm_bindgen_test]
+async fn test_upload_stream() {
+    install();
+
+    // 边生成边上传的 `CSV`
+    let chunks = vec![Bytes::from("id,name\n"), Bytes::from("1,a\n"), Bytes::from("2,b\n")];
+    let body = stream::from_stream(futures::stream::iter(chunks)).unwrap();
+    let mut options = HttpRequestOptions::default();
+    options.url = String::from("http://localhost/export");
+    options.method = Some(String::from("post"));
+    options.data = Some(body.into());
+    options.headers = Some(json!({"Content-Type": "text/csv"}));
+
+    let response = send_request(options, HttpRequest::new()).await.unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.body, json!({"method": "POST", "type": "text/csv", "body": "id,name\n1,a\n2,b\n"}));
+}
@@ -0,0 +1,23 @@
+//! 流式请求 `body`, 需要在浏览器中运行: `wasm-pack test --headless --chrome -- --test stream`
+
+#![cfg(target_arch = "wasm32")]
+
+use bytes::Bytes;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::stream;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn test_from_stream() {
+    let chunks = vec![Bytes::from("id,name\n"), Bytes::new(), Bytes::from("1,a\n"), Bytes::from("2,b\n")];
+    let body = stream::from_stream(futures::stream::iter(chunks)).unwrap();
+    assert!(stream::is_stream(&JsValue::from(body.clone())));
+
+    // 使用 `Response` 读取流的全部内容
+    let response = web_sys::Response::new_with_opt_readable_stream(Some(&body)).unwrap();
+    let text = JsFuture::from(response.text().unwrap()).await.unwrap();
+    assert_eq!(text.as_string().unwrap(), "id,name\n1,a\n2,b\n");
+}
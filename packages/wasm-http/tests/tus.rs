@@ -0,0 +1,31 @@
+use http::header::{HeaderMap, HeaderValue};
+use std::collections::BTreeMap;
+use wasm_http::tus::{concat_header, encode_metadata, get_offset, split_ranges};
+
+#[test]
+fn test_encode_metadata() {
+    let mut metadata = BTreeMap::new();
+    metadata.insert(String::from("filename"), String::from("world_domination_plan.pdf"));
+    metadata.insert(String::from("is_confidential"), String::new());
+    assert_eq!(encode_metadata(&metadata), "filename d29ybGRfZG9taW5hdGlvbl9wbGFuLnBkZg==,is_confidential");
+    assert_eq!(encode_metadata(&BTreeMap::new()), "");
+}
+
+#[test]
+fn test_split_ranges() {
+    assert_eq!(split_ranges(10, 3), vec![(0, 3), (3, 6), (6, 10)]);
+    assert_eq!(split_ranges(10, 1), vec![(0, 10)]);
+    assert_eq!(split_ranges(2, 4), vec![(0, 1), (1, 2)]);
+    assert_eq!(split_ranges(0, 4), vec![(0, 0)]);
+}
+
+#[test]
+fn test_concat_and_offset() {
+    let urls = vec![String::from("https://tus.example.org/files/a"), String::from("/files/b")];
+    assert_eq!(concat_header(&urls), "final;https://tus.example.org/files/a /files/b");
+
+    let mut headers = HeaderMap::new();
+    assert_eq!(get_offset(&headers), None);
+    headers.insert("Upload-Offset", HeaderValue::from_static("70"));
+    assert_eq!(get_offset(&headers), Some(70));
+}
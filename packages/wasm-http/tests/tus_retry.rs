@@ -0,0 +1,86 @@
+//! `tus` 上传的重试, 使用模拟的 `fetch`, 可以在 `node` 中运行: `wasm-pack test --node -- --test tus_retry`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::tus::TusUpload;
+use web_sys::Blob;
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    const server = { offset: 0, failed: false, requests: [], encodings: [] };
+    globalThis.__server = server;
+    globalThis.fetch = async (request) => {
+        const key = request.headers.get("Idempotency-Key");
+        server.encodings.push(request.headers.get("Content-Encoding"));
+        server.requests.push([request.method, request.headers.get("Upload-Offset"), key].filter((value) => value !== null).join(" "));
+        const headers = { "Tus-Resumable": "1.0.0", "Upload-Offset": String(server.offset) };
+        switch (request.method) {
+            case "POST":
+                return new Response(null, { status: 201, headers: { Location: "http://localhost/files/1" } });
+            case "HEAD":
+                return new Response(null, { status: 200, headers });
+            case "PATCH": {
+                const body = await request.arrayBuffer();
+                // 第一块第一次上传时断开连接
+                if (!server.failed) {
+                    server.failed = true;
+                    throw new TypeError("Failed to fetch");
+                }
+                server.offset += body.byteLength;
+                return new Response(null, { status: 204, headers: { ...headers, "Upload-Offset": String(server.offset) } });
+            }
+        }
+        return new Response(null, { status: 405 });
+    };
+}
+export function requests() { return globalThis.__server.requests; }
+export function encodings() { return globalThis.__server.encodings; }
+"#)]
+extern "C" {
+    fn install();
+    fn requests() -> Array;
+    fn encodings() -> Array;
+}
+
+fn upload(options: &str) -> TusUpload {
+    let file = Blob::new_with_str_sequence(&Array::of1(&JsValue::from_str("0123456789"))).unwrap();
+    let options = JSON::parse(&format!(r#"{{"endpoint": "http://localhost/files", "chunkSize": 4, "retryDelays": [0], "storeUrl": false, {}}}"#, options)).unwrap();
+    TusUpload::new(file, options).unwrap()
+}
+
+fn recorded() -> Vec<String> {
+    requests().iter().filter_map(|request| request.as_string()).collect()
+}
+
+#[wasm_bindgen_test]
+async fn test_retry_reuses_key() {
+    install();
+    JsFuture::from(upload(r#""idempotencyKey": "upload-1""#).start_js()).await.unwrap();
+
+    // 创建与每一块使用不同的键, 重试同一块时沿用
+    assert_eq!(recorded(), vec!["POST upload-1-create", "PATCH 0 upload-1-0-0", "HEAD", "PATCH 0 upload-1-0-0", "PATCH 4 upload-1-0-4", "PATCH 8 upload-1-0-8",]);
+
+    install();
+    JsFuture::from(upload(r#""idempotencyKey": true"#).start_js()).await.unwrap();
+
+    let keys: Vec<String> = recorded().iter().filter(|request| request.starts_with("PATCH")).map(|request| request.split(' ').nth(2).unwrap_or_default().to_string()).collect();
+    assert_eq!(keys.len(), 4);
+    assert_eq!(keys[0], keys[1]);
+    assert_ne!(keys[1], keys[2]);
+    assert_ne!(keys[2], keys[3]);
+}
+
+#[wasm_bindgen_test]
+async fn test_ignored_options() {
+    install();
+    // 不压缩分块, 不校验摘要, 否则服务端的 `Upload-Offset` 与文件不一致或上传失败
+    let options = r#""compressRequest": "gzip", "compressThreshold": 0, "transformRequestKeys": "snake", "verifyDigest": true"#;
+    JsFuture::from(upload(options).start_js()).await.unwrap();
+
+    assert!(encodings().iter().all(|encoding| encoding.is_null()));
+    assert_eq!(recorded().last().map(String::as_str), Some("PATCH 8"));
+}
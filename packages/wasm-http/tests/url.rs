@@ -0,0 +1,65 @@
+use wasm_http::instance::url::resolve;
+
+#[test]
+fn test_resolve_normal() {
+    // https://www.rfc-editor.org/rfc/rfc3986#section-5.4.1
+    let base = "http://a/b/c/d;p?q";
+    let examples = [
+        ("g:h", "g:h"),
+        ("g", "http://a/b/c/g"),
+        ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"),
+        ("/g", "http://a/g"),
+        ("//g", "http://g"),
+        ("?y", "http://a/b/c/d;p?y"),
+        ("g?y", "http://a/b/c/g?y"),
+        ("#s", "http://a/b/c/d;p?q#s"),
+        ("g#s", "http://a/b/c/g#s"),
+        ("g?y#s", "http://a/b/c/g?y#s"),
+        (";x", "http://a/b/c/;x"),
+        ("g;x", "http://a/b/c/g;x"),
+        ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"),
+        ("./", "http://a/b/c/"),
+        ("..", "http://a/b/"),
+        ("../", "http://a/b/"),
+        ("../g", "http://a/b/g"),
+        ("../..", "http://a/"),
+        ("../../g", "http://a/g"),
+    ];
+
+    for (reference, expected) in examples {
+        assert_eq!(resolve(base, reference), expected, "reference: {}", reference);
+    }
+}
+
+#[test]
+fn test_resolve_abnormal() {
+    // https://www.rfc-editor.org/rfc/rfc3986#section-5.4.2
+    let base = "http://a/b/c/d;p?q";
+    let examples = [
+        ("../../../g", "http://a/g"),
+        ("/./g", "http://a/g"),
+        ("/../g", "http://a/g"),
+        ("g.", "http://a/b/c/g."),
+        ("..g", "http://a/b/c/..g"),
+        ("./../g", "http://a/b/g"),
+        ("g/./h", "http://a/b/c/g/h"),
+        ("g/../h", "http://a/b/c/h"),
+        ("g?y/./x", "http://a/b/c/g?y/./x"),
+        ("g#s/../x", "http://a/b/c/g#s/../x"),
+    ];
+
+    for (reference, expected) in examples {
+        assert_eq!(resolve(base, reference), expected, "reference: {}", reference);
+    }
+}
+
+#[test]
+fn test_resolve_base_url() {
+    assert_eq!(resolve("https://example.com/api/", "users/1"), "https://example.com/api/users/1");
+    assert_eq!(resolve("https://example.com/api", "users/1"), "https://example.com/users/1");
+    assert_eq!(resolve("https://example.com", "users"), "https://example.com/users");
+    assert_eq!(resolve("/api/v1/", "users"), "/api/v1/users");
+    assert_eq!(resolve("", "https://example.com/users"), "https://example.com/users");
+}
@@ -0,0 +1,19 @@
+use wasm_http::websocket::ReconnectOptions;
+
+#[test]
+fn test_reconnect_delay() {
+    let options = ReconnectOptions { jitter: 0.0, ..ReconnectOptions::default() };
+
+    assert_eq!(options.delay(1, 0.5), 1000);
+    assert_eq!(options.delay(2, 0.5), 2000);
+    assert_eq!(options.delay(3, 0.5), 4000);
+    assert_eq!(options.delay(5, 0.5), 16000);
+    assert_eq!(options.delay(6, 0.5), 30000);
+    assert_eq!(options.delay(100, 0.5), 30000);
+
+    // 抖动范围为 `±jitter`
+    let options = ReconnectOptions::default();
+    assert_eq!(options.delay(1, 0.0), 800);
+    assert_eq!(options.delay(1, 0.5), 1000);
+    assert_eq!(options.delay(1, 1.0), 1200);
+}
@@ -0,0 +1,142 @@
+//! `WebSocketClient` 的行为测试, 使用模拟的 `WebSocket`, 可以在 `node` 中运行: `wasm-pack test --node -- --test websocket_client`
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, Function, Object, Promise, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use wasm_http::websocket::{HeartbeatOptions, ReconnectOptions, WebSocketClient, WebSocketOptions};
+
+#[wasm_bindgen(inline_js = r#"
+export function install() {
+    globalThis.__sockets = [];
+    globalThis.WebSocket = class {
+        constructor(url) {
+            globalThis.__attempts = (globalThis.__attempts ?? 0) + 1;
+            if (!url.startsWith("ws")) {
+                throw new SyntaxError(`invalid url: ${url}`);
+            }
+            this.url = url;
+            this.readyState = 0;
+            this.sent = [];
+            this.closed = null;
+            globalThis.__sockets.push(this);
+        }
+        send(data) { this.sent.push(data); }
+        // 不触发 `onclose`, 模拟关闭握手迟迟没有完成的断开连接
+        close(code, reason) { this.closed = { code, reason }; this.readyState = 2; }
+    };
+}
+export function open(index) { const socket = globalThis.__sockets[index]; socket.readyState = 1; socket.onopen({}); }
+export function receive(index, data) { globalThis.__sockets[index].onmessage({ data }); }
+export async function first(iterable) { for await (const message of iterable) { return message; } }
+export function count() { return globalThis.__sockets.length; }
+export function closed(index) { return globalThis.__sockets[index].closed?.code; }
+export function attached(index) { return typeof globalThis.__sockets[index].onclose === "function"; }
+export function sent(index) { return globalThis.__sockets[index].sent; }
+export function attempts() { return globalThis.__attempts; }
+export function sleep(ms) { return new Promise((resolve) => setTimeout(resolve, ms)); }
+"#)]
+extern "C" {
+    fn install();
+    fn open(index: u32);
+    fn receive(index: u32, data: &str);
+    fn first(iterable: &Object) -> Promise;
+    fn count() -> u32;
+    fn closed(index: u32) -> Option<u16>;
+    fn attached(index: u32) -> bool;
+    fn sent(index: u32) -> Array;
+    fn attempts() -> u32;
+    fn sleep(ms: u32) -> Promise;
+}
+
+#[wasm_bindgen_test]
+async fn test_messages_break() {
+    install();
+    let client = WebSocketClient::connect_with("ws://localhost/socket", WebSocketOptions::default());
+    open(0);
+
+    // `for await` 中 `return` 会调用迭代器的 `return()`
+    let promise = first(&client.messages().unwrap());
+    assert_eq!(client.subscribers(), 1);
+    receive(0, r#"{"id":1}"#);
+
+    let message = JsFuture::from(promise).await.unwrap();
+    assert_eq!(Reflect::get(&message, &JsValue::from_str("id")).unwrap().as_f64(), Some(1.0));
+    assert_eq!(client.subscribers(), 0);
+
+    // 直接调用 `return()`
+    let iterator = client.messages().unwrap();
+    let finish: Function = Reflect::get(&iterator, &JsValue::from_str("return")).unwrap().into();
+    let result = JsFuture::from(Promise::from(finish.call0(&iterator).unwrap())).await.unwrap();
+    assert_eq!(Reflect::get(&result, &JsValue::from_str("done")).unwrap().as_bool(), Some(true));
+    assert_eq!(client.subscribers(), 0);
+    receive(0, "after");
+
+    client.close(None, None);
+}
+
+#[wasm_bindgen_test]
+async fn test_heartbeat_timeout() {
+    install();
+    let options = WebSocketOptions {
+        reconnect: Some(ReconnectOptions {
+            initial_delay: 10,
+            jitter: 0.0,
+            ..Default::default()
+        }),
+        heartbeat: Some(HeartbeatOptions {
+            interval: 10,
+            timeout: 10,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = WebSocketClient::connect_with("ws://localhost/socket", options);
+    open(0);
+
+    // 没有回复 `pong`, 旧连接的 `onclose` 不会触发, 超时后直接重连
+    JsFuture::from(sleep(60)).await.unwrap();
+    assert_eq!(closed(0), Some(4000));
+    assert!(!attached(0));
+    assert_eq!(count(), 2);
+    assert_eq!(client.state(), "reconnecting");
+
+    // 重连期间缓存, 连接后发送
+    client.send(JsValue::from_str("hello")).unwrap();
+    assert_eq!(client.buffered(), 1);
+    open(1);
+    assert_eq!(client.state(), "open");
+    assert_eq!(client.buffered(), 0);
+    assert!(sent(1).includes(&JsValue::from_str("hello"), 0));
+    assert!(!sent(0).includes(&JsValue::from_str("hello"), 0));
+
+    client.close(None, None);
+}
+
+#[wasm_bindgen_test]
+async fn test_invalid_url() {
+    install();
+    let options = WebSocketOptions {
+        reconnect: Some(ReconnectOptions {
+            initial_delay: 1,
+            jitter: 0.0,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    // 创建 `WebSocket` 失败时不再重连
+    let before = attempts();
+    let client = WebSocketClient::connect_with("http://localhost/socket", options);
+    assert_eq!(attempts(), before + 1);
+    assert_eq!(client.state(), "closed");
+
+    JsFuture::from(sleep(20)).await.unwrap();
+    assert_eq!(attempts(), before + 1);
+
+    // `js` 中直接抛出
+    assert!(WebSocketClient::new(String::from("http://localhost/socket"), JsValue::UNDEFINED).is_err());
+    assert_eq!(attempts(), before + 2);
+}